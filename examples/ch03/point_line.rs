@@ -1,14 +1,21 @@
-#[path = "./common.rs"]
-mod common;
 use std::borrow::Cow;
-use winit::{event_loop::EventLoop, window::WindowAttributes};
+use wgpu_step_by_step::app::{
+    self,
+    primitive::{self, Inputs, PrimitiveScene},
+    App,
+};
+use wgpu_step_by_step::InitWgpu;
+use winit::dpi::PhysicalSize;
 
-fn main() {
-    let mut primitive_type = "point-list";
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 {
-        primitive_type = &args[1];
-    }
+const NUM_VERTICES: u32 = 6;
+
+fn primitive_type() -> String {
+    std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "point-list".to_string())
+}
+
+fn inputs(primitive_type: &str) -> Inputs<'static> {
     let mut topology = wgpu::PrimitiveTopology::PointList;
     let mut index_format = None;
     if primitive_type == "line-list" {
@@ -18,17 +25,42 @@ fn main() {
         topology = wgpu::PrimitiveTopology::LineStrip;
         index_format = Some(wgpu::IndexFormat::Uint32);
     }
-    let inputs = common::Inputs {
+    Inputs {
         source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!("point_line.wgsl"))),
-        topology: topology,
-
+        topology,
         strip_index_format: index_format,
-    };
-    let event_loop = EventLoop::new().unwrap();
-    let window: winit::window::Window = event_loop
-        .create_window(WindowAttributes::default())
-        .unwrap();
-    window.set_title(&*format!("{}: {}", "Primitive", primitive_type));
+    }
+}
+
+struct State(PrimitiveScene);
+
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        State(PrimitiveScene::new(
+            init,
+            inputs(&primitive_type()),
+            NUM_VERTICES,
+        ))
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.0.resize(new_size);
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        self.0.render()
+    }
+}
+
+fn main() {
     env_logger::init();
-    pollster::block_on(common::run(event_loop, &window, inputs, 6));
+    let primitive_type = primitive_type();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        primitive::capture(inputs(&primitive_type), NUM_VERTICES, &path).unwrap();
+        return;
+    }
+
+    app::run::<State>(&format!("{}: {}", "Primitive", primitive_type));
 }
@@ -1,12 +1,11 @@
 use bytemuck::{cast_slice, Pod, Zeroable};
 use std::{iter, mem};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes},
+use wgpu_step_by_step::{
+    app::{self, App},
+    capture, InitWgpu,
 };
+use winit::dpi::PhysicalSize;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -87,51 +86,16 @@ impl Vertex {
     }
 }
 
-struct State<'a> {
-    surface: wgpu::Surface<'a>,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    config: wgpu::SurfaceConfiguration,
-    size: winit::dpi::PhysicalSize<u32>,
+struct State {
+    init: InitWgpu<'static>,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
 }
 
-impl<'a> State<'a> {
-    async fn new(window: &'a Window) -> Self {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::default();
-        let surface = instance.create_surface(window).unwrap();
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: Some(&surface),
-                force_fallback_adapter: false,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(
-                &wgpu::DeviceDescriptor {
-                    label: None,
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::default(),
-                    memory_hints: wgpu::MemoryHints::MemoryUsage,
-                },
-                None,
-            ) // Trace path
-            .await
-            .unwrap();
-
-        let mut config = surface
-            .get_default_config(&adapter, size.width, size.height)
-            .unwrap();
-
-        config.present_mode = wgpu::PresentMode::Fifo;
-        config.usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
-
-        surface.configure(&device, &config);
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let device = &init.device;
+        let config = &init.config;
 
         let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("Shader"),
@@ -184,43 +148,28 @@ impl<'a> State<'a> {
         });
 
         Self {
-            surface,
-            device,
-            queue,
-            config,
-            size,
+            init,
             pipeline,
             vertex_buffer,
         }
     }
 
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
-        if new_size.width > 0 && new_size.height > 0 {
-            self.size = new_size;
-            self.config.width = new_size.width;
-            self.config.height = new_size.height;
-            self.surface.configure(&self.device, &self.config);
-        }
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.init.resize(new_size);
     }
 
-    #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
-        false
-    }
-
-    fn update(&mut self) {}
-
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.surface.get_current_texture()?;
+        let output = self.init.get_current_frame()?;
         let view = output
-            .texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
 
         {
             let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
@@ -248,66 +197,33 @@ impl<'a> State<'a> {
             render_pass.draw(0..6, 0..1);
         }
 
-        self.queue.submit(iter::once(encoder.finish()));
+        self.init.queue.submit(iter::once(encoder.finish()));
         output.present();
         Ok(())
     }
 }
 
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
 fn main() {
     env_logger::init();
-    let event_loop = EventLoop::new().unwrap();
-    let window: winit::window::Window = event_loop
-        .create_window(WindowAttributes::default())
-        .unwrap();
-    window.set_title(&*format!("{}", "ch04-triangle"));
 
-    let mut state = pollster::block_on(State::new(&window));
-    let current_window_id: winit::window::WindowId = window.id();
-    event_loop
-        .run(move |event, target| {
-            if let Event::WindowEvent {
-                ref event,
-                window_id,
-            } = event
-            {
-                if window_id == current_window_id {
-                    if !state.input(event) {
-                        match event {
-                            WindowEvent::CloseRequested
-                            | WindowEvent::KeyboardInput {
-                                event:
-                                    KeyEvent {
-                                        state: ElementState::Pressed,
-                                        physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                        ..
-                                    },
-                                ..
-                            } => target.exit(),
-                            WindowEvent::Resized(new_size) => {
-                                state.resize(*new_size);
-                                state.render()
-                            }
-                            // WindowEvent::ScaleFactorChanged {
-                            //         inner_size_writer: InnerSizeWriter{
-                            //             new_inner_size
-                            //         }, .. } => {
-                            //     state.resize(new_inner_size);
-                            // }
-                            WindowEvent::RedrawRequested => {
-                                state.update();
-                                match state.render() {
-                                    Ok(_) => {}
-                                    Err(wgpu::SurfaceError::Lost) => state.resize(state.size),
-                                    Err(wgpu::SurfaceError::OutOfMemory) => target.exit(),
-                                    Err(e) => eprintln!("{:?}", e),
-                                }
-                            }
-                            _ => {}
-                        }
-                    }
-                };
-            }
-        })
-        .unwrap();
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+    app::run::<State>("ch04-triangle");
 }
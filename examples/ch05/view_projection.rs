@@ -39,7 +39,6 @@ fn main() {
     println!("perspective matrix: {:?}\n ", persp_mat);
 
     // construct orthographic projection matrix
-    let mut ortho_mat = [0.0; 16];
     let ortho_mat = ortho(left, right, bottom, top, near, far);
     println!("orthographic matrix: {:?}\n ", ortho_mat);
 }
@@ -0,0 +1,322 @@
+use bytemuck::{cast_slice, Pod, Zeroable};
+use cgmath::*;
+use std::{iter, mem};
+use wgpu::util::DeviceExt;
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::FlyCamera,
+    capture,
+    depth::DepthTarget,
+    geometry::vertex_data,
+    msaa::MsaaTarget,
+    transforms::{self, Projection},
+    InitWgpu,
+};
+use winit::{
+    dpi::PhysicalSize,
+    event::{DeviceEvent, WindowEvent},
+};
+
+const SAMPLE_COUNT: u32 = 4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, Pod, Zeroable)]
+struct Vertex {
+    position: [f32; 4],
+    color: [f32; 4],
+}
+fn vertex(p: [i8; 3], c: [i8; 3]) -> Vertex {
+    Vertex {
+        position: [p[0] as f32, p[1] as f32, p[2] as f32, 1.0],
+        color: [c[0] as f32, c[1] as f32, c[2] as f32, 1.0],
+    }
+}
+fn create_vertices() -> Vec<Vertex> {
+    let (pos, col, _uv, _normal) = vertex_data::cube_data();
+
+    let mut data: Vec<Vertex> = Vec::with_capacity(pos.len());
+
+    for i in 0..pos.len() {
+        data.push(vertex(pos[i], col[i]));
+    }
+    data.to_vec()
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
+        wgpu::vertex_attr_array![0=>Float32x4, 1=>Float32x4];
+
+    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    model_mat: Matrix4<f32>,
+    view_mat: Matrix4<f32>,
+    projection: Projection,
+    camera: FlyCamera,
+}
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let shader = init
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("cube_face_color.wgsl").into()),
+            });
+
+        // uniform data; click to look around, move with WASD and Q/E, Escape releases
+        // the cursor
+        let camera = FlyCamera::looking_at((3.0, 1.5, 3.0).into(), (0.0, 0.0, 0.0).into());
+
+        let model_mat =
+            transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+
+        let view_mat = camera.view_matrix();
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(72.0), aspect, 0.1, 100.0);
+
+        let mvp_mat = projection.matrix() * view_mat * model_mat;
+
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+
+        let uniform_buffer = init
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Uniform Buffer"),
+                contents: bytemuck::cast_slice(mvp_ref),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let uniform_bind_group_layout =
+            init.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("Uniform Bind Group Layout"),
+                });
+
+        let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Uniform Bind Group"),
+        });
+
+        let pipeline_layout = init
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(
+                SAMPLE_COUNT,
+                &[init.config.format, wgpu::TextureFormat::Depth24Plus],
+            )
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            wgpu::TextureFormat::Depth24Plus,
+            sample_count,
+        );
+
+        let pipeline = init
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                cache: None,
+                multiview: None,
+                label: Some("Render Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[Vertex::desc()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: init.config.format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::REPLACE,
+                            alpha: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+
+                //depth_stencil: None,
+                depth_stencil: Some(depth.depth_stencil_state()),
+                multisample: msaa.multisample_state(),
+            });
+
+        let vertex_buffer = init
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: cast_slice(&create_vertices()),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+        Self {
+            init,
+            msaa,
+            depth,
+            pipeline,
+            vertex_buffer,
+            uniform_buffer,
+            uniform_bind_group,
+            model_mat,
+            view_mat,
+            projection,
+            camera,
+        }
+    }
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.projection.resize(new_size.width, new_size.height);
+
+            let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
+
+            let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+            self.init
+                .queue
+                .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera.input(event)
+    }
+
+    fn device_input(&mut self, event: &DeviceEvent) {
+        self.camera.device_input(event);
+    }
+
+    fn cursor_grabbed(&self) -> bool {
+        self.camera.cursor_grabbed()
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.view_mat = self.camera.view_matrix();
+
+        let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+        self.init
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.init.get_current_frame()?;
+
+        let view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    },
+                ))],
+                //depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            render_pass.draw(0..36, 0..1);
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+fn main() {
+    env_logger::init();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch06-cube-fly");
+}
@@ -0,0 +1,372 @@
+use cgmath::*;
+use std::iter;
+use wgpu::util::DeviceExt;
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::OrbitCamera,
+    capture,
+    culling::Frustum,
+    depth::DepthTarget,
+    geometry::{vertex_data, BoundingSphere, GpuMesh, Indices, Mesh},
+    instance::{Instance, InstanceBuffer},
+    msaa::MsaaTarget,
+    transforms::Projection,
+    InitWgpu,
+};
+use winit::{
+    dpi::PhysicalSize,
+    event::{ElementState, KeyEvent, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+fn create_mesh() -> Mesh {
+    let (pos, col, ind) = vertex_data::cube_data_index();
+    Mesh {
+        positions: pos
+            .iter()
+            .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+            .collect(),
+        colors: Some(
+            col.iter()
+                .map(|c| [c[0] as f32, c[1] as f32, c[2] as f32, 1.0])
+                .collect(),
+        ),
+        indices: Some(Indices::U16(ind)),
+        ..Default::default()
+    }
+}
+
+const ANIMATION_SPEED: f32 = 1.0;
+const SAMPLE_COUNT: u32 = 4;
+// cubes sit on a GRID x GRID x GRID lattice, filled layer by layer from the bottom
+const GRID: usize = 20;
+const SPACING: f32 = 3.0;
+// +/- add or remove one layer
+const LAYER: usize = GRID * GRID;
+
+// the grid cell, spin and color of cube `i` at time `t`
+fn create_instance(i: usize, t: f32) -> Instance {
+    let cell = Vector3::new(i % GRID, i / LAYER, (i / GRID) % GRID)
+        .cast::<f32>()
+        .unwrap();
+    let offset = (GRID - 1) as f32 / 2.0;
+    let position = (cell - Vector3::new(offset, offset, offset)) * SPACING;
+
+    // neighbours spin about different axes and at different speeds
+    let phase = i as f32 * 0.618;
+    let axis = Vector3::new(phase.sin(), phase.cos(), 0.5).normalize();
+    let angle = Rad(t * (1.0 + (phase * 3.0).sin().abs()) + phase);
+    let model = Matrix4::from_translation(position)
+        * Matrix4::from_axis_angle(axis, angle)
+        * Matrix4::from_scale(0.6);
+
+    let color = cell / (GRID - 1) as f32;
+    Instance::new(model, [color.x, color.y, 1.0 - color.z, 1.0])
+}
+
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
+    pipeline: wgpu::RenderPipeline,
+    mesh: GpuMesh,
+    bounds: BoundingSphere,
+    instances: Vec<Instance>,
+    instance_buffer: InstanceBuffer,
+    count: usize,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    projection: Projection,
+    camera: OrbitCamera,
+    elapsed: std::time::Duration,
+}
+
+impl State {
+    fn write_uniforms(&self) {
+        let vp_mat = self.view_projection();
+        let vp_ref: &[f32; 16] = vp_mat.as_ref();
+        self.init
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(vp_ref));
+    }
+
+    fn view_projection(&self) -> Matrix4<f32> {
+        self.projection.matrix() * self.camera.view_matrix()
+    }
+}
+
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let mesh = create_mesh();
+        let mesh_layout = mesh.layout(0);
+        let instance_layout = Instance::layout(mesh_layout.attributes.len() as u32);
+
+        let shader = init
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("cube_instances.wgsl").into()),
+            });
+
+        // uniform data
+        let camera = OrbitCamera::new((55.0, 40.0, 55.0).into(), (0.0, 0.0, 0.0).into(), init.size);
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(60.0), aspect, 0.1, 300.0);
+        let vp_mat = projection.matrix() * camera.view_matrix();
+        let vp_ref: &[f32; 16] = vp_mat.as_ref();
+
+        let uniform_buffer = init
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Uniform Buffer"),
+                contents: bytemuck::cast_slice(vp_ref),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let uniform_bind_group_layout =
+            init.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("Uniform Bind Group Layout"),
+                });
+
+        let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Uniform Bind Group"),
+        });
+
+        let pipeline_layout = init
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(
+                SAMPLE_COUNT,
+                &[init.config.format, wgpu::TextureFormat::Depth24Plus],
+            )
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            wgpu::TextureFormat::Depth24Plus,
+            sample_count,
+        );
+
+        let pipeline = init
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                cache: None,
+                multiview: None,
+                label: Some("Render Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[mesh_layout.buffer_layout(), instance_layout.buffer_layout()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: init.config.format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::REPLACE,
+                            alpha: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: Some(depth.depth_stencil_state()),
+                multisample: msaa.multisample_state(),
+            });
+
+        let bounds = mesh.bounding_sphere().unwrap();
+        let mesh = mesh.upload(&init.device);
+
+        // start half full so the count can go both ways
+        let count = GRID * LAYER / 2;
+        let instances: Vec<Instance> = (0..count).map(|i| create_instance(i, 0.0)).collect();
+        let instance_buffer = InstanceBuffer::new(&init.device, &init.queue, &instances);
+
+        Self {
+            init,
+            msaa,
+            depth,
+            pipeline,
+            mesh,
+            bounds,
+            instances,
+            instance_buffer,
+            count,
+            uniform_buffer,
+            uniform_bind_group,
+            projection,
+            camera,
+            elapsed: std::time::Duration::ZERO,
+        }
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.camera.resize(new_size);
+            self.projection.resize(new_size.width, new_size.height);
+            self.write_uniforms();
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        if let WindowEvent::KeyboardInput {
+            event:
+                KeyEvent {
+                    physical_key: PhysicalKey::Code(code),
+                    state: ElementState::Pressed,
+                    ..
+                },
+            ..
+        } = event
+        {
+            match code {
+                KeyCode::Equal | KeyCode::NumpadAdd => {
+                    self.count = (self.count + LAYER).min(GRID * LAYER);
+                    return true;
+                }
+                KeyCode::Minus | KeyCode::NumpadSubtract => {
+                    self.count = self.count.saturating_sub(LAYER);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        self.camera.input(event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.write_uniforms();
+
+        // animate every cube on the CPU and upload the ones in view all at once
+        self.elapsed += dt;
+        let t = ANIMATION_SPEED * self.elapsed.as_secs_f32();
+        let frustum = Frustum::from_matrix(self.view_projection());
+        let bounds = self.bounds;
+        self.instances.clear();
+        self.instances.extend(
+            (0..self.count)
+                .map(|i| create_instance(i, t))
+                .filter(|instance| {
+                    frustum.intersects_sphere(&bounds.transform(instance.model.into()))
+                }),
+        );
+        self.instance_buffer
+            .update(&self.init.device, &self.init.queue, &self.instances);
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.init.get_current_frame()?;
+
+        let view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    },
+                ))],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            self.mesh
+                .draw_instanced(&mut render_pass, &self.instance_buffer);
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(
+    width: u32,
+    height: u32,
+    time: std::time::Duration,
+) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.update(time);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+fn main() {
+    env_logger::init();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600, std::time::Duration::ZERO).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch06-cube-instances");
+}
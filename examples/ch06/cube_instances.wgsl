@@ -0,0 +1,34 @@
+struct Uniforms {
+    vpMatrix : mat4x4<f32>,
+};
+
+@binding(0) @group(0)
+var<uniform> uniforms : Uniforms;
+
+struct Instance {
+    @location(2) model0 : vec4<f32>,
+    @location(3) model1 : vec4<f32>,
+    @location(4) model2 : vec4<f32>,
+    @location(5) model3 : vec4<f32>,
+    @location(6) color : vec4<f32>,
+};
+
+struct Output {
+    @builtin(position) Position : vec4<f32>,
+    @location(0) vColor : vec4<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) pos: vec4<f32>, @location(1) color: vec4<f32>, instance: Instance) -> Output {
+    let model = mat4x4<f32>(instance.model0, instance.model1, instance.model2, instance.model3);
+    var output: Output;
+    output.Position = uniforms.vpMatrix * model * pos;
+    // tint the corner colors of the cube with the instance color
+    output.vColor = mix(color, instance.color, 0.6);
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) vColor: vec4<f32>) -> @location(0) vec4<f32> {
+    return vColor;
+}
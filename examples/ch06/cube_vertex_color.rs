@@ -1,73 +1,56 @@
-use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::*;
-use std::{iter, mem};
+use std::iter;
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes},
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::OrbitCamera,
+    capture,
+    depth::DepthTarget,
+    geometry::{vertex_data, GpuMesh, Indices, Mesh},
+    msaa::MsaaTarget,
+    transforms::{self, Projection},
+    InitWgpu,
 };
+use winit::{dpi::PhysicalSize, event::WindowEvent};
 
-#[path = "../common/transforms.rs"]
-mod transforms;
-
-#[path = "../common/vertex_data.rs"]
-mod vertex_data;
-
-const IS_PERSPECTIVE: bool = true;
-
-#[repr(C)]
-#[derive(Copy, Clone, Debug, Pod, Zeroable)]
-struct Vertex {
-    position: [f32; 4],
-    color: [f32; 4],
-}
-fn vertex(p: [i8; 3], c: [i8; 3]) -> Vertex {
-    Vertex {
-        position: [p[0] as f32, p[1] as f32, p[2] as f32, 1.0],
-        color: [c[0] as f32, c[1] as f32, c[2] as f32, 1.0],
-    }
-}
-fn create_vertices() -> (Vec<Vertex>, Vec<u16>) {
+fn create_mesh() -> Mesh {
     let (pos, col, ind) = vertex_data::cube_data_index();
-    let mut data: Vec<Vertex> = Vec::with_capacity(pos.len());
-    for i in 0..pos.len() {
-        data.push(vertex(pos[i], col[i]));
+    Mesh {
+        positions: pos
+            .iter()
+            .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+            .collect(),
+        colors: Some(
+            col.iter()
+                .map(|c| [c[0] as f32, c[1] as f32, c[2] as f32, 1.0])
+                .collect(),
+        ),
+        indices: Some(Indices::U16(ind)),
+        ..Default::default()
     }
-    (data.to_vec(), ind)
 }
 
 const ANIMATION_SPEED: f32 = 1.0;
+const SAMPLE_COUNT: u32 = 4;
 
-impl Vertex {
-    const ATTRIBUTES: [wgpu::VertexAttribute; 2] =
-        wgpu::vertex_attr_array![0=>Float32x4, 1=>Float32x4];
-
-    fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
-        wgpu::VertexBufferLayout {
-            array_stride: mem::size_of::<Vertex>() as wgpu::BufferAddress,
-            step_mode: wgpu::VertexStepMode::Vertex,
-            attributes: &Self::ATTRIBUTES,
-        }
-    }
-}
-struct State<'a> {
-    init: transforms::InitWgpu<'a>,
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
     pipeline: wgpu::RenderPipeline,
-    vertex_buffer: wgpu::Buffer,
-    index_buffer: wgpu::Buffer,
-    indices_len: u32,
+    mesh: GpuMesh,
     uniform_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     model_mat: Matrix4<f32>,
     view_mat: Matrix4<f32>,
-    project_mat: Matrix4<f32>,
+    projection: Projection,
+    camera: OrbitCamera,
+    elapsed: std::time::Duration,
 }
-impl<'a> State<'a> {
-    async fn new(window: &'a Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
-        let (vertex_data, index_data) = create_vertices();
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let mesh = create_mesh();
+        let mesh_layout = mesh.layout(0);
 
         let shader = init
             .device
@@ -77,24 +60,16 @@ impl<'a> State<'a> {
             });
 
         // uniform data
-        let camera_position = (3.0, 1.5, 3.0).into();
-
-        let look_direction = (0.0, 0.0, 0.0).into();
-
-        let up_direction = cgmath::Vector3::unit_y();
+        let camera = OrbitCamera::new((3.0, 1.5, 3.0).into(), (0.0, 0.0, 0.0).into(), init.size);
 
         let model_mat =
             transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
 
-        let (view_mat, project_mat, view_project_mat) = transforms::create_view_projection(
-            camera_position,
-            look_direction,
-            up_direction,
-            init.config.width as f32 / init.config.height as f32,
-            IS_PERSPECTIVE,
-        );
+        let view_mat = camera.view_matrix();
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(72.0), aspect, 0.1, 100.0);
 
-        let mvp_mat = view_project_mat * model_mat;
+        let mvp_mat = projection.matrix() * view_mat * model_mat;
 
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
 
@@ -139,6 +114,28 @@ impl<'a> State<'a> {
                 push_constant_ranges: &[],
             });
 
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(
+                SAMPLE_COUNT,
+                &[init.config.format, wgpu::TextureFormat::Depth24Plus],
+            )
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            wgpu::TextureFormat::Depth24Plus,
+            sample_count,
+        );
+
         let pipeline = init
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -149,7 +146,7 @@ impl<'a> State<'a> {
                 vertex: wgpu::VertexState {
                     module: &shader,
                     entry_point: "vs_main",
-                    buffers: &[Vertex::desc()],
+                    buffers: &[mesh_layout.buffer_layout()],
                     compilation_options: Default::default(),
                 },
                 fragment: Some(wgpu::FragmentState {
@@ -173,60 +170,38 @@ impl<'a> State<'a> {
                 },
 
                 //depth_stencil: None,
-                depth_stencil: Some(wgpu::DepthStencilState {
-                    format: wgpu::TextureFormat::Depth24Plus,
-                    depth_write_enabled: true,
-                    depth_compare: wgpu::CompareFunction::LessEqual,
-                    stencil: wgpu::StencilState::default(),
-                    bias: wgpu::DepthBiasState::default(),
-                }),
-                multisample: wgpu::MultisampleState::default(),
+                depth_stencil: Some(depth.depth_stencil_state()),
+                multisample: msaa.multisample_state(),
             });
 
-        let vertex_buffer = init
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: cast_slice(&vertex_data),
-                usage: wgpu::BufferUsages::VERTEX,
-            });
-
-        let index_buffer = init
-            .device
-            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(&index_data),
-                usage: wgpu::BufferUsages::INDEX,
-            });
-        let indices_len = index_data.len() as u32;
+        let mesh = mesh.upload(&init.device);
 
         Self {
             init,
+            msaa,
+            depth,
             pipeline,
-            vertex_buffer,
+            mesh,
             uniform_buffer,
             uniform_bind_group,
-            index_buffer,
-            indices_len,
             model_mat,
             view_mat,
-            project_mat,
+            projection,
+            camera,
+            elapsed: std::time::Duration::ZERO,
         }
     }
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.camera.resize(new_size);
+            self.projection.resize(new_size.width, new_size.height);
 
-            let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
+            let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
 
             let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
             self.init
@@ -234,20 +209,25 @@ impl<'a> State<'a> {
                 .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
         }
     }
-    #[allow(unused_variables)]
+
     fn input(&mut self, event: &WindowEvent) -> bool {
-        false
+        self.camera.input(event)
     }
 
     fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.view_mat = self.camera.view_matrix();
+
         // update uniform buffer
-        let dt = ANIMATION_SPEED * dt.as_secs_f32();
-        let model_mat = transforms::create_transforms(
+        self.elapsed += dt;
+        let t = ANIMATION_SPEED * self.elapsed.as_secs_f32();
+        // kept so a resize between updates redraws the cube where it was
+        self.model_mat = transforms::create_transforms(
             [0.0, 0.0, 0.0],
-            [dt.sin(), dt.cos(), 0.0],
+            [t.sin(), t.cos(), 0.0],
             [1.0, 1.0, 1.0],
         );
-        let mvp_mat = self.project_mat * self.view_mat * model_mat;
+        let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
         self.init
             .queue
@@ -255,29 +235,12 @@ impl<'a> State<'a> {
     }
 
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
+        let output = self.init.get_current_frame()?;
 
         let view = output
-            .texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
 
-        let depth_texture = self.init.device.create_texture(&wgpu::TextureDescriptor {
-            size: wgpu::Extent3d {
-                width: self.init.config.width,
-                height: self.init.config.height,
-                depth_or_array_layers: 1,
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24Plus,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            label: None,
-            view_formats: &[],
-        });
-
-        let depth_view = depth_texture.create_view(&wgpu::TextureViewDescriptor::default());
-
         let mut encoder =
             self.init
                 .device
@@ -289,34 +252,21 @@ impl<'a> State<'a> {
                 label: Some("Render Pass"),
                 timestamp_writes: None,
                 occlusion_query_set: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     },
-                })],
+                ))],
                 //depth_stencil_attachment: None,
-                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                    view: &depth_view,
-                    depth_ops: Some(wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(1.0),
-                        store: wgpu::StoreOp::Store,
-                    }),
-                    stencil_ops: None,
-                }),
+                depth_stencil_attachment: Some(self.depth.attachment()),
             });
             render_pass.set_pipeline(&self.pipeline);
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
             render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
-            render_pass.draw_indexed(0..self.indices_len, 0, 0..1);
+            self.mesh.draw(&mut render_pass);
         }
         self.init.queue.submit(iter::once(encoder.finish()));
         output.present();
@@ -324,59 +274,33 @@ impl<'a> State<'a> {
     }
 }
 
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(
+    width: u32,
+    height: u32,
+    time: std::time::Duration,
+) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.update(time);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
 fn main() {
     env_logger::init();
 
-    let event_loop = EventLoop::new().unwrap();
-    let window: winit::window::Window = event_loop
-        .create_window(WindowAttributes::default())
-        .unwrap();
-    window.set_title(&*format!("{}", "ch06-cube-face-color"));
-    let mut state = pollster::block_on(State::new(&window));
-
-    let render_start_time: std::time::Instant = std::time::Instant::now();    
-
-    event_loop
-        .run(move |event: Event<()>, target| {
-            if let Event::WindowEvent {
-                window_id: _,
-                event,
-            } = event
-            {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                state: ElementState::Pressed,
-                                physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => target.exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(physical_size);
-                        state.render();
-                    }
-                    WindowEvent::RedrawRequested => {
-                        dbg!("{:?}", &event);
-                        let now: std::time::Instant = std::time::Instant::now();    
-                        let dt = now - render_start_time;   
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600, std::time::Duration::ZERO).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
 
-                        state.update(dt);
-                        match state.render() {
-                            Ok(_) => {}
-                            Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                            Err(wgpu::SurfaceError::OutOfMemory) => target.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
-                    }
-                    // WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    //     state.resize(**new_inner_size);
-                    // }
-                    _ => {}
-                }
-            }
-        })
-        .unwrap();
+    app::run::<State>("ch06-cube-vertex-color");
 }
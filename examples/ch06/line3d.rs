@@ -2,17 +2,16 @@ use bytemuck::{cast_slice, Pod, Zeroable};
 use cgmath::*;
 use std::{iter, mem};
 use wgpu::util::DeviceExt;
-use winit::{
-    event::{ElementState, Event, KeyEvent, WindowEvent},
-    event_loop::EventLoop,
-    keyboard::{KeyCode, PhysicalKey},
-    window::{Window, WindowAttributes},
+use wgpu_step_by_step::{
+    app::{self, App},
+    capture,
+    msaa::MsaaTarget,
+    transforms::{self, Projection},
+    InitWgpu,
 };
+use winit::dpi::PhysicalSize;
 
-#[path = "../common/transforms.rs"]
-mod transforms;
-
-const IS_PERSPECTIVE: bool = true;
+const SAMPLE_COUNT: u32 = 4;
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, Pod, Zeroable)]
@@ -25,13 +24,13 @@ fn create_vertices() -> [Vertex; 300] {
         position: [0.0, 0.0, 0.0],
     }; 300];
 
-    for i in 0..300 {
+    for (i, vertex) in vertices.iter_mut().enumerate() {
         let t = 0.1 * (i as f32) / 30.0;
         let x = (-t).exp() * (30.0 * t).sin();
         let z = (-t).exp() * (30.0 * t).cos();
         let y = 2.0 * t - 1.0;
 
-        vertices[i] = Vertex {
+        *vertex = Vertex {
             position: [x, y, z],
         };
     }
@@ -48,21 +47,20 @@ impl Vertex {
         }
     }
 }
-struct State<'a> {
-    init: transforms::InitWgpu<'a>,
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: wgpu::Buffer,
     uniform_buffer: wgpu::Buffer,
     uniform_bind_group: wgpu::BindGroup,
     model_mat: Matrix4<f32>,
     view_mat: Matrix4<f32>,
-    project_mat: Matrix4<f32>,
+    projection: Projection,
 }
 
-impl<'a> State<'a> {
-    async fn new(window: &'a Window) -> Self {
-        let init = transforms::InitWgpu::init_wgpu(window).await;
-
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
         let shader = init
             .device
             .create_shader_module(wgpu::ShaderModuleDescriptor {
@@ -79,15 +77,11 @@ impl<'a> State<'a> {
         let model_mat =
             transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
 
-        let (view_mat, project_mat, view_project_mat) = transforms::create_view_projection(
-            camera_position,
-            look_direction,
-            up_direction,
-            init.config.width as f32 / init.config.height as f32,
-            false,
-        );
+        let view_mat = transforms::create_view(camera_position, look_direction, up_direction);
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::orthographic(6.0, aspect, -1.0, 6.0);
 
-        let mvp_mat = view_project_mat * model_mat;
+        let mvp_mat = projection.matrix() * view_mat * model_mat;
 
         let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
 
@@ -132,6 +126,18 @@ impl<'a> State<'a> {
                 push_constant_ranges: &[],
             });
 
+        // 4x MSAA smooths the helix, if the adapter supports it for this format
+        let sample_count = init
+            .sample_count(SAMPLE_COUNT, &[init.config.format])
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+
         let pipeline = init
             .device
             .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
@@ -164,7 +170,7 @@ impl<'a> State<'a> {
                     ..Default::default()
                 },
                 depth_stencil: None,
-                multisample: wgpu::MultisampleState::default(),
+                multisample: msaa.multisample_state(),
             });
         let vertex_buffer = init
             .device
@@ -176,29 +182,24 @@ impl<'a> State<'a> {
 
         Self {
             init,
+            msaa,
             pipeline,
             vertex_buffer,
             uniform_buffer,
             uniform_bind_group,
             model_mat,
             view_mat,
-            project_mat,
+            projection,
         }
     }
-    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
         if new_size.width > 0 && new_size.height > 0 {
-            self.init.size = new_size;
-            self.init.config.width = new_size.width;
-            self.init.config.height = new_size.height;
-            self.init
-                .surface
-                .configure(&self.init.device, &self.init.config);
-            self.project_mat = transforms::create_projection(
-                new_size.width as f32 / new_size.height as f32,
-                IS_PERSPECTIVE,
-            );
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.projection.resize(new_size.width, new_size.height);
 
-            let mvp_mat = self.project_mat * self.view_mat * self.model_mat;
+            let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
 
             let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
             self.init
@@ -206,16 +207,10 @@ impl<'a> State<'a> {
                 .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
         }
     }
-    #[allow(unused_variables)]
-    fn input(&mut self, event: &WindowEvent) -> bool {
-        return false;
-    }
-    fn update(&mut self) {}
     fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
-        let output = self.init.surface.get_current_texture()?;
-        print!("render");
+        let output = self.init.get_current_frame()?;
         let view = output
-            .texture
+            .texture()
             .create_view(&wgpu::TextureViewDescriptor::default());
 
         let mut encoder =
@@ -229,19 +224,15 @@ impl<'a> State<'a> {
                 label: Some("Render Pass"),
                 timestamp_writes: None,
                 occlusion_query_set: None,
-                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: 0.2,
-                            g: 0.247,
-                            b: 0.314,
-                            a: 1.0,
-                        }),
-                        store: wgpu::StoreOp::Store,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
                     },
-                })],
+                ))],
                 depth_stencil_attachment: None,
             });
             render_pass.set_pipeline(&self.pipeline);
@@ -255,54 +246,28 @@ impl<'a> State<'a> {
         Ok(())
     }
 }
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
 fn main() {
     env_logger::init();
 
-    let event_loop = EventLoop::new().unwrap();
-    let window: winit::window::Window = event_loop
-        .create_window(WindowAttributes::default())
-        .unwrap();
-    window.set_title(&*format!("{}", "ch06-3d-line"));
-    let current_window_id = window.id();
-    let mut state = pollster::block_on(State::new(&window));
-    event_loop
-        .run(move |event: Event<()>, target| {
-            if let Event::WindowEvent {
-                window_id: _,
-                event,
-            } = event
-            {
-                match event {
-                    WindowEvent::CloseRequested
-                    | WindowEvent::KeyboardInput {
-                        event:
-                            KeyEvent {
-                                state: ElementState::Pressed,
-                                physical_key: PhysicalKey::Code(KeyCode::Escape),
-                                ..
-                            },
-                        ..
-                    } => target.exit(),
-                    WindowEvent::Resized(physical_size) => {
-                        state.resize(physical_size);
-                        state.render();
-                    }
-                    WindowEvent::RedrawRequested => {
-                        dbg!("{:?}", &event);
-                        state.update();
-                        match state.render() {
-                            Ok(_) => {}
-                            Err(wgpu::SurfaceError::Lost) => state.resize(state.init.size),
-                            Err(wgpu::SurfaceError::OutOfMemory) => target.exit(),
-                            Err(e) => eprintln!("{:?}", e),
-                        }
-                    }
-                    // WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                    //     state.resize(**new_inner_size);
-                    // }
-                    _ => {}
-                }
-            }
-        })
-        .unwrap();
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch06-3d-line");
 }
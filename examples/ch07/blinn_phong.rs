@@ -0,0 +1,290 @@
+use cgmath::*;
+use std::iter;
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::OrbitCamera,
+    capture,
+    depth::DepthTarget,
+    geometry::{vertex_data, GpuMesh, Mesh},
+    lighting::{
+        Attenuation, FrameUniform, Light, LightColor, PhongMaterial, PhongObject, PhongPipeline,
+        ShadowMap, ShadowPipeline, ShadowSettings,
+    },
+    msaa::MsaaTarget,
+    transforms::{self, Projection},
+    InitWgpu,
+};
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+const SAMPLE_COUNT: u32 = 4;
+// the spot light casts shadows
+const SHADOW_LIGHT: usize = 2;
+
+// positions and normals of the cube; its colors and uvs are not needed for lighting
+fn create_cube() -> Mesh {
+    let (pos, _col, _uv, normal) = vertex_data::cube_data();
+    let to_f32 = |v: &[i8; 3]| [v[0] as f32, v[1] as f32, v[2] as f32];
+    Mesh {
+        positions: pos.iter().map(to_f32).collect(),
+        normals: Some(normal.iter().map(to_f32).collect()),
+        ..Default::default()
+    }
+}
+
+fn create_lights() -> Vec<Light> {
+    vec![
+        Light::Directional {
+            direction: Vector3::new(-0.3, -1.0, -0.5),
+            color: LightColor::new([0.4, 0.4, 0.45], 0.3),
+        },
+        Light::Point {
+            position: Point3::new(2.5, 1.5, 2.0),
+            color: LightColor::new([1.0, 0.6, 0.3], 0.05),
+            attenuation: Attenuation::default(),
+        },
+        Light::Spot {
+            position: Point3::new(-2.0, 4.0, 1.0),
+            direction: Vector3::new(0.5, -1.0, -0.2),
+            inner_angle: Deg(15.0).into(),
+            outer_angle: Deg(25.0).into(),
+            color: LightColor::new([0.3, 0.6, 1.0], 0.0),
+            attenuation: Attenuation::default(),
+        },
+    ]
+}
+
+// a mesh with its uniforms
+struct Object {
+    mesh: GpuMesh,
+    uniforms: PhongObject,
+}
+
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
+    pipeline: PhongPipeline,
+    shadow_map: ShadowMap,
+    shadow_pipeline: ShadowPipeline,
+    objects: Vec<Object>,
+    lights: Vec<Light>,
+    projection: Projection,
+    camera: OrbitCamera,
+}
+
+impl State {
+    fn write_frame(&mut self) {
+        let view = self.camera.view_matrix();
+        let view_projection = self.projection.matrix() * view;
+        let frame = FrameUniform::new(view_projection, self.camera.eye(), &self.lights);
+        self.pipeline.set_frame(&self.init.queue, &frame);
+        self.shadow_map.update(
+            &self.init.queue,
+            &self.lights,
+            Some(SHADOW_LIGHT),
+            view,
+            &self.projection,
+        );
+    }
+}
+
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let cube = create_cube();
+        // drop the uvs so every mesh shares the cube's position + normal layout
+        let sphere = Mesh {
+            uvs: None,
+            ..vertex_data::uv_sphere_data(0.8, 32, 16).into()
+        };
+        let floor = Mesh {
+            uvs: None,
+            ..vertex_data::plane_data(10.0, 10.0, 1, 1).into()
+        };
+
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(SAMPLE_COUNT, &[init.config.format, DEPTH_FORMAT])
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            DEPTH_FORMAT,
+            sample_count,
+        );
+
+        let shadow_map = ShadowMap::new(
+            &init.device,
+            ShadowSettings {
+                distance: 20.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let shadow_pipeline = shadow_map.create_pipeline(&init.device, &cube.layout(0));
+        let pipeline = PhongPipeline::new(
+            &init.device,
+            init.config.format,
+            Some(DEPTH_FORMAT),
+            sample_count,
+            &cube.layout(0),
+            &shadow_map,
+        );
+
+        let scene = [
+            (
+                &cube,
+                transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.5, 0.0], [1.0, 1.0, 1.0]),
+                PhongMaterial {
+                    diffuse: [0.9, 0.2, 0.2, 1.0],
+                    ..Default::default()
+                },
+            ),
+            (
+                &sphere,
+                transforms::create_transforms([-2.2, -0.2, 0.5], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]),
+                PhongMaterial {
+                    diffuse: [0.2, 0.7, 0.3, 1.0],
+                    specular: [1.0, 1.0, 1.0],
+                    shininess: 64.0,
+                },
+            ),
+            (
+                &floor,
+                transforms::create_transforms([0.0, -1.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]),
+                PhongMaterial {
+                    diffuse: [0.6, 0.6, 0.6, 1.0],
+                    specular: [0.1, 0.1, 0.1],
+                    shininess: 8.0,
+                },
+            ),
+        ];
+        let objects = scene
+            .iter()
+            .map(|(mesh, model, material)| Object {
+                mesh: mesh.upload(&init.device),
+                uniforms: pipeline.create_object(&init.device, *model, material),
+            })
+            .collect();
+
+        let camera = OrbitCamera::new((4.0, 3.0, 5.0).into(), (0.0, 0.0, 0.0).into(), init.size);
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(60.0), aspect, 0.1, 100.0);
+
+        let mut state = Self {
+            init,
+            msaa,
+            depth,
+            pipeline,
+            shadow_map,
+            shadow_pipeline,
+            objects,
+            lights: create_lights(),
+            projection,
+            camera,
+        };
+        state.write_frame();
+        state
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.projection.resize(new_size.width, new_size.height);
+            self.camera.resize(new_size);
+            self.write_frame();
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera.input(event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.write_frame();
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.init.get_current_frame()?;
+
+        let view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        self.shadow_map.render(&mut encoder, |render_pass| {
+            for object in &self.objects {
+                self.shadow_pipeline
+                    .draw(render_pass, &object.uniforms.bind_group, &object.mesh);
+            }
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.05,
+                        g: 0.05,
+                        b: 0.08,
+                        a: 1.0,
+                    },
+                ))],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            for object in &self.objects {
+                self.pipeline
+                    .draw(&mut render_pass, &object.uniforms, &object.mesh);
+            }
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+fn main() {
+    env_logger::init();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch07-blinn-phong");
+}
@@ -0,0 +1,319 @@
+use cgmath::*;
+use std::iter;
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::OrbitCamera,
+    capture,
+    depth::DepthTarget,
+    geometry::{vertex_data, GpuMesh, Mesh, PbrMaterial},
+    lighting::{
+        Attenuation, FrameUniform, Light, LightColor, PbrMaterialBinding, PbrObject, PbrPipeline,
+        PbrTextures, ShadowMap, ShadowPipeline, ShadowSettings,
+    },
+    msaa::MsaaTarget,
+    texture::{ColorSpace, MipmapGenerator, Mipmaps, SamplerOptions, Texture, TextureData},
+    transforms::{self, Projection},
+    InitWgpu,
+};
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24Plus;
+const SAMPLE_COUNT: u32 = 4;
+const COLUMNS: usize = 5;
+// the directional light casts cascaded shadows
+const SHADOW_LIGHT: usize = 0;
+
+fn create_lights() -> Vec<Light> {
+    vec![
+        Light::Directional {
+            direction: Vector3::new(-0.4, -1.0, -0.6),
+            color: LightColor::new([1.2, 1.2, 1.1], 0.03),
+        },
+        Light::Point {
+            position: Point3::new(-3.0, 2.0, 3.0),
+            color: LightColor::new([6.0, 4.5, 3.0], 0.0),
+            attenuation: Attenuation::default(),
+        },
+        Light::Point {
+            position: Point3::new(4.0, 1.0, 2.0),
+            color: LightColor::new([1.5, 3.0, 6.0], 0.0),
+            attenuation: Attenuation::default(),
+        },
+    ]
+}
+
+// grey and white squares, eight per side
+fn create_checker_texture(device: &wgpu::Device, queue: &wgpu::Queue) -> Texture {
+    const SIZE: u32 = 64;
+    let mut pixels = Vec::with_capacity((SIZE * SIZE * 4) as usize);
+    for y in 0..SIZE {
+        for x in 0..SIZE {
+            let value = if (x / 8 + y / 8) % 2 == 0 { 230 } else { 90 };
+            pixels.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+    let data = TextureData::rgba8(SIZE, SIZE, pixels, ColorSpace::Srgb);
+    // the floor is seen at a grazing angle, where it would alias without mipmaps
+    Texture::from_data(
+        device,
+        queue,
+        &data,
+        Some("Checker Texture"),
+        &SamplerOptions::default().with_anisotropy(16),
+        Mipmaps::Gpu(&MipmapGenerator::new(device)),
+    )
+}
+
+// a mesh with its matrices and material
+struct Object {
+    mesh: usize,
+    uniforms: PbrObject,
+    material: PbrMaterialBinding,
+}
+
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
+    pipeline: PbrPipeline,
+    shadow_map: ShadowMap,
+    shadow_pipeline: ShadowPipeline,
+    meshes: Vec<GpuMesh>,
+    objects: Vec<Object>,
+    lights: Vec<Light>,
+    projection: Projection,
+    camera: OrbitCamera,
+}
+
+impl State {
+    fn write_frame(&mut self) {
+        let view = self.camera.view_matrix();
+        let view_projection = self.projection.matrix() * view;
+        let frame = FrameUniform::new(view_projection, self.camera.eye(), &self.lights);
+        self.pipeline.set_frame(&self.init.queue, &frame);
+        self.shadow_map.update(
+            &self.init.queue,
+            &self.lights,
+            Some(SHADOW_LIGHT),
+            view,
+            &self.projection,
+        );
+    }
+}
+
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let sphere: Mesh = vertex_data::uv_sphere_data(0.4, 48, 24).into();
+        let floor: Mesh = vertex_data::plane_data(12.0, 12.0, 1, 1).into();
+
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(SAMPLE_COUNT, &[init.config.format, DEPTH_FORMAT])
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            DEPTH_FORMAT,
+            sample_count,
+        );
+
+        let shadow_map = ShadowMap::new(
+            &init.device,
+            ShadowSettings {
+                distance: 20.0,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        let shadow_pipeline = shadow_map.create_pipeline(&init.device, &sphere.layout(0));
+        let pipeline = PbrPipeline::new(
+            &init.device,
+            &init.queue,
+            init.config.format,
+            Some(DEPTH_FORMAT),
+            sample_count,
+            &sphere.layout(0),
+            &shadow_map,
+        );
+        let checker = create_checker_texture(&init.device, &init.queue);
+
+        // a row of dielectric and a row of metallic spheres, getting rougher to the right
+        let mut objects = Vec::new();
+        for (row, metallic) in [0.0, 1.0].into_iter().enumerate() {
+            for column in 0..COLUMNS {
+                let x = (column as f32 - (COLUMNS - 1) as f32 / 2.0) * 1.1;
+                let model = transforms::create_transforms(
+                    [x, 0.0, row as f32 * -1.2 + 0.6],
+                    [0.0, 0.0, 0.0],
+                    [1.0, 1.0, 1.0],
+                );
+                let material = PbrMaterial {
+                    base_color_factor: [0.9, 0.5, 0.2, 1.0],
+                    metallic_factor: metallic,
+                    roughness_factor: 0.1 + 0.9 * column as f32 / (COLUMNS - 1) as f32,
+                    ..Default::default()
+                };
+                objects.push(Object {
+                    mesh: 0,
+                    uniforms: pipeline.create_object(&init.device, model),
+                    material: pipeline.create_material(
+                        &init.device,
+                        &material,
+                        &PbrTextures::default(),
+                    ),
+                });
+            }
+        }
+        let floor_material = PbrMaterial {
+            metallic_factor: 0.0,
+            roughness_factor: 0.8,
+            ..Default::default()
+        };
+        objects.push(Object {
+            mesh: 1,
+            uniforms: pipeline.create_object(
+                &init.device,
+                transforms::create_transforms([0.0, -0.4, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]),
+            ),
+            material: pipeline.create_material(
+                &init.device,
+                &floor_material,
+                &PbrTextures {
+                    base_color: Some(&checker.view),
+                    sampler: Some(&checker.sampler),
+                    ..Default::default()
+                },
+            ),
+        });
+
+        let meshes = vec![sphere.upload(&init.device), floor.upload(&init.device)];
+
+        let camera = OrbitCamera::new((0.0, 2.5, 4.5).into(), (0.0, 0.0, 0.0).into(), init.size);
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(60.0), aspect, 0.1, 100.0);
+
+        let mut state = Self {
+            init,
+            msaa,
+            depth,
+            pipeline,
+            shadow_map,
+            shadow_pipeline,
+            meshes,
+            objects,
+            lights: create_lights(),
+            projection,
+            camera,
+        };
+        state.write_frame();
+        state
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.projection.resize(new_size.width, new_size.height);
+            self.camera.resize(new_size);
+            self.write_frame();
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera.input(event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.write_frame();
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.init.get_current_frame()?;
+
+        let view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        self.shadow_map.render(&mut encoder, |render_pass| {
+            for object in &self.objects {
+                self.shadow_pipeline.draw(
+                    render_pass,
+                    &object.uniforms.bind_group,
+                    &self.meshes[object.mesh],
+                );
+            }
+        });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.02,
+                        g: 0.02,
+                        b: 0.03,
+                        a: 1.0,
+                    },
+                ))],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            for object in &self.objects {
+                self.pipeline.draw(
+                    &mut render_pass,
+                    &object.uniforms,
+                    &object.material,
+                    &self.meshes[object.mesh],
+                );
+            }
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+fn main() {
+    env_logger::init();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch07-pbr");
+}
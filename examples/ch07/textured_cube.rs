@@ -0,0 +1,302 @@
+use cgmath::*;
+use std::iter;
+use wgpu::util::DeviceExt;
+use wgpu_step_by_step::{
+    app::{self, App},
+    camera::OrbitCamera,
+    capture,
+    depth::DepthTarget,
+    geometry::{vertex_data, GpuMesh, Mesh},
+    msaa::MsaaTarget,
+    texture::{ColorSpace, MipmapGenerator, Mipmaps, SamplerOptions, Texture},
+    transforms::{self, Projection},
+    InitWgpu,
+};
+use winit::{dpi::PhysicalSize, event::WindowEvent};
+
+const TEXTURE_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/ch07/crate.png");
+const SAMPLE_COUNT: u32 = 4;
+
+fn create_mesh() -> Mesh {
+    let (pos, _col, uv, _normal) = vertex_data::cube_data();
+    Mesh {
+        positions: pos
+            .iter()
+            .map(|p| [p[0] as f32, p[1] as f32, p[2] as f32])
+            .collect(),
+        uvs: Some(uv.iter().map(|t| [t[0] as f32, t[1] as f32]).collect()),
+        ..Default::default()
+    }
+}
+
+struct State {
+    init: InitWgpu<'static>,
+    msaa: MsaaTarget,
+    depth: DepthTarget,
+    pipeline: wgpu::RenderPipeline,
+    mesh: GpuMesh,
+    uniform_buffer: wgpu::Buffer,
+    uniform_bind_group: wgpu::BindGroup,
+    texture_bind_group: wgpu::BindGroup,
+    model_mat: Matrix4<f32>,
+    view_mat: Matrix4<f32>,
+    projection: Projection,
+    camera: OrbitCamera,
+}
+
+impl State {
+    fn write_mvp(&self) {
+        let mvp_mat = self.projection.matrix() * self.view_mat * self.model_mat;
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+        self.init
+            .queue
+            .write_buffer(&self.uniform_buffer, 0, bytemuck::cast_slice(mvp_ref));
+    }
+}
+
+impl App for State {
+    fn init(init: InitWgpu<'static>) -> Self {
+        let mesh = create_mesh();
+        let mesh_layout = mesh.layout(0);
+
+        let shader = init
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some("Shader"),
+                source: wgpu::ShaderSource::Wgsl(include_str!("textured_cube.wgsl").into()),
+            });
+
+        // the crate image holds colors, so it is sampled as sRGB; mipmaps and
+        // anisotropic filtering keep the faces from shimmering at grazing angles
+        let mipmaps = MipmapGenerator::new(&init.device);
+        let texture = Texture::load(
+            &init.device,
+            &init.queue,
+            TEXTURE_PATH,
+            ColorSpace::Srgb,
+            &SamplerOptions::default().with_anisotropy(16),
+            Mipmaps::Gpu(&mipmaps),
+        )
+        .unwrap();
+        let texture_bind_group_layout = Texture::bind_group_layout(&init.device);
+        let texture_bind_group = texture.bind_group(&init.device, &texture_bind_group_layout);
+
+        // uniform data
+        let camera = OrbitCamera::new((3.0, 1.5, 3.0).into(), (0.0, 0.0, 0.0).into(), init.size);
+
+        let model_mat =
+            transforms::create_transforms([0.0, 0.0, 0.0], [0.0, 0.0, 0.0], [1.0, 1.0, 1.0]);
+
+        let view_mat = camera.view_matrix();
+        let aspect = init.config.width as f32 / init.config.height as f32;
+        let projection = Projection::perspective(Deg(72.0), aspect, 0.1, 100.0);
+
+        let mvp_mat = projection.matrix() * view_mat * model_mat;
+
+        let mvp_ref: &[f32; 16] = mvp_mat.as_ref();
+
+        let uniform_buffer = init
+            .device
+            .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Uniform Buffer"),
+                contents: bytemuck::cast_slice(mvp_ref),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            });
+
+        let uniform_bind_group_layout =
+            init.device
+                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                    entries: &[wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStages::VERTEX,
+                        ty: wgpu::BindingType::Buffer {
+                            ty: wgpu::BufferBindingType::Uniform,
+                            has_dynamic_offset: false,
+                            min_binding_size: None,
+                        },
+                        count: None,
+                    }],
+                    label: Some("Uniform Bind Group Layout"),
+                });
+
+        let uniform_bind_group = init.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &uniform_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: uniform_buffer.as_entire_binding(),
+            }],
+            label: Some("Uniform Bind Group"),
+        });
+
+        let pipeline_layout = init
+            .device
+            .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Render Pipeline Layout"),
+                bind_group_layouts: &[&uniform_bind_group_layout, &texture_bind_group_layout],
+                push_constant_ranges: &[],
+            });
+
+        // 4x MSAA, or the most the adapter supports for these formats
+        let sample_count = init
+            .sample_count(
+                SAMPLE_COUNT,
+                &[init.config.format, wgpu::TextureFormat::Depth24Plus],
+            )
+            .unwrap();
+        let msaa = MsaaTarget::new(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            init.config.format,
+            sample_count,
+        );
+        let depth = DepthTarget::multisampled(
+            &init.device,
+            init.config.width,
+            init.config.height,
+            wgpu::TextureFormat::Depth24Plus,
+            sample_count,
+        );
+
+        let pipeline = init
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                cache: None,
+                multiview: None,
+                label: Some("Render Pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[mesh_layout.buffer_layout()],
+                    compilation_options: Default::default(),
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: init.config.format,
+                        blend: Some(wgpu::BlendState {
+                            color: wgpu::BlendComponent::REPLACE,
+                            alpha: wgpu::BlendComponent::REPLACE,
+                        }),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                    compilation_options: Default::default(),
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    cull_mode: Some(wgpu::Face::Back),
+                    ..Default::default()
+                },
+                depth_stencil: Some(depth.depth_stencil_state()),
+                multisample: msaa.multisample_state(),
+            });
+
+        let mesh = mesh.upload(&init.device);
+
+        Self {
+            init,
+            msaa,
+            depth,
+            pipeline,
+            mesh,
+            uniform_buffer,
+            uniform_bind_group,
+            texture_bind_group,
+            model_mat,
+            view_mat,
+            projection,
+            camera,
+        }
+    }
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.init.resize(new_size);
+            self.msaa
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.depth
+                .resize(&self.init.device, new_size.width, new_size.height);
+            self.camera.resize(new_size);
+            self.projection.resize(new_size.width, new_size.height);
+            self.write_mvp();
+        }
+    }
+
+    fn input(&mut self, event: &WindowEvent) -> bool {
+        self.camera.input(event)
+    }
+
+    fn update(&mut self, dt: std::time::Duration) {
+        self.camera.update(dt);
+        self.view_mat = self.camera.view_matrix();
+        self.write_mvp();
+    }
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let output = self.init.get_current_frame()?;
+
+        let view = output
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+
+        let mut encoder =
+            self.init
+                .device
+                .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Render Encoder"),
+                });
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Render Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(self.msaa.color_attachment(
+                    &view,
+                    wgpu::Color {
+                        r: 0.2,
+                        g: 0.247,
+                        b: 0.314,
+                        a: 1.0,
+                    },
+                ))],
+                depth_stencil_attachment: Some(self.depth.attachment()),
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(0, &self.uniform_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.texture_bind_group, &[]);
+            self.mesh.draw(&mut render_pass);
+        }
+        self.init.queue.submit(iter::once(encoder.finish()));
+        output.present();
+        Ok(())
+    }
+}
+
+// render a single frame into an offscreen texture and read it back
+pub(crate) fn render_headless(width: u32, height: u32) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut state = State::init(init);
+    state.render()?;
+    let init = &state.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+fn main() {
+    env_logger::init();
+
+    // CAPTURE_PNG=<file> renders a single frame headlessly and saves it instead of opening a window
+    if let Ok(path) = std::env::var("CAPTURE_PNG") {
+        let image = render_headless(800, 600).unwrap();
+        image.save(path).unwrap();
+        return;
+    }
+
+    app::run::<State>("ch07-textured-cube");
+}
@@ -0,0 +1,29 @@
+struct Uniforms {
+    mvpMatrix : mat4x4<f32>,
+};
+
+@binding(0) @group(0)
+var<uniform> uniforms : Uniforms;
+
+@binding(0) @group(1)
+var textureData : texture_2d<f32>;
+@binding(1) @group(1)
+var textureSampler : sampler;
+
+struct Output {
+    @builtin(position) Position : vec4<f32>,
+    @location(0) vUV : vec2<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) pos: vec3<f32>, @location(1) uv: vec2<f32>) -> Output {
+    var output: Output;
+    output.Position = uniforms.mvpMatrix * vec4<f32>(pos, 1.0);
+    output.vUV = uv;
+    return output;
+}
+
+@fragment
+fn fs_main(@location(0) vUV: vec2<f32>) -> @location(0) vec4<f32> {
+    return textureSample(textureData, textureSampler, vUV);
+}
@@ -0,0 +1,222 @@
+//! The [`App`] trait implemented by every example and the winit runner that drives it.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use winit::{
+    application::ApplicationHandler,
+    dpi::PhysicalSize,
+    event::{DeviceEvent, DeviceId, ElementState, KeyEvent, WindowEvent},
+    event_loop::{ActiveEventLoop, EventLoop},
+    keyboard::{KeyCode, PhysicalKey},
+    window::{CursorGrabMode, Window, WindowAttributes, WindowId},
+};
+
+use crate::init::InitWgpu;
+
+pub mod primitive;
+
+/// A scene driven by [`run`]: built once the window and device exist, then resized,
+/// fed input, updated and rendered every frame.
+pub trait App {
+    fn init(init: InitWgpu<'static>) -> Self
+    where
+        Self: Sized;
+
+    fn resize(&mut self, new_size: PhysicalSize<u32>);
+
+    /// Returns `true` if the event was consumed. A consumed escape key no longer exits;
+    /// close requests, resizes and redraws reach the runner either way.
+    fn input(&mut self, _event: &WindowEvent) -> bool {
+        false
+    }
+
+    /// Raw device input such as unaccelerated mouse motion, used for mouse look.
+    fn device_input(&mut self, _event: &DeviceEvent) {}
+
+    /// Whether the cursor should be hidden and locked to the window. Checked by the
+    /// runner after every update.
+    fn cursor_grabbed(&self) -> bool {
+        false
+    }
+
+    /// Advances the scene by `dt`, the time since the previous frame.
+    fn update(&mut self, _dt: Duration) {}
+
+    fn render(&mut self) -> Result<(), wgpu::SurfaceError>;
+}
+
+/// Frame delta and frames-per-second bookkeeping for the runner.
+pub struct FrameTimer {
+    last_frame: Instant,
+    window_start: Instant,
+    frames_in_window: u32,
+    fps: f32,
+}
+
+impl FrameTimer {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            last_frame: now,
+            window_start: now,
+            frames_in_window: 0,
+            fps: 0.0,
+        }
+    }
+
+    /// Marks the start of a frame and returns the time elapsed since the previous one.
+    pub fn tick(&mut self) -> Duration {
+        let now = Instant::now();
+        let dt = now - self.last_frame;
+        self.last_frame = now;
+
+        // average the frame rate over roughly one second
+        self.frames_in_window += 1;
+        let elapsed = now - self.window_start;
+        if elapsed >= Duration::from_secs(1) {
+            self.fps = self.frames_in_window as f32 / elapsed.as_secs_f32();
+            self.frames_in_window = 0;
+            self.window_start = now;
+            log::debug!("{:.1} fps", self.fps);
+        }
+        dt
+    }
+
+    pub fn fps(&self) -> f32 {
+        self.fps
+    }
+}
+
+impl Default for FrameTimer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Opens a window titled `title` and runs `A` in it until the window is closed or
+/// Escape is pressed.
+pub fn run<A: App>(title: &str) {
+    let event_loop = EventLoop::new().unwrap();
+    let mut runner = Runner::<A> {
+        title: title.to_string(),
+        window: None,
+        app: None,
+        timer: FrameTimer::new(),
+        cursor_grabbed: false,
+    };
+    event_loop.run_app(&mut runner).unwrap();
+}
+
+struct Runner<A> {
+    title: String,
+    window: Option<Arc<Window>>,
+    app: Option<A>,
+    timer: FrameTimer,
+    cursor_grabbed: bool,
+}
+
+// lock the cursor in place, or at least keep it inside the window where locking is
+// not supported
+fn grab_cursor(window: &Window, grab: bool) {
+    let result = if grab {
+        window
+            .set_cursor_grab(CursorGrabMode::Locked)
+            .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+    } else {
+        window.set_cursor_grab(CursorGrabMode::None)
+    };
+    if let Err(err) = result {
+        log::warn!("cannot change cursor grab: {}", err);
+    }
+    window.set_cursor_visible(!grab);
+}
+
+impl<A: App> ApplicationHandler for Runner<A> {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.window.is_some() {
+            return;
+        }
+        let window = Arc::new(
+            event_loop
+                .create_window(WindowAttributes::default().with_title(&self.title))
+                .unwrap(),
+        );
+        let init = pollster::block_on(InitWgpu::init_wgpu(window.clone()));
+        self.app = Some(A::init(init));
+        self.window = Some(window);
+        self.timer = FrameTimer::new();
+    }
+
+    fn window_event(
+        &mut self,
+        event_loop: &ActiveEventLoop,
+        window_id: WindowId,
+        event: WindowEvent,
+    ) {
+        let (Some(window), Some(app)) = (&self.window, &mut self.app) else {
+            return;
+        };
+        if window.id() != window_id {
+            return;
+        }
+        // only the escape key can be taken over; closing, resizing and redrawing are
+        // handled even when the app used the event as well
+        let consumed = app.input(&event);
+
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        state: ElementState::Pressed,
+                        physical_key: PhysicalKey::Code(KeyCode::Escape),
+                        ..
+                    },
+                ..
+            } if !consumed => event_loop.exit(),
+            WindowEvent::Resized(new_size) => {
+                app.resize(new_size);
+                window.request_redraw();
+            }
+            WindowEvent::RedrawRequested => {
+                let dt = self.timer.tick();
+                app.update(dt);
+                if app.cursor_grabbed() != self.cursor_grabbed {
+                    self.cursor_grabbed = app.cursor_grabbed();
+                    grab_cursor(window, self.cursor_grabbed);
+                }
+                match app.render() {
+                    Ok(_) => {}
+                    // reconfigure the surface and try again on the next frame
+                    Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                        app.resize(window.inner_size())
+                    }
+                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                        log::error!("out of memory, exiting");
+                        event_loop.exit();
+                    }
+                    Err(wgpu::SurfaceError::Timeout) => {
+                        log::warn!("surface timeout, frame skipped")
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn device_event(
+        &mut self,
+        _event_loop: &ActiveEventLoop,
+        _device_id: DeviceId,
+        event: DeviceEvent,
+    ) {
+        if let Some(app) = &mut self.app {
+            app.device_input(&event);
+        }
+    }
+
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(window) = &self.window {
+            window.request_redraw();
+        }
+    }
+}
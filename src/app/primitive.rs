@@ -0,0 +1,152 @@
+//! Shader-only scenes of chapter 3, which draw a fixed number of vertices generated
+//! in the vertex shader.
+use wgpu::{IndexFormat, PrimitiveTopology, ShaderSource};
+use winit::dpi::PhysicalSize;
+
+use crate::{capture, init::InitWgpu};
+
+/// Shader and primitive state of a shader-only scene.
+pub struct Inputs<'a> {
+    pub source: ShaderSource<'a>,
+    pub topology: PrimitiveTopology,
+    pub strip_index_format: Option<IndexFormat>,
+}
+
+/// A shader-only scene: the pipeline built from [`Inputs`] drawing `num_vertices`
+/// vertices onto a green background.
+pub struct PrimitiveScene {
+    pub init: InitWgpu<'static>,
+    pipeline: wgpu::RenderPipeline,
+    num_vertices: u32,
+}
+
+impl PrimitiveScene {
+    pub fn new(init: InitWgpu<'static>, inputs: Inputs<'_>, num_vertices: u32) -> Self {
+        let pipeline = create_pipeline(&init.device, inputs, init.config.format);
+        Self {
+            init,
+            pipeline,
+            num_vertices,
+        }
+    }
+
+    pub fn resize(&mut self, new_size: PhysicalSize<u32>) {
+        self.init.resize(new_size);
+    }
+
+    pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        let frame = self.init.get_current_frame()?;
+        let view = frame
+            .texture()
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        draw(
+            &self.init.device,
+            &self.init.queue,
+            &view,
+            &self.pipeline,
+            self.num_vertices,
+        );
+        frame.present();
+        Ok(())
+    }
+}
+
+fn create_pipeline(
+    device: &wgpu::Device,
+    inputs: Inputs<'_>,
+    format: wgpu::TextureFormat,
+) -> wgpu::RenderPipeline {
+    // Load the shaders from disk
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: None,
+        source: inputs.source,
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: None,
+        bind_group_layouts: &[],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: None,
+        layout: Some(&pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: "vs_main",
+            buffers: &[],
+            compilation_options: Default::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "fs_main",
+            compilation_options: Default::default(),
+            targets: &[Some(format.into())],
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: inputs.topology,
+            strip_index_format: inputs.strip_index_format,
+            ..Default::default()
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState::default(),
+        multiview: None,
+        cache: None,
+    })
+}
+
+fn draw(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    view: &wgpu::TextureView,
+    pipeline: &wgpu::RenderPipeline,
+    num_vertices: u32,
+) {
+    let mut encoder =
+        device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(wgpu::Color::GREEN),
+                    store: wgpu::StoreOp::Store,
+                },
+            })],
+            depth_stencil_attachment: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        rpass.set_pipeline(pipeline);
+        rpass.draw(0..num_vertices, 0..1);
+    }
+
+    queue.submit(Some(encoder.finish()));
+}
+
+/// Renders a single frame into an offscreen texture and reads it back.
+pub fn render_headless(
+    inputs: Inputs<'_>,
+    num_vertices: u32,
+    width: u32,
+    height: u32,
+) -> anyhow::Result<image::RgbaImage> {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        width,
+        height,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let mut scene = PrimitiveScene::new(init, inputs, num_vertices);
+    scene.render()?;
+    let init = &scene.init;
+    capture::read_texture(&init.device, &init.queue, init.offscreen.as_ref().unwrap())
+}
+
+/// Renders a single frame headlessly and writes it to a PNG file instead of opening a window.
+pub fn capture(inputs: Inputs<'_>, num_vertices: u32, path: &str) -> anyhow::Result<()> {
+    let image = render_headless(inputs, num_vertices, 800, 600)?;
+    image.save(path)?;
+    Ok(())
+}
@@ -0,0 +1,193 @@
+//! A first-person fly camera: click to grab the cursor and look around with the mouse,
+//! move with WASD, descend and rise with Q/E, hold Shift to sprint and press Escape to
+//! release the cursor.
+use cgmath::*;
+use std::time::Duration;
+use winit::{
+    event::{DeviceEvent, ElementState, KeyEvent, MouseButton, WindowEvent},
+    keyboard::{KeyCode, PhysicalKey},
+};
+
+use crate::transforms;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FlySettings {
+    /// Units per second.
+    pub speed: f32,
+    /// Speed factor while Shift is held.
+    pub sprint_multiplier: f32,
+    /// Radians per unit of raw mouse motion.
+    pub look_sensitivity: f32,
+    /// Largest pitch above or below the horizon; keep it under 90° so the view never
+    /// flips.
+    pub max_pitch: Rad<f32>,
+}
+
+impl Default for FlySettings {
+    fn default() -> Self {
+        Self {
+            speed: 4.0,
+            sprint_multiplier: 4.0,
+            look_sensitivity: 0.002,
+            max_pitch: Deg(89.0).into(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct HeldKeys {
+    forward: bool,
+    back: bool,
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    sprint: bool,
+}
+
+/// Fly camera state plus held keys. Forward window events to [`FlyCamera::input`] and
+/// device events to [`FlyCamera::device_input`], report [`FlyCamera::cursor_grabbed`]
+/// from `App::cursor_grabbed` and call [`FlyCamera::update`] once per frame.
+#[derive(Clone, Debug)]
+pub struct FlyCamera {
+    pub position: Point3<f32>,
+    /// Rotation about +y; 0 looks down -z.
+    pub yaw: Rad<f32>,
+    /// Angle above the horizon.
+    pub pitch: Rad<f32>,
+    pub settings: FlySettings,
+    keys: HeldKeys,
+    grabbed: bool,
+}
+
+impl FlyCamera {
+    pub fn new(position: Point3<f32>, yaw: Rad<f32>, pitch: Rad<f32>) -> Self {
+        let mut camera = Self {
+            position,
+            yaw,
+            pitch,
+            settings: FlySettings::default(),
+            keys: HeldKeys::default(),
+            grabbed: false,
+        };
+        camera.look(0.0, 0.0);
+        camera
+    }
+
+    /// A camera at `position` facing `target`.
+    pub fn looking_at(position: Point3<f32>, target: Point3<f32>) -> Self {
+        let direction = (target - position).normalize();
+        let yaw = Rad((-direction.x).atan2(-direction.z));
+        let pitch = Rad(direction.y.clamp(-1.0, 1.0).asin());
+        Self::new(position, yaw, pitch)
+    }
+
+    pub fn forward(&self) -> Vector3<f32> {
+        transforms::fly_direction(self.yaw, self.pitch)
+    }
+
+    /// Horizontal right vector; strafing never changes height.
+    pub fn right(&self) -> Vector3<f32> {
+        let (sin, cos) = self.yaw.sin_cos();
+        Vector3::new(cos, 0.0, -sin)
+    }
+
+    /// The view matrix, built with [`transforms::create_fly_view`].
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        transforms::create_fly_view(self.position, self.yaw, self.pitch)
+    }
+
+    /// Whether the cursor should be hidden and locked for mouse look.
+    pub fn cursor_grabbed(&self) -> bool {
+        self.grabbed
+    }
+
+    /// Turns by a mouse offset: positive `dx` looks right, positive `dy` looks down.
+    pub fn look(&mut self, dx: f32, dy: f32) {
+        let s = self.settings.look_sensitivity;
+        self.yaw = Rad((self.yaw.0 - dx * s) % std::f32::consts::TAU);
+        let max = self.settings.max_pitch.0;
+        self.pitch = Rad((self.pitch.0 - dy * s).clamp(-max, max));
+    }
+
+    /// Tracks a movement key. Returns `true` if `code` is one of the camera's keys.
+    pub fn key(&mut self, code: KeyCode, state: ElementState) -> bool {
+        let pressed = state == ElementState::Pressed;
+        let key = match code {
+            KeyCode::KeyW => &mut self.keys.forward,
+            KeyCode::KeyS => &mut self.keys.back,
+            KeyCode::KeyA => &mut self.keys.left,
+            KeyCode::KeyD => &mut self.keys.right,
+            KeyCode::KeyE => &mut self.keys.up,
+            KeyCode::KeyQ => &mut self.keys.down,
+            KeyCode::ShiftLeft | KeyCode::ShiftRight => &mut self.keys.sprint,
+            _ => return false,
+        };
+        *key = pressed;
+        true
+    }
+
+    /// Handles clicks, keys and focus changes. Returns `true` if the event was used;
+    /// Escape is only consumed while the cursor is grabbed, so it still closes the
+    /// window otherwise.
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } => {
+                self.grabbed = true;
+                true
+            }
+            WindowEvent::Focused(false) => {
+                self.grabbed = false;
+                self.keys = HeldKeys::default();
+                false
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        ..
+                    },
+                ..
+            } => {
+                if *code == KeyCode::Escape && self.grabbed {
+                    if *state == ElementState::Pressed {
+                        self.grabbed = false;
+                    }
+                    return true;
+                }
+                self.key(*code, *state)
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies raw mouse motion while the cursor is grabbed.
+    pub fn device_input(&mut self, event: &DeviceEvent) {
+        if let DeviceEvent::MouseMotion { delta } = event {
+            if self.grabbed {
+                self.look(delta.0 as f32, delta.1 as f32);
+            }
+        }
+    }
+
+    /// Moves along the held directions for a frame of length `dt`.
+    pub fn update(&mut self, dt: Duration) {
+        let axis = |positive: bool, negative: bool| positive as i32 as f32 - negative as i32 as f32;
+        let direction = self.forward() * axis(self.keys.forward, self.keys.back)
+            + self.right() * axis(self.keys.right, self.keys.left)
+            + Vector3::unit_y() * axis(self.keys.up, self.keys.down);
+        if direction.magnitude2() == 0.0 {
+            return;
+        }
+        let mut speed = self.settings.speed;
+        if self.keys.sprint {
+            speed *= self.settings.sprint_multiplier;
+        }
+        self.position += direction.normalize() * speed * dt.as_secs_f32();
+    }
+}
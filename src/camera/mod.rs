@@ -0,0 +1,6 @@
+//! Interactive cameras driven by winit window events.
+pub mod fly;
+pub mod orbit;
+
+pub use fly::{FlyCamera, FlySettings};
+pub use orbit::{OrbitCamera, OrbitMode, OrbitSettings};
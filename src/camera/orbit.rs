@@ -0,0 +1,343 @@
+//! A camera orbiting a target point: rotate with the left mouse button or the arrow
+//! keys, pan with the right or middle button (or Shift + left), dolly with the wheel
+//! or PageUp/PageDown.
+use cgmath::*;
+use std::time::Duration;
+use winit::{
+    dpi::{PhysicalPosition, PhysicalSize},
+    event::{ElementState, KeyEvent, MouseButton, MouseScrollDelta, WindowEvent},
+    keyboard::{KeyCode, ModifiersState, PhysicalKey},
+};
+
+use crate::transforms;
+
+// wheel lines reported for one line of a pixel-precise touchpad scroll
+const PIXELS_PER_LINE: f32 = 20.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OrbitMode {
+    /// Yaw around the world y axis and clamped pitch, so the horizon stays level.
+    Turntable,
+    /// Free rotation that follows the cursor on a virtual sphere.
+    Arcball,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrbitSettings {
+    /// Radians per pixel of cursor movement while rotating in turntable mode.
+    pub rotate_sensitivity: f32,
+    /// Multiplier on the arcball angle; 1 keeps the grabbed point under the cursor.
+    pub arcball_sensitivity: f32,
+    /// Fraction of the target distance panned per pixel.
+    pub pan_sensitivity: f32,
+    /// Fraction of the target distance dollied per wheel line.
+    pub zoom_sensitivity: f32,
+    /// Radians per second while an arrow key is held.
+    pub key_rotate_speed: f32,
+    /// Wheel lines per second while PageUp/PageDown is held.
+    pub key_zoom_speed: f32,
+    /// Turntable pitch limits; keep them inside ±90° so the view never flips.
+    pub min_pitch: Rad<f32>,
+    pub max_pitch: Rad<f32>,
+    pub min_distance: f32,
+    pub max_distance: f32,
+}
+
+impl Default for OrbitSettings {
+    fn default() -> Self {
+        Self {
+            rotate_sensitivity: 0.01,
+            arcball_sensitivity: 1.0,
+            pan_sensitivity: 0.002,
+            zoom_sensitivity: 0.1,
+            key_rotate_speed: 1.5,
+            key_zoom_speed: 5.0,
+            min_pitch: Deg(-89.0).into(),
+            max_pitch: Deg(89.0).into(),
+            min_distance: 0.1,
+            max_distance: 1000.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct HeldKeys {
+    left: bool,
+    right: bool,
+    up: bool,
+    down: bool,
+    zoom_in: bool,
+    zoom_out: bool,
+}
+
+/// Orbit camera state plus the input it is currently tracking. Feed it window events
+/// through [`OrbitCamera::input`], call [`OrbitCamera::update`] once per frame and read
+/// back [`OrbitCamera::view_matrix`].
+#[derive(Clone, Debug)]
+pub struct OrbitCamera {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    /// Turntable angles; `yaw` 0 looks down -z, positive `pitch` raises the camera.
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub settings: OrbitSettings,
+    mode: OrbitMode,
+    // camera-to-world rotation used in arcball mode
+    orientation: Quaternion<f32>,
+    viewport: PhysicalSize<u32>,
+    cursor: Option<PhysicalPosition<f64>>,
+    modifiers: ModifiersState,
+    rotating: bool,
+    panning: bool,
+    keys: HeldKeys,
+}
+
+impl OrbitCamera {
+    /// A turntable camera at `eye` looking at `target`, for a viewport of `size`.
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, size: PhysicalSize<u32>) -> Self {
+        let settings = OrbitSettings::default();
+        let offset = eye - target;
+        let distance = offset.magnitude().max(settings.min_distance);
+        let yaw = Rad(offset.x.atan2(offset.z));
+        let pitch = Rad((offset.y / distance).clamp(-1.0, 1.0).asin());
+        let mut camera = Self {
+            target,
+            distance,
+            yaw,
+            pitch,
+            settings,
+            mode: OrbitMode::Turntable,
+            orientation: Quaternion::one(),
+            viewport: size,
+            cursor: None,
+            modifiers: ModifiersState::empty(),
+            rotating: false,
+            panning: false,
+            keys: HeldKeys::default(),
+        };
+        camera.clamp();
+        camera
+    }
+
+    pub fn mode(&self) -> OrbitMode {
+        self.mode
+    }
+
+    /// Switches between turntable and arcball rotation, keeping the current view.
+    pub fn set_mode(&mut self, mode: OrbitMode) {
+        if mode == self.mode {
+            return;
+        }
+        match mode {
+            OrbitMode::Arcball => self.orientation = self.rotation(),
+            OrbitMode::Turntable => {
+                let back = self.rotation().rotate_vector(Vector3::unit_z());
+                self.yaw = Rad(back.x.atan2(back.z));
+                self.pitch = Rad(back.y.clamp(-1.0, 1.0).asin());
+            }
+        }
+        self.mode = mode;
+        self.clamp();
+    }
+
+    /// The viewport size used to scale arcball drags; also tracked from `Resized` events.
+    pub fn resize(&mut self, size: PhysicalSize<u32>) {
+        self.viewport = size;
+    }
+
+    /// Camera-to-world rotation.
+    pub fn rotation(&self) -> Quaternion<f32> {
+        match self.mode {
+            OrbitMode::Turntable => {
+                Quaternion::from_angle_y(self.yaw) * Quaternion::from_angle_x(-self.pitch)
+            }
+            OrbitMode::Arcball => self.orientation,
+        }
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        self.target
+            + self
+                .rotation()
+                .rotate_vector(Vector3::unit_z() * self.distance)
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.rotation().rotate_vector(Vector3::unit_y())
+    }
+
+    /// The view matrix, built with [`transforms::create_view`].
+    pub fn view_matrix(&self) -> Matrix4<f32> {
+        transforms::create_view(self.eye(), self.target, self.up())
+    }
+
+    /// Rotates around the target: `yaw` about the up axis, `pitch` about the camera's
+    /// right axis.
+    pub fn orbit(&mut self, yaw: Rad<f32>, pitch: Rad<f32>) {
+        match self.mode {
+            OrbitMode::Turntable => {
+                self.yaw += yaw;
+                self.pitch += pitch;
+                self.clamp();
+            }
+            OrbitMode::Arcball => {
+                self.orientation = (self.orientation
+                    * Quaternion::from_angle_y(yaw)
+                    * Quaternion::from_angle_x(-pitch))
+                .normalize();
+            }
+        }
+    }
+
+    /// Moves the target (and the camera with it) by a cursor offset in pixels, scaled
+    /// by the distance so the scene follows the cursor at any zoom.
+    pub fn pan(&mut self, dx: f32, dy: f32) {
+        let rotation = self.rotation();
+        let right = rotation.rotate_vector(Vector3::unit_x());
+        let up = rotation.rotate_vector(Vector3::unit_y());
+        let scale = self.distance * self.settings.pan_sensitivity;
+        self.target += (up * dy - right * dx) * scale;
+    }
+
+    /// Moves towards the target by `lines` wheel lines; negative values move away.
+    pub fn dolly(&mut self, lines: f32) {
+        self.distance *= (1.0 - self.settings.zoom_sensitivity).powf(lines);
+        self.clamp();
+    }
+
+    /// Handles mouse and keyboard events. Returns `true` if the event was used.
+    pub fn input(&mut self, event: &WindowEvent) -> bool {
+        match event {
+            WindowEvent::Resized(size) => {
+                self.resize(*size);
+                false
+            }
+            WindowEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = modifiers.state();
+                false
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let pressed = *state == ElementState::Pressed;
+                match button {
+                    MouseButton::Left if pressed && self.modifiers.shift_key() => {
+                        self.panning = true
+                    }
+                    MouseButton::Left if pressed => self.rotating = true,
+                    MouseButton::Left => {
+                        self.rotating = false;
+                        self.panning = false;
+                    }
+                    MouseButton::Right | MouseButton::Middle => self.panning = pressed,
+                    _ => return false,
+                }
+                true
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let previous = self.cursor.replace(*position);
+                let Some(previous) = previous else {
+                    return false;
+                };
+                let dx = (position.x - previous.x) as f32;
+                let dy = (position.y - previous.y) as f32;
+                if self.panning {
+                    self.pan(dx, dy);
+                } else if self.rotating {
+                    match self.mode {
+                        OrbitMode::Turntable => {
+                            let s = self.settings.rotate_sensitivity;
+                            self.orbit(Rad(-dx * s), Rad(dy * s));
+                        }
+                        OrbitMode::Arcball => self.arcball(previous, *position),
+                    }
+                }
+                self.rotating || self.panning
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                false
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let lines = match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+                };
+                self.dolly(lines);
+                true
+            }
+            WindowEvent::KeyboardInput {
+                event:
+                    KeyEvent {
+                        physical_key: PhysicalKey::Code(code),
+                        state,
+                        ..
+                    },
+                ..
+            } => {
+                let pressed = *state == ElementState::Pressed;
+                let key = match code {
+                    KeyCode::ArrowLeft => &mut self.keys.left,
+                    KeyCode::ArrowRight => &mut self.keys.right,
+                    KeyCode::ArrowUp => &mut self.keys.up,
+                    KeyCode::ArrowDown => &mut self.keys.down,
+                    KeyCode::PageUp => &mut self.keys.zoom_in,
+                    KeyCode::PageDown => &mut self.keys.zoom_out,
+                    _ => return false,
+                };
+                *key = pressed;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies held keys for a frame of length `dt`.
+    pub fn update(&mut self, dt: Duration) {
+        let dt = dt.as_secs_f32();
+        let axis = |positive: bool, negative: bool| positive as i32 as f32 - negative as i32 as f32;
+        let yaw = axis(self.keys.left, self.keys.right);
+        let pitch = axis(self.keys.up, self.keys.down);
+        if yaw != 0.0 || pitch != 0.0 {
+            let step = self.settings.key_rotate_speed * dt;
+            self.orbit(Rad(yaw * step), Rad(pitch * step));
+        }
+        let zoom = axis(self.keys.zoom_in, self.keys.zoom_out);
+        if zoom != 0.0 {
+            self.dolly(zoom * self.settings.key_zoom_speed * dt);
+        }
+    }
+
+    // rotate so that the point of the virtual sphere under `from` moves under `to`
+    fn arcball(&mut self, from: PhysicalPosition<f64>, to: PhysicalPosition<f64>) {
+        let (a, b) = (self.sphere_point(from), self.sphere_point(to));
+        let axis = a.cross(b);
+        if axis.magnitude2() < 1e-12 {
+            return;
+        }
+        let angle = a.dot(b).clamp(-1.0, 1.0).acos() * self.settings.arcball_sensitivity;
+        // the scene turns with the cursor, so the camera turns the other way
+        self.orientation = (self.orientation
+            * Quaternion::from_axis_angle(axis.normalize(), Rad(-angle)))
+        .normalize();
+    }
+
+    // cursor position on a unit sphere filling the viewport, in camera space
+    fn sphere_point(&self, position: PhysicalPosition<f64>) -> Vector3<f32> {
+        let (w, h) = (self.viewport.width as f32, self.viewport.height as f32);
+        let radius = 0.5 * w.min(h).max(1.0);
+        let x = (position.x as f32 - 0.5 * w) / radius;
+        let y = (0.5 * h - position.y as f32) / radius;
+        let d2 = x * x + y * y;
+        if d2 <= 1.0 {
+            Vector3::new(x, y, (1.0 - d2).sqrt())
+        } else {
+            let d = d2.sqrt();
+            Vector3::new(x / d, y / d, 0.0)
+        }
+    }
+
+    fn clamp(&mut self) {
+        let s = &self.settings;
+        self.pitch = Rad(self.pitch.0.clamp(s.min_pitch.0, s.max_pitch.0));
+        self.distance = self.distance.clamp(s.min_distance, s.max_distance);
+    }
+}
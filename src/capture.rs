@@ -0,0 +1,111 @@
+//! Reading rendered frames back to the CPU and saving them as PNG files.
+use std::path::Path;
+use std::sync::mpsc;
+
+/// Row pitch for copying `width` pixels out of a texture: rows must be padded to a
+/// multiple of `COPY_BYTES_PER_ROW_ALIGNMENT`.
+pub fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let unpadded = width * bytes_per_pixel;
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    unpadded.div_ceil(align) * align
+}
+
+/// Copies a 2D render target into a mapped buffer and returns its pixels as RGBA8.
+/// The texture must have been created with `TextureUsages::COPY_SRC` and use an
+/// 8-bit RGBA or BGRA format.
+pub fn read_texture(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+) -> anyhow::Result<image::RgbaImage> {
+    read_texture_level(device, queue, texture, 0)
+}
+
+/// Like [`read_texture`], for one mip level of the texture.
+pub fn read_texture_level(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    mip_level: u32,
+) -> anyhow::Result<image::RgbaImage> {
+    let format = texture.format();
+    let swap_red_blue = match format {
+        wgpu::TextureFormat::Rgba8Unorm | wgpu::TextureFormat::Rgba8UnormSrgb => false,
+        wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => true,
+        _ => anyhow::bail!("unsupported capture format {:?}", format),
+    };
+
+    let size = texture
+        .size()
+        .mip_level_size(mip_level, texture.dimension());
+    let (width, height) = (size.width, size.height);
+    let unpadded_bytes_per_row = width * 4;
+    let padded_bytes_per_row = padded_bytes_per_row(width, 4);
+
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Capture Buffer"),
+        size: (padded_bytes_per_row * height) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Capture Encoder"),
+    });
+    encoder.copy_texture_to_buffer(
+        wgpu::ImageCopyTexture {
+            texture,
+            mip_level,
+            origin: wgpu::Origin3d::ZERO,
+            aspect: wgpu::TextureAspect::All,
+        },
+        wgpu::ImageCopyBuffer {
+            buffer: &buffer,
+            layout: wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: Some(padded_bytes_per_row),
+                rows_per_image: Some(height),
+            },
+        },
+        size,
+    );
+    queue.submit(Some(encoder.finish()));
+
+    let slice = buffer.slice(..);
+    let (sender, receiver) = mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device.poll(wgpu::Maintain::Wait);
+    receiver.recv()??;
+
+    let mut pixels = Vec::with_capacity((unpadded_bytes_per_row * height) as usize);
+    {
+        let data = slice.get_mapped_range();
+        for row in data.chunks(padded_bytes_per_row as usize) {
+            pixels.extend_from_slice(&row[..unpadded_bytes_per_row as usize]);
+        }
+    }
+    buffer.unmap();
+
+    if swap_red_blue {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    image::RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| anyhow::anyhow!("capture buffer size does not match {}x{}", width, height))
+}
+
+/// Reads back a render target and writes it to a PNG file.
+pub fn save_png<P: AsRef<Path>>(
+    device: &wgpu::Device,
+    queue: &wgpu::Queue,
+    texture: &wgpu::Texture,
+    path: P,
+) -> anyhow::Result<()> {
+    let image = read_texture(device, queue, texture)?;
+    image.save_with_format(path, image::ImageFormat::Png)?;
+    Ok(())
+}
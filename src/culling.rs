@@ -0,0 +1,96 @@
+//! View frustum planes extracted from a view-projection matrix, and tests of bounding
+//! volumes against them to skip objects that are off screen.
+use cgmath::*;
+
+use crate::geometry::{Aabb, BoundingSphere};
+
+/// The plane `normal · p + distance = 0`. Points on the side the normal points to have a
+/// positive signed distance and count as inside.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub distance: f32,
+}
+
+impl Plane {
+    /// Plane through `point` facing along `normal`, which must be unit length.
+    pub fn new(normal: Vector3<f32>, point: Point3<f32>) -> Self {
+        Self {
+            normal,
+            distance: -normal.dot(point.to_vec()),
+        }
+    }
+
+    /// Plane with the equation `x·p.x + y·p.y + z·p.z + w ≥ 0` for the inside, scaled so
+    /// the normal has unit length. A zero normal is left as is; such a plane holds
+    /// everything when `w` is positive, as the far plane of an infinite projection does.
+    pub fn from_coefficients(coefficients: Vector4<f32>) -> Self {
+        let normal = coefficients.truncate();
+        let length = normal.magnitude();
+        let scale = if length > f32::EPSILON {
+            1.0 / length
+        } else {
+            1.0
+        };
+        Self {
+            normal: normal * scale,
+            distance: coefficients.w * scale,
+        }
+    }
+
+    pub fn signed_distance(&self, point: Point3<f32>) -> f32 {
+        self.normal.dot(point.to_vec()) + self.distance
+    }
+}
+
+/// The six planes bounding what a view-projection matrix maps into wgpu clip space,
+/// all facing inwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, then the planes at depth 0 and depth 1: near and far,
+    /// or far and near with reverse-z.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Extracts the planes from a view-projection matrix such as the third one returned
+    /// by [`create_view_projection`](crate::transforms::create_view_projection). A point
+    /// is visible when `-w ≤ x ≤ w`, `-w ≤ y ≤ w` and `0 ≤ z ≤ w` in clip space, and
+    /// each of those bounds is a plane in the space the matrix maps from: world space
+    /// for a view-projection matrix, object space when the model matrix is included.
+    pub fn from_matrix(view_projection: Matrix4<f32>) -> Self {
+        let m = view_projection;
+        let (x, y, z, w) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        Self {
+            planes: [w + x, w - x, w + y, w - y, z, w - z].map(Plane::from_coefficients),
+        }
+    }
+
+    pub fn contains_point(&self, point: Point3<f32>) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(point) >= 0.0)
+    }
+
+    /// Whether any part of the sphere may be visible. Spheres near a corner of the
+    /// frustum may pass while lying just outside it, which only costs a wasted draw.
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) >= -sphere.radius)
+    }
+
+    /// Whether any part of the box may be visible, testing for each plane the corner
+    /// furthest along its normal. Like the sphere test it errs on the side of drawing.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let pick = |normal: f32, min: f32, max: f32| if normal >= 0.0 { max } else { min };
+        self.planes.iter().all(|plane| {
+            let corner = Point3::new(
+                pick(plane.normal.x, aabb.min.x, aabb.max.x),
+                pick(plane.normal.y, aabb.min.y, aabb.max.y),
+                pick(plane.normal.z, aabb.min.z, aabb.max.z),
+            );
+            plane.signed_distance(corner) >= 0.0
+        })
+    }
+}
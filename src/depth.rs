@@ -0,0 +1,148 @@
+//! Depth buffers that live as long as the render state and are only reallocated when
+//! the surface is resized.
+
+/// A depth (or depth-stencil) render target matching the size of the color target.
+///
+/// With `reverse_z` the buffer is cleared to 0 and compared with `GreaterEqual`, to pair
+/// with [`Projection::with_reverse_z`](crate::transforms::Projection::with_reverse_z).
+pub struct DepthTarget {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub reverse_z: bool,
+}
+
+impl DepthTarget {
+    /// Creates the target. `format` must have a depth aspect, e.g. `Depth24Plus`,
+    /// `Depth32Float` or `Depth24PlusStencil8`.
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        Self::multisampled(device, width, height, format, 1)
+    }
+
+    /// A target for passes whose color attachment has `sample_count` samples, see
+    /// [`MsaaTarget`](crate::msaa::MsaaTarget).
+    pub fn multisampled(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        assert!(
+            format.has_depth_aspect(),
+            "{:?} is not a depth format",
+            format
+        );
+        let texture = create_depth_texture(device, width, height, format, sample_count);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            reverse_z: false,
+        }
+    }
+
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
+
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.texture.format()
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.texture.sample_count()
+    }
+
+    /// Recreates the texture if the size changed; zero sizes are ignored. Returns
+    /// whether a new texture was allocated.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        if width == 0
+            || height == 0
+            || (width, height) == (self.texture.width(), self.texture.height())
+        {
+            return false;
+        }
+        self.texture =
+            create_depth_texture(device, width, height, self.format(), self.sample_count());
+        self.view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        true
+    }
+
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        if self.reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        }
+    }
+
+    pub fn clear_value(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Depth state for pipelines drawing into this target, writing depth and testing
+    /// with [`DepthTarget::depth_compare`].
+    pub fn depth_stencil_state(&self) -> wgpu::DepthStencilState {
+        wgpu::DepthStencilState {
+            format: self.format(),
+            depth_write_enabled: true,
+            depth_compare: self.depth_compare(),
+            stencil: wgpu::StencilState::default(),
+            bias: wgpu::DepthBiasState::default(),
+        }
+    }
+
+    /// Attachment that clears depth to [`DepthTarget::clear_value`], and stencil to 0
+    /// for formats that have one.
+    pub fn attachment(&self) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.view,
+            depth_ops: Some(wgpu::Operations {
+                load: wgpu::LoadOp::Clear(self.clear_value()),
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: self
+                .format()
+                .has_stencil_aspect()
+                .then_some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(0),
+                    store: wgpu::StoreOp::Store,
+                }),
+        }
+    }
+}
+
+fn create_depth_texture(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Depth Texture"),
+        size: wgpu::Extent3d {
+            width: width.max(1),
+            height: height.max(1),
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,118 @@
+//! Axis-aligned boxes and spheres enclosing a set of points, for culling and picking.
+use cgmath::*;
+
+/// Axis-aligned bounding box given by its two extreme corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// The smallest box holding every point, or `None` without points.
+    pub fn from_points<I: IntoIterator<Item = Point3<f32>>>(points: I) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, p| Self {
+            min: Point3::new(
+                aabb.min.x.min(p.x),
+                aabb.min.y.min(p.y),
+                aabb.min.z.min(p.z),
+            ),
+            max: Point3::new(
+                aabb.max.x.max(p.x),
+                aabb.max.y.max(p.y),
+                aabb.max.z.max(p.z),
+            ),
+        }))
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    /// Half the size of the box along each axis.
+    pub fn half_extents(&self) -> Vector3<f32> {
+        (self.max - self.min) / 2.0
+    }
+
+    pub fn contains(&self, point: Point3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    /// The eight corners, with bit 0 of the index choosing max x, bit 1 max y and
+    /// bit 2 max z.
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        std::array::from_fn(|i| {
+            Point3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        })
+    }
+
+    /// The box around this one after an affine transform. It holds the transformed box
+    /// but is larger than it under rotation.
+    pub fn transform(&self, matrix: Matrix4<f32>) -> Self {
+        let center = matrix.transform_point(self.center());
+        let e = self.half_extents();
+        // each new half extent sums the absolute contributions of the old ones
+        let half = Vector3::new(
+            matrix.x.x.abs() * e.x + matrix.y.x.abs() * e.y + matrix.z.x.abs() * e.z,
+            matrix.x.y.abs() * e.x + matrix.y.y.abs() * e.y + matrix.z.y.abs() * e.z,
+            matrix.x.z.abs() * e.x + matrix.y.z.abs() * e.y + matrix.z.z.abs() * e.z,
+        );
+        Self::new(center - half, center + half)
+    }
+}
+
+/// Sphere given by its centre and radius.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Point3<f32>, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// A sphere around the centre of the points' [`Aabb`], just large enough to hold
+    /// them all. Not the smallest possible sphere, but close for the usual meshes.
+    pub fn from_points<I>(points: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Point3<f32>>,
+        I::IntoIter: Clone,
+    {
+        let points = points.into_iter();
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points.map(|p| p.distance2(center)).fold(0.0, f32::max);
+        Some(Self::new(center, radius.sqrt()))
+    }
+
+    pub fn contains(&self, point: Point3<f32>) -> bool {
+        point.distance2(self.center) <= self.radius * self.radius
+    }
+
+    /// The sphere after an affine transform, grown by the largest axis scale so it
+    /// still holds the transformed points under non-uniform scale.
+    pub fn transform(&self, matrix: Matrix4<f32>) -> Self {
+        let scale = [matrix.x, matrix.y, matrix.z]
+            .iter()
+            .map(|axis| axis.truncate().magnitude())
+            .fold(0.0, f32::max);
+        Self::new(matrix.transform_point(self.center), self.radius * scale)
+    }
+}
+
+impl From<Aabb> for BoundingSphere {
+    fn from(aabb: Aabb) -> Self {
+        Self::new(aabb.center(), aabb.half_extents().magnitude())
+    }
+}
@@ -0,0 +1,397 @@
+//! glTF 2.0 import from `.gltf` (with external or embedded buffers) and `.glb` files.
+//!
+//! Only local files and data URIs are read. Every primitive becomes an indexed [`Mesh`],
+//! nodes keep their local and world matrices, and materials expose the
+//! metallic-roughness parameters.
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+use cgmath::{Matrix4, SquareMatrix};
+use gltf::mesh::util::ReadIndices;
+use gltf::mesh::Mode;
+
+use super::mesh::{Indices, Mesh};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+    pub materials: Vec<PbrMaterial>,
+    pub images: Vec<GltfImage>,
+    pub nodes: Vec<GltfNode>,
+    /// Root nodes of the default scene, or of all nodes without a parent if the file
+    /// has no scenes.
+    pub roots: Vec<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfMesh {
+    pub name: Option<String>,
+    pub primitives: Vec<GltfPrimitive>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfPrimitive {
+    pub mesh: Mesh,
+    /// Index into [`GltfScene::materials`], `None` for the default material.
+    pub material: Option<usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GltfNode {
+    pub name: Option<String>,
+    pub parent: Option<usize>,
+    pub children: Vec<usize>,
+    /// Index into [`GltfScene::meshes`].
+    pub mesh: Option<usize>,
+    /// Translation · rotation · scale relative to the parent.
+    pub local: Matrix4<f32>,
+    /// `local` premultiplied by every ancestor's transform.
+    pub world: Matrix4<f32>,
+}
+
+/// Where the encoded data of an image lives.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GltfImage {
+    File(PathBuf),
+    Embedded { bytes: Vec<u8>, mime_type: String },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlphaMode {
+    Opaque,
+    Mask,
+    Blend,
+}
+
+/// A texture reference: the image index and the uv set it is sampled with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextureRef {
+    pub image: usize,
+    pub tex_coord: u32,
+}
+
+/// Metallic-roughness material parameters with the glTF defaults filled in.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PbrMaterial {
+    pub name: Option<String>,
+    pub base_color_factor: [f32; 4],
+    pub base_color_texture: Option<TextureRef>,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    /// Metalness in the blue channel, roughness in the green one.
+    pub metallic_roughness_texture: Option<TextureRef>,
+    pub normal_texture: Option<TextureRef>,
+    pub normal_scale: f32,
+    pub occlusion_texture: Option<TextureRef>,
+    pub occlusion_strength: f32,
+    pub emissive_factor: [f32; 3],
+    pub emissive_texture: Option<TextureRef>,
+    pub alpha_mode: AlphaMode,
+    pub alpha_cutoff: f32,
+    pub double_sided: bool,
+}
+
+impl Default for PbrMaterial {
+    fn default() -> Self {
+        PbrMaterial {
+            name: None,
+            base_color_factor: [1.0; 4],
+            base_color_texture: None,
+            metallic_factor: 1.0,
+            roughness_factor: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            normal_scale: 1.0,
+            occlusion_texture: None,
+            occlusion_strength: 1.0,
+            emissive_factor: [0.0; 3],
+            emissive_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+            alpha_cutoff: 0.5,
+            double_sided: false,
+        }
+    }
+}
+
+impl GltfScene {
+    /// Every node that references a mesh, paired with its world matrix, in depth-first
+    /// order from the roots.
+    pub fn mesh_instances(&self) -> Vec<(usize, Matrix4<f32>)> {
+        let mut instances = Vec::new();
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if let Some(mesh) = node.mesh {
+                instances.push((mesh, node.world));
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        instances
+    }
+}
+
+/// Loads a `.gltf` or `.glb` file; external buffers and images are resolved relative
+/// to it.
+pub fn load_gltf<P: AsRef<Path>>(path: P) -> anyhow::Result<GltfScene> {
+    let path = path.as_ref();
+    let bytes = std::fs::read(path).with_context(|| format!("reading {}", path.display()))?;
+    import_gltf(&bytes, path.parent()).with_context(|| format!("importing {}", path.display()))
+}
+
+/// Imports glTF JSON or GLB bytes. Without a `base` directory only embedded and
+/// data-URI buffers can be read.
+pub fn import_gltf(bytes: &[u8], base: Option<&Path>) -> anyhow::Result<GltfScene> {
+    let gltf::Gltf { document, blob } = gltf::Gltf::from_slice(bytes)?;
+    let buffers = gltf::import_buffers(&document, base, blob)?;
+
+    let meshes = document
+        .meshes()
+        .map(|mesh| {
+            let primitives = mesh
+                .primitives()
+                .filter_map(|primitive| read_primitive(&primitive, &buffers).transpose())
+                .collect::<anyhow::Result<_>>()
+                .with_context(|| format!("mesh {}", mesh.index()))?;
+            Ok(GltfMesh {
+                name: mesh.name().map(str::to_string),
+                primitives,
+            })
+        })
+        .collect::<anyhow::Result<_>>()?;
+
+    let materials = document.materials().map(read_material).collect();
+
+    let images = document
+        .images()
+        .map(|image| match image.source() {
+            gltf::image::Source::View { view, mime_type } => {
+                let data = &buffers[view.buffer().index()];
+                GltfImage::Embedded {
+                    bytes: data[view.offset()..view.offset() + view.length()].to_vec(),
+                    mime_type: mime_type.to_string(),
+                }
+            }
+            gltf::image::Source::Uri { uri, .. } => {
+                GltfImage::File(base.unwrap_or(Path::new("")).join(uri))
+            }
+        })
+        .collect();
+
+    let mut nodes: Vec<GltfNode> = document
+        .nodes()
+        .map(|node| GltfNode {
+            name: node.name().map(str::to_string),
+            parent: None,
+            children: node.children().map(|child| child.index()).collect(),
+            mesh: node.mesh().map(|mesh| mesh.index()),
+            local: Matrix4::from(node.transform().matrix()),
+            world: Matrix4::identity(),
+        })
+        .collect();
+    for index in 0..nodes.len() {
+        for child in nodes[index].children.clone() {
+            if nodes[child].parent.is_some() {
+                bail!("node {} has more than one parent", child);
+            }
+            nodes[child].parent = Some(index);
+        }
+    }
+    // with one parent each, a node is in a cycle when its parent chain comes back to
+    // a node already on it; chains that reach a node checked earlier can stop there
+    let mut acyclic = vec![false; nodes.len()];
+    for start in 0..nodes.len() {
+        let mut chain = Vec::new();
+        let mut next = Some(start);
+        while let Some(index) = next.filter(|&index| !acyclic[index]) {
+            if chain.contains(&index) {
+                bail!("node {} is part of a cycle", index);
+            }
+            chain.push(index);
+            next = nodes[index].parent;
+        }
+        for index in chain {
+            acyclic[index] = true;
+        }
+    }
+
+    let roots: Vec<usize> = match document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+    {
+        Some(scene) => scene.nodes().map(|node| node.index()).collect(),
+        None => (0..nodes.len())
+            .filter(|&i| nodes[i].parent.is_none())
+            .collect(),
+    };
+
+    // propagate world matrices top-down from every parentless node, so nodes outside
+    // the chosen scene still get correct matrices
+    let mut stack: Vec<(usize, Matrix4<f32>)> = (0..nodes.len())
+        .filter(|&i| nodes[i].parent.is_none())
+        .map(|i| (i, Matrix4::identity()))
+        .collect();
+    while let Some((index, parent_world)) = stack.pop() {
+        let node = &mut nodes[index];
+        node.world = parent_world * node.local;
+        let world = node.world;
+        stack.extend(node.children.iter().map(|&child| (child, world)));
+    }
+
+    Ok(GltfScene {
+        meshes,
+        materials,
+        images,
+        nodes,
+        roots,
+    })
+}
+
+// reads one primitive into a triangle-list mesh; points and lines are skipped
+fn read_primitive(
+    primitive: &gltf::Primitive<'_>,
+    buffers: &[gltf::buffer::Data],
+) -> anyhow::Result<Option<GltfPrimitive>> {
+    let mode = primitive.mode();
+    if !matches!(
+        mode,
+        Mode::Triangles | Mode::TriangleStrip | Mode::TriangleFan
+    ) {
+        log::warn!(
+            "skipping primitive {} with mode {:?}",
+            primitive.index(),
+            mode
+        );
+        return Ok(None);
+    }
+
+    let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+    let Some(positions) = reader.read_positions() else {
+        bail!("primitive {} has no POSITION attribute", primitive.index());
+    };
+    let positions: Vec<[f32; 3]> = positions.collect();
+    let count = positions.len();
+    let check = |name: &str, len: usize| {
+        if len != count {
+            bail!(
+                "primitive {} has {} positions but {} {}",
+                primitive.index(),
+                count,
+                len,
+                name
+            );
+        }
+        Ok(())
+    };
+
+    let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+    let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0).map(|t| t.into_f32().collect());
+    let colors: Option<Vec<[f32; 4]>> = reader.read_colors(0).map(|c| c.into_rgba_f32().collect());
+    let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(Iterator::collect);
+    for (name, len) in [
+        ("normals", normals.as_ref().map(Vec::len)),
+        ("uvs", uvs.as_ref().map(Vec::len)),
+        ("colors", colors.as_ref().map(Vec::len)),
+        ("tangents", tangents.as_ref().map(Vec::len)),
+    ] {
+        if let Some(len) = len {
+            check(name, len)?;
+        }
+    }
+
+    let indices = match reader.read_indices() {
+        Some(ReadIndices::U8(iter)) => Indices::U16(iter.map(u16::from).collect()),
+        Some(ReadIndices::U16(iter)) => Indices::U16(iter.collect()),
+        Some(ReadIndices::U32(iter)) => Indices::U32(iter.collect()),
+        None => Indices::U32((0..count as u32).collect()),
+    };
+    if let Some(index) = indices.iter().find(|&i| i as usize >= count) {
+        bail!(
+            "primitive {} index {} out of range for {} vertices",
+            primitive.index(),
+            index,
+            count
+        );
+    }
+    let indices = match mode {
+        Mode::TriangleStrip => Indices::U32(strip_to_list(&indices.iter().collect::<Vec<_>>())),
+        Mode::TriangleFan => Indices::U32(fan_to_list(&indices.iter().collect::<Vec<_>>())),
+        _ => indices,
+    };
+
+    Ok(Some(GltfPrimitive {
+        mesh: Mesh {
+            positions,
+            normals,
+            uvs,
+            colors,
+            tangents,
+            indices: Some(indices),
+        },
+        material: primitive.material().index(),
+    }))
+}
+
+// flip every odd triangle so the whole strip keeps the winding of the first one
+fn strip_to_list(strip: &[u32]) -> Vec<u32> {
+    strip
+        .windows(3)
+        .enumerate()
+        .flat_map(|(i, w)| {
+            if i % 2 == 0 {
+                [w[0], w[1], w[2]]
+            } else {
+                [w[1], w[0], w[2]]
+            }
+        })
+        .collect()
+}
+
+fn fan_to_list(fan: &[u32]) -> Vec<u32> {
+    if fan.len() < 3 {
+        return Vec::new();
+    }
+    fan[1..]
+        .windows(2)
+        .flat_map(|w| [fan[0], w[0], w[1]])
+        .collect()
+}
+
+fn texture_ref(info: gltf::texture::Info<'_>) -> TextureRef {
+    TextureRef {
+        image: info.texture().source().index(),
+        tex_coord: info.tex_coord(),
+    }
+}
+
+fn read_material(material: gltf::Material<'_>) -> PbrMaterial {
+    let pbr = material.pbr_metallic_roughness();
+    let normal = material.normal_texture();
+    let occlusion = material.occlusion_texture();
+    PbrMaterial {
+        name: material.name().map(str::to_string),
+        base_color_factor: pbr.base_color_factor(),
+        base_color_texture: pbr.base_color_texture().map(texture_ref),
+        metallic_factor: pbr.metallic_factor(),
+        roughness_factor: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().map(texture_ref),
+        normal_scale: normal.as_ref().map_or(1.0, |n| n.scale()),
+        normal_texture: normal.map(|n| TextureRef {
+            image: n.texture().source().index(),
+            tex_coord: n.tex_coord(),
+        }),
+        occlusion_strength: occlusion.as_ref().map_or(1.0, |o| o.strength()),
+        occlusion_texture: occlusion.map(|o| TextureRef {
+            image: o.texture().source().index(),
+            tex_coord: o.tex_coord(),
+        }),
+        emissive_factor: material.emissive_factor(),
+        emissive_texture: material.emissive_texture().map(texture_ref),
+        alpha_mode: match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask,
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        },
+        alpha_cutoff: material.alpha_cutoff().unwrap_or(0.5),
+        double_sided: material.double_sided(),
+    }
+}
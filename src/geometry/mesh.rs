@@ -0,0 +1,280 @@
+//! Indexed meshes with optional vertex attributes, packed into one interleaved
+//! vertex buffer.
+use cgmath::Point3;
+use std::mem;
+use std::ops::Range;
+use wgpu::util::DeviceExt;
+
+use super::bounds::{Aabb, BoundingSphere};
+use super::vertex_data::MeshData;
+use crate::instance::InstanceBuffer;
+
+/// Triangle-list indices in either of the two wgpu index formats.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Indices {
+    U16(Vec<u16>),
+    U32(Vec<u32>),
+}
+
+impl Indices {
+    pub fn len(&self) -> usize {
+        match self {
+            Indices::U16(indices) => indices.len(),
+            Indices::U32(indices) => indices.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn format(&self) -> wgpu::IndexFormat {
+        match self {
+            Indices::U16(_) => wgpu::IndexFormat::Uint16,
+            Indices::U32(_) => wgpu::IndexFormat::Uint32,
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            Indices::U16(indices) => bytemuck::cast_slice(indices),
+            Indices::U32(indices) => bytemuck::cast_slice(indices),
+        }
+    }
+
+    /// Iterates over the indices widened to `u32`.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        match self {
+            Indices::U16(indices) => Box::new(indices.iter().map(|&i| i as u32)),
+            Indices::U32(indices) => Box::new(indices.iter().copied()),
+        }
+    }
+}
+
+/// A vertex attribute a [`Mesh`] can carry, in the order they are interleaved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attribute {
+    Position,
+    Normal,
+    Uv,
+    Color,
+    Tangent,
+}
+
+impl Attribute {
+    pub fn format(self) -> wgpu::VertexFormat {
+        match self {
+            Attribute::Position | Attribute::Normal => wgpu::VertexFormat::Float32x3,
+            Attribute::Uv => wgpu::VertexFormat::Float32x2,
+            Attribute::Color | Attribute::Tangent => wgpu::VertexFormat::Float32x4,
+        }
+    }
+}
+
+/// Vertex attributes of a mesh plus optional indices. Every present attribute must
+/// hold one entry per position.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Mesh {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Option<Vec<[f32; 3]>>,
+    pub uvs: Option<Vec<[f32; 2]>>,
+    pub colors: Option<Vec<[f32; 4]>>,
+    /// xyz tangent and w the bitangent sign.
+    pub tangents: Option<Vec<[f32; 4]>>,
+    pub indices: Option<Indices>,
+}
+
+/// Owned counterpart of `wgpu::VertexBufferLayout` describing a mesh's interleaved
+/// vertices, or per-instance data such as
+/// [`Instance::layout`](crate::instance::Instance::layout).
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshLayout {
+    pub array_stride: wgpu::BufferAddress,
+    pub step_mode: wgpu::VertexStepMode,
+    pub attributes: Vec<wgpu::VertexAttribute>,
+}
+
+impl MeshLayout {
+    pub fn buffer_layout(&self) -> wgpu::VertexBufferLayout<'_> {
+        wgpu::VertexBufferLayout {
+            array_stride: self.array_stride,
+            step_mode: self.step_mode,
+            attributes: &self.attributes,
+        }
+    }
+}
+
+/// Vertex and index buffers of a mesh uploaded with [`Mesh::upload`].
+pub struct GpuMesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: Option<(wgpu::Buffer, wgpu::IndexFormat)>,
+    pub vertex_count: u32,
+    pub index_count: u32,
+}
+
+impl GpuMesh {
+    /// Binds the buffers to vertex slot 0 and issues the draw call.
+    pub fn draw(&self, render_pass: &mut wgpu::RenderPass<'_>) {
+        self.draw_range(render_pass, 0..1);
+    }
+
+    /// Draws one copy of the mesh per instance in `instances`, which are bound to
+    /// vertex slot 1. Does nothing while the buffer is empty.
+    pub fn draw_instanced(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        instances: &InstanceBuffer,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        render_pass.set_vertex_buffer(1, instances.slice());
+        self.draw_range(render_pass, 0..instances.len() as u32);
+    }
+
+    fn draw_range(&self, render_pass: &mut wgpu::RenderPass<'_>, instances: Range<u32>) {
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        match &self.index_buffer {
+            Some((buffer, format)) => {
+                render_pass.set_index_buffer(buffer.slice(..), *format);
+                render_pass.draw_indexed(0..self.index_count, 0, instances);
+            }
+            None => render_pass.draw(0..self.vertex_count, instances),
+        }
+    }
+}
+
+impl Mesh {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Box around every position, or `None` for a mesh without vertices.
+    pub fn aabb(&self) -> Option<Aabb> {
+        Aabb::from_points(self.positions.iter().map(|&p| Point3::from(p)))
+    }
+
+    /// Sphere around every position, or `None` for a mesh without vertices.
+    pub fn bounding_sphere(&self) -> Option<BoundingSphere> {
+        BoundingSphere::from_points(self.positions.iter().map(|&p| Point3::from(p)))
+    }
+
+    /// The attributes present in this mesh, in interleaving order.
+    pub fn attributes(&self) -> Vec<Attribute> {
+        let mut attributes = vec![Attribute::Position];
+        if self.normals.is_some() {
+            attributes.push(Attribute::Normal);
+        }
+        if self.uvs.is_some() {
+            attributes.push(Attribute::Uv);
+        }
+        if self.colors.is_some() {
+            attributes.push(Attribute::Color);
+        }
+        if self.tangents.is_some() {
+            attributes.push(Attribute::Tangent);
+        }
+        attributes
+    }
+
+    /// Vertex layout matching [`Mesh::interleaved`]: present attributes get consecutive
+    /// shader locations starting at `first_location`.
+    pub fn layout(&self, first_location: u32) -> MeshLayout {
+        let mut offset = 0;
+        let attributes = self
+            .attributes()
+            .into_iter()
+            .zip(first_location..)
+            .map(|(attribute, shader_location)| {
+                let format = attribute.format();
+                let attribute = wgpu::VertexAttribute {
+                    format,
+                    offset,
+                    shader_location,
+                };
+                offset += format.size();
+                attribute
+            })
+            .collect();
+        MeshLayout {
+            array_stride: offset,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes,
+        }
+    }
+
+    /// Packs the present attributes of every vertex next to each other.
+    pub fn interleaved(&self) -> Vec<u8> {
+        let n = self.vertex_count();
+        let check = |name: &str, len: usize| {
+            assert_eq!(len, n, "mesh has {} positions but {} {}", n, len, name);
+        };
+        if let Some(normals) = &self.normals {
+            check("normals", normals.len());
+        }
+        if let Some(uvs) = &self.uvs {
+            check("uvs", uvs.len());
+        }
+        if let Some(colors) = &self.colors {
+            check("colors", colors.len());
+        }
+        if let Some(tangents) = &self.tangents {
+            check("tangents", tangents.len());
+        }
+
+        let stride = self.layout(0).array_stride as usize;
+        let mut data: Vec<f32> = Vec::with_capacity(n * stride / mem::size_of::<f32>());
+        for i in 0..n {
+            data.extend_from_slice(&self.positions[i]);
+            if let Some(normals) = &self.normals {
+                data.extend_from_slice(&normals[i]);
+            }
+            if let Some(uvs) = &self.uvs {
+                data.extend_from_slice(&uvs[i]);
+            }
+            if let Some(colors) = &self.colors {
+                data.extend_from_slice(&colors[i]);
+            }
+            if let Some(tangents) = &self.tangents {
+                data.extend_from_slice(&tangents[i]);
+            }
+        }
+        bytemuck::cast_slice(&data).to_vec()
+    }
+
+    /// Creates the vertex buffer and, for indexed meshes, the index buffer.
+    pub fn upload(&self, device: &wgpu::Device) -> GpuMesh {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Vertex Buffer"),
+            contents: &self.interleaved(),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = self.indices.as_ref().map(|indices| {
+            let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Mesh Index Buffer"),
+                contents: indices.as_bytes(),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (buffer, indices.format())
+        });
+        GpuMesh {
+            vertex_buffer,
+            index_buffer,
+            vertex_count: self.vertex_count() as u32,
+            index_count: self.indices.as_ref().map_or(0, |i| i.len() as u32),
+        }
+    }
+}
+
+impl From<MeshData> for Mesh {
+    fn from(data: MeshData) -> Self {
+        Mesh {
+            positions: data.positions,
+            normals: Some(data.normals),
+            uvs: Some(data.uvs),
+            colors: None,
+            tangents: None,
+            indices: Some(Indices::U32(data.indices)),
+        }
+    }
+}
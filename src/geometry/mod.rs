@@ -0,0 +1,18 @@
+//! Vertex data for the example meshes.
+pub mod bounds;
+pub mod gltf;
+pub mod mesh;
+pub mod obj;
+pub mod validate;
+pub mod vertex_data;
+
+pub use self::gltf::{load_gltf, GltfScene, PbrMaterial};
+pub use bounds::{Aabb, BoundingSphere};
+pub use mesh::{Attribute, GpuMesh, Indices, Mesh, MeshLayout};
+pub use obj::{load_obj, ObjError, ObjMaterial, ObjMesh, ObjModel};
+pub use validate::{validate_mesh, MeshIssue, MeshReport};
+
+pub use vertex_data::{
+    capsule_data, cone_data, cube_data, cube_data_index, cylinder_data, icosphere_data, plane_data,
+    torus_data, uv_sphere_data, MeshData,
+};
@@ -0,0 +1,532 @@
+//! Wavefront OBJ/MTL loading into indexed [`Mesh`]es.
+//!
+//! Faces are triangulated by ear clipping, vertices are deduplicated per
+//! position/uv/normal triple, missing normals are replaced by smooth area-weighted
+//! normals and texture coordinates are flipped to wgpu's top-left origin.
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::mesh::{Indices, Mesh};
+
+/// Error raised while reading or parsing OBJ and MTL files.
+#[derive(Debug)]
+pub enum ObjError {
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        file: String,
+        line: usize,
+        message: String,
+    },
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ObjError::Io { path, source } => write!(f, "{}: {}", path.display(), source),
+            ObjError::Parse {
+                file,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", file, line, message),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ObjError::Io { source, .. } => Some(source),
+            ObjError::Parse { .. } => None,
+        }
+    }
+}
+
+/// A material from an MTL file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    /// `Ka`
+    pub ambient: [f32; 3],
+    /// `Kd`
+    pub diffuse: [f32; 3],
+    /// `Ks`
+    pub specular: [f32; 3],
+    /// `Ns`
+    pub shininess: f32,
+    /// `d`, or `1 - Tr`
+    pub opacity: f32,
+    /// `map_Kd`, relative to the MTL file
+    pub diffuse_texture: Option<PathBuf>,
+}
+
+impl ObjMaterial {
+    fn new(name: &str) -> Self {
+        ObjMaterial {
+            name: name.to_string(),
+            ambient: [0.0; 3],
+            diffuse: [1.0; 3],
+            specular: [0.0; 3],
+            shininess: 0.0,
+            opacity: 1.0,
+            diffuse_texture: None,
+        }
+    }
+}
+
+/// The faces of one object or group that share a material.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjMesh {
+    pub name: String,
+    pub material: Option<String>,
+    pub mesh: Mesh,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ObjModel {
+    pub meshes: Vec<ObjMesh>,
+    pub materials: Vec<ObjMaterial>,
+    /// `mtllib` files referenced by the OBJ, in order.
+    pub material_libraries: Vec<String>,
+}
+
+impl ObjModel {
+    pub fn material(&self, mesh: &ObjMesh) -> Option<&ObjMaterial> {
+        let name = mesh.material.as_ref()?;
+        self.materials.iter().find(|m| &m.name == name)
+    }
+}
+
+/// Reads an OBJ file together with the MTL libraries it references, resolved relative
+/// to the OBJ file.
+pub fn load_obj<P: AsRef<Path>>(path: P) -> Result<ObjModel, ObjError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    let mut model = parse_obj(&source, &path.display().to_string())?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    for library in &model.material_libraries {
+        let mtl_path = dir.join(library);
+        let mtl_source = read(&mtl_path)?;
+        let mut materials = parse_mtl(&mtl_source, &mtl_path.display().to_string())?;
+        let mtl_dir = mtl_path.parent().unwrap_or(Path::new(""));
+        for material in &mut materials {
+            if let Some(texture) = &material.diffuse_texture {
+                material.diffuse_texture = Some(mtl_dir.join(texture));
+            }
+        }
+        model.materials.extend(materials);
+    }
+    Ok(model)
+}
+
+fn read(path: &Path) -> Result<String, ObjError> {
+    std::fs::read_to_string(path).map_err(|source| ObjError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+// one vertex of a face: position, uv and normal indices into the file-wide lists
+type FaceVertex = (usize, Option<usize>, Option<usize>);
+
+#[derive(Default)]
+struct MeshBuilder {
+    name: String,
+    material: Option<String>,
+    vertices: Vec<FaceVertex>,
+    lookup: HashMap<FaceVertex, u32>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    fn add(&mut self, vertex: FaceVertex) -> u32 {
+        let vertices = &mut self.vertices;
+        *self.lookup.entry(vertex).or_insert_with(|| {
+            vertices.push(vertex);
+            vertices.len() as u32 - 1
+        })
+    }
+
+    fn build(
+        self,
+        positions: &[[f32; 3]],
+        colors: &[[f32; 3]],
+        uvs: &[[f32; 2]],
+        normals: &[[f32; 3]],
+    ) -> ObjMesh {
+        let has_uvs = self.vertices.iter().any(|v| v.1.is_some());
+        let has_colors = !colors.is_empty();
+
+        // smooth normals for vertices that have none, accumulated per position so that
+        // uv seams do not split them
+        let mut generated: HashMap<usize, [f32; 3]> = HashMap::new();
+        if self.vertices.iter().any(|v| v.2.is_none()) {
+            for tri in self.indices.chunks(3) {
+                let [a, b, c] = [0, 1, 2].map(|k| self.vertices[tri[k] as usize].0);
+                let n = face_normal(positions[a], positions[b], positions[c]);
+                for p in [a, b, c] {
+                    let sum = generated.entry(p).or_insert([0.0; 3]);
+                    *sum = [sum[0] + n[0], sum[1] + n[1], sum[2] + n[2]];
+                }
+            }
+        }
+
+        let mesh = Mesh {
+            positions: self.vertices.iter().map(|v| positions[v.0]).collect(),
+            normals: Some(
+                self.vertices
+                    .iter()
+                    .map(|v| match v.2 {
+                        Some(n) => normals[n],
+                        None => normalize(generated.get(&v.0).copied().unwrap_or([0.0; 3])),
+                    })
+                    .collect(),
+            ),
+            uvs: has_uvs.then(|| {
+                self.vertices
+                    .iter()
+                    .map(|v| v.1.map_or([0.0, 0.0], |t| uvs[t]))
+                    .collect()
+            }),
+            colors: has_colors.then(|| {
+                self.vertices
+                    .iter()
+                    .map(|v| {
+                        let c = colors[v.0];
+                        [c[0], c[1], c[2], 1.0]
+                    })
+                    .collect()
+            }),
+            tangents: None,
+            indices: Some(Indices::U32(self.indices)),
+        };
+        ObjMesh {
+            name: self.name,
+            material: self.material,
+            mesh,
+        }
+    }
+}
+
+/// Parses OBJ source. `file` only labels errors; `mtllib` statements are recorded in
+/// [`ObjModel::material_libraries`] but not loaded.
+pub fn parse_obj(source: &str, file: &str) -> Result<ObjModel, ObjError> {
+    let mut positions: Vec<[f32; 3]> = Vec::new();
+    let mut colors: Vec<[f32; 3]> = Vec::new();
+    let mut uvs: Vec<[f32; 2]> = Vec::new();
+    let mut normals: Vec<[f32; 3]> = Vec::new();
+    let mut model = ObjModel::default();
+    let mut finished: Vec<MeshBuilder> = Vec::new();
+    let mut current = MeshBuilder::default();
+
+    for (number, line) in source.lines().enumerate() {
+        let line_number = number + 1;
+        let error = |message: String| ObjError::Parse {
+            file: file.to_string(),
+            line: line_number,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(&args, 3, 7).map_err(error)?;
+                positions.push([values[0], values[1], values[2]]);
+                // x y z, optionally followed by w, then optionally by an rgb vertex color
+                // as some exporters write it
+                let color = match values.len() {
+                    3 | 4 => None,
+                    6 | 7 => Some(&values[values.len() - 3..]),
+                    n => return Err(error(format!("expected 3, 4, 6 or 7 numbers, got {}", n))),
+                };
+                // colors must line up with positions, so either every vertex has one or
+                // none does
+                let consistent = if color.is_some() {
+                    colors.len() + 1 == positions.len()
+                } else {
+                    colors.is_empty()
+                };
+                if !consistent {
+                    return Err(error(
+                        "vertex colors on some vertices but not on others".into(),
+                    ));
+                }
+                if let Some(c) = color {
+                    colors.push([c[0], c[1], c[2]]);
+                }
+            }
+            "vt" => {
+                let values = parse_floats(&args, 1, 3).map_err(error)?;
+                let v = values.get(1).copied().unwrap_or(0.0);
+                uvs.push([values[0], 1.0 - v]);
+            }
+            "vn" => {
+                let values = parse_floats(&args, 3, 3).map_err(error)?;
+                normals.push(normalize([values[0], values[1], values[2]]));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(error(format!(
+                        "face needs at least 3 vertices, got {}",
+                        args.len()
+                    )));
+                }
+                let face = args
+                    .iter()
+                    .map(|arg| parse_face_vertex(arg, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(error)?;
+                let points: Vec<[f32; 3]> = face.iter().map(|v| positions[v.0]).collect();
+                for tri in triangulate(&points) {
+                    for k in tri {
+                        let index = current.add(face[k]);
+                        current.indices.push(index);
+                    }
+                }
+            }
+            "o" | "g" => {
+                let name = args.join(" ");
+                let material = current.material.clone();
+                finish(&mut finished, &mut current);
+                current.name = name;
+                current.material = material;
+            }
+            "usemtl" => {
+                let name = current.name.clone();
+                finish(&mut finished, &mut current);
+                current.name = name;
+                current.material = Some(args.join(" "));
+            }
+            "mtllib" => model
+                .material_libraries
+                .extend(args.iter().map(|s| s.to_string())),
+            // smoothing groups, lines, points and free-form geometry are not supported
+            _ => log::debug!("{}:{}: ignoring '{}'", file, line_number, keyword),
+        }
+    }
+    finish(&mut finished, &mut current);
+
+    model.meshes = finished
+        .into_iter()
+        .map(|builder| builder.build(&positions, &colors, &uvs, &normals))
+        .collect();
+    Ok(model)
+}
+
+// move the current mesh to the finished list if it has any faces
+fn finish(finished: &mut Vec<MeshBuilder>, current: &mut MeshBuilder) {
+    let builder = std::mem::take(current);
+    if !builder.indices.is_empty() {
+        finished.push(builder);
+    }
+}
+
+/// Parses MTL source. `file` only labels errors.
+pub fn parse_mtl(source: &str, file: &str) -> Result<Vec<ObjMaterial>, ObjError> {
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+    for (number, line) in source.lines().enumerate() {
+        let line_number = number + 1;
+        let error = |message: String| ObjError::Parse {
+            file: file.to_string(),
+            line: line_number,
+            message,
+        };
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+
+        if keyword == "newmtl" {
+            materials.push(ObjMaterial::new(&args.join(" ")));
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            return Err(error(format!("'{}' before any newmtl", keyword)));
+        };
+        match keyword {
+            "Ka" | "Kd" | "Ks" => {
+                let values = parse_floats(&args, 3, 3).map_err(error)?;
+                let color = [values[0], values[1], values[2]];
+                match keyword {
+                    "Ka" => material.ambient = color,
+                    "Kd" => material.diffuse = color,
+                    _ => material.specular = color,
+                }
+            }
+            "Ns" => material.shininess = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "d" => material.opacity = parse_floats(&args, 1, 1).map_err(error)?[0],
+            "Tr" => material.opacity = 1.0 - parse_floats(&args, 1, 1).map_err(error)?[0],
+            "map_Kd" => {
+                // options such as "-s 1 1 1" come before the file name
+                let Some(texture) = args.last() else {
+                    return Err(error("map_Kd without a file name".into()));
+                };
+                material.diffuse_texture = Some(PathBuf::from(texture));
+            }
+            _ => log::debug!("{}:{}: ignoring '{}'", file, line_number, keyword),
+        }
+    }
+    Ok(materials)
+}
+
+fn parse_floats(args: &[&str], min: usize, max: usize) -> Result<Vec<f32>, String> {
+    if args.len() < min || args.len() > max {
+        return Err(if min == max {
+            format!("expected {} numbers, got {}", min, args.len())
+        } else {
+            format!("expected {} to {} numbers, got {}", min, max, args.len())
+        });
+    }
+    args.iter()
+        .map(|arg| {
+            arg.parse::<f32>()
+                .map_err(|_| format!("invalid number '{}'", arg))
+        })
+        .collect()
+}
+
+// resolve a 1-based or negative (relative to the end) OBJ index
+fn resolve_index(token: &str, count: usize, kind: &str) -> Result<usize, String> {
+    let index: i64 = token
+        .parse()
+        .map_err(|_| format!("invalid {} index '{}'", kind, token))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        count as i64 + index
+    } else {
+        return Err(format!(
+            "{} index 0 is invalid, OBJ indices start at 1",
+            kind
+        ));
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(format!(
+            "{} index {} out of range, {} defined so far",
+            kind, index, count
+        ));
+    }
+    Ok(resolved as usize)
+}
+
+fn parse_face_vertex(
+    token: &str,
+    positions: usize,
+    uvs: usize,
+    normals: usize,
+) -> Result<FaceVertex, String> {
+    let mut parts = token.split('/');
+    let position = resolve_index(parts.next().unwrap_or(""), positions, "vertex")?;
+    let uv = match parts.next() {
+        Some("") | None => None,
+        Some(t) => Some(resolve_index(t, uvs, "texture coordinate")?),
+    };
+    let normal = match parts.next() {
+        Some("") | None => None,
+        Some(n) => Some(resolve_index(n, normals, "normal")?),
+    };
+    if parts.next().is_some() {
+        return Err(format!("malformed face vertex '{}'", token));
+    }
+    Ok((position, uv, normal))
+}
+
+fn face_normal(a: [f32; 3], b: [f32; 3], c: [f32; 3]) -> [f32; 3] {
+    let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+    let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+    [
+        u[1] * v[2] - u[2] * v[1],
+        u[2] * v[0] - u[0] * v[2],
+        u[0] * v[1] - u[1] * v[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+/// Splits a planar polygon into triangles by ear clipping, keeping its winding. Falls
+/// back to a fan for degenerate polygons.
+pub fn triangulate(points: &[[f32; 3]]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    if n < 3 {
+        return Vec::new();
+    }
+    if n == 3 {
+        return vec![[0, 1, 2]];
+    }
+
+    // Newell normal; project onto the plane of its largest component, keeping the
+    // remaining axes in cyclic order so the 2D winding matches the normal's sign
+    let mut normal = [0.0f32; 3];
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+    let axis = (0..3)
+        .max_by(|&i, &j| normal[i].abs().total_cmp(&normal[j].abs()))
+        .unwrap();
+    let sign = if normal[axis] < 0.0 { -1.0 } else { 1.0 };
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let flat: Vec<[f32; 2]> = points.iter().map(|p| [p[u], p[v]]).collect();
+    let cross = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| {
+        ((b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])) * sign
+    };
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = Vec::with_capacity(n - 2);
+    while remaining.len() > 3 {
+        let m = remaining.len();
+        let ear = (0..m).find(|&i| {
+            let (p, c, x) = (
+                remaining[(i + m - 1) % m],
+                remaining[i],
+                remaining[(i + 1) % m],
+            );
+            let (a, b, d) = (flat[p], flat[c], flat[x]);
+            if cross(a, b, d) <= 0.0 {
+                return false;
+            }
+            // no other vertex may lie inside the candidate ear
+            remaining.iter().all(|&o| {
+                o == p
+                    || o == c
+                    || o == x
+                    || cross(a, b, flat[o]) < 0.0
+                    || cross(b, d, flat[o]) < 0.0
+                    || cross(d, a, flat[o]) < 0.0
+            })
+        });
+        let Some(i) = ear else {
+            break;
+        };
+        triangles.push([
+            remaining[(i + m - 1) % m],
+            remaining[i],
+            remaining[(i + 1) % m],
+        ]);
+        remaining.remove(i);
+    }
+    // the last triangle, or a fan over whatever the clipping could not resolve
+    for k in 1..remaining.len() - 1 {
+        triangles.push([remaining[0], remaining[k], remaining[k + 1]]);
+    }
+    triangles
+}
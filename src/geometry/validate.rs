@@ -0,0 +1,288 @@
+//! Consistency checks for [`Mesh`]es: attribute counts, index ranges, degenerate and
+//! duplicate triangles, duplicate vertices and winding.
+//!
+//! Winding is checked two ways. Triangles must turn counter-clockwise when seen from
+//! the side their vertex normals point to, and triangles sharing an edge must agree
+//! on their facing; a closed mesh must also face outwards, or back-face culling hides
+//! it. Edges are matched by position, so non-indexed meshes are checked as well.
+use std::collections::HashMap;
+use std::fmt;
+
+use super::mesh::{Attribute, Mesh};
+
+/// A problem found by [`validate_mesh`]. Triangles are numbered in the order they are
+/// drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeshIssue {
+    /// An optional attribute does not have one entry per position.
+    AttributeCount {
+        attribute: Attribute,
+        expected: usize,
+        found: usize,
+    },
+    /// The index count, or the vertex count of a non-indexed mesh, is not a multiple
+    /// of three.
+    IncompleteTriangle { count: usize },
+    IndexOutOfRange {
+        position: usize,
+        index: u32,
+        vertex_count: usize,
+    },
+    /// A triangle with no area, or using a vertex twice.
+    DegenerateTriangle { triangle: usize },
+    /// A triangle with the same corners as an earlier one, in any order.
+    DuplicateTriangle { triangle: usize, first: usize },
+    /// A vertex of an indexed mesh whose attributes all equal an earlier vertex.
+    DuplicateVertex { vertex: usize, first: usize },
+    /// A triangle that turns clockwise seen from the side its vertex normals point to.
+    WindingAgainstNormals { triangle: usize },
+    /// Two triangles running along a shared edge in the same direction, so that they
+    /// face opposite ways.
+    InconsistentWinding { triangle: usize, other: usize },
+    /// A closed mesh whose triangles face inwards.
+    InsideOut,
+}
+
+impl fmt::Display for MeshIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshIssue::AttributeCount {
+                attribute,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} {:?} values for {} positions",
+                found, attribute, expected
+            ),
+            MeshIssue::IncompleteTriangle { count } => {
+                write!(f, "{} corners do not make whole triangles", count)
+            }
+            MeshIssue::IndexOutOfRange {
+                position,
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "index {} at {} is out of range for {} vertices",
+                index, position, vertex_count
+            ),
+            MeshIssue::DegenerateTriangle { triangle } => {
+                write!(f, "triangle {} is degenerate", triangle)
+            }
+            MeshIssue::DuplicateTriangle { triangle, first } => {
+                write!(f, "triangle {} duplicates triangle {}", triangle, first)
+            }
+            MeshIssue::DuplicateVertex { vertex, first } => {
+                write!(f, "vertex {} duplicates vertex {}", vertex, first)
+            }
+            MeshIssue::WindingAgainstNormals { triangle } => {
+                write!(f, "triangle {} winds against its normals", triangle)
+            }
+            MeshIssue::InconsistentWinding { triangle, other } => write!(
+                f,
+                "triangles {} and {} share an edge in the same direction",
+                other, triangle
+            ),
+            MeshIssue::InsideOut => write!(f, "the closed mesh faces inwards"),
+        }
+    }
+}
+
+/// The result of [`validate_mesh`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MeshReport {
+    pub vertex_count: usize,
+    /// Whole triangles drawn, including invalid ones.
+    pub triangle_count: usize,
+    pub issues: Vec<MeshIssue>,
+}
+
+impl MeshReport {
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+impl fmt::Display for MeshReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} vertices, {} triangles",
+            self.vertex_count, self.triangle_count
+        )?;
+        for issue in &self.issues {
+            write!(f, "\n  {}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+// bit patterns, so that equal floats hash equally; adding zero turns -0 into +0
+fn float_key(v: &[f32]) -> Vec<u32> {
+    v.iter().map(|x| (x + 0.0).to_bits()).collect()
+}
+
+// an attribute with one value per vertex; the others are reported and then ignored
+fn matching<T>(values: &Option<Vec<T>>, count: usize) -> Option<&Vec<T>> {
+    values.as_ref().filter(|values| values.len() == count)
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Runs every check on `mesh` and collects what fails.
+pub fn validate_mesh(mesh: &Mesh) -> MeshReport {
+    let vertex_count = mesh.vertex_count();
+    let mut issues = Vec::new();
+
+    let counts = [
+        (Attribute::Normal, mesh.normals.as_ref().map(Vec::len)),
+        (Attribute::Uv, mesh.uvs.as_ref().map(Vec::len)),
+        (Attribute::Color, mesh.colors.as_ref().map(Vec::len)),
+        (Attribute::Tangent, mesh.tangents.as_ref().map(Vec::len)),
+    ];
+    for (attribute, found) in counts {
+        if let Some(found) = found.filter(|&found| found != vertex_count) {
+            issues.push(MeshIssue::AttributeCount {
+                attribute,
+                expected: vertex_count,
+                found,
+            });
+        }
+    }
+    let normals = matching(&mesh.normals, vertex_count);
+
+    let corners: Vec<u32> = match &mesh.indices {
+        Some(indices) => indices.iter().collect(),
+        None => (0..vertex_count as u32).collect(),
+    };
+    if !corners.len().is_multiple_of(3) {
+        issues.push(MeshIssue::IncompleteTriangle {
+            count: corners.len(),
+        });
+    }
+    for (position, &index) in corners.iter().enumerate() {
+        if index as usize >= vertex_count {
+            issues.push(MeshIssue::IndexOutOfRange {
+                position,
+                index,
+                vertex_count,
+            });
+        }
+    }
+
+    if mesh.indices.is_some() {
+        let uvs = matching(&mesh.uvs, vertex_count);
+        let colors = matching(&mesh.colors, vertex_count);
+        let tangents = matching(&mesh.tangents, vertex_count);
+        let mut seen = HashMap::new();
+        for vertex in 0..vertex_count {
+            let mut key = float_key(&mesh.positions[vertex]);
+            if let Some(normals) = normals {
+                key.extend(float_key(&normals[vertex]));
+            }
+            if let Some(uvs) = uvs {
+                key.extend(float_key(&uvs[vertex]));
+            }
+            if let Some(colors) = colors {
+                key.extend(float_key(&colors[vertex]));
+            }
+            if let Some(tangents) = tangents {
+                key.extend(float_key(&tangents[vertex]));
+            }
+            if let Some(&first) = seen.get(&key) {
+                issues.push(MeshIssue::DuplicateVertex { vertex, first });
+            } else {
+                seen.insert(key, vertex);
+            }
+        }
+    }
+
+    let triangles: Vec<[usize; 3]> = corners
+        .chunks_exact(3)
+        .map(|t| [t[0] as usize, t[1] as usize, t[2] as usize])
+        .collect();
+    let mut triangle_keys = HashMap::new();
+    let mut edges: HashMap<(Vec<u32>, Vec<u32>), usize> = HashMap::new();
+    let mut closed = true;
+    let mut volume = 0.0;
+    for (triangle, &[a, b, c]) in triangles.iter().enumerate() {
+        if a.max(b).max(c) >= vertex_count {
+            closed = false;
+            continue;
+        }
+        let [pa, pb, pc] = [a, b, c].map(|i| mesh.positions[i]);
+        let (e1, e2) = (sub(pb, pa), sub(pc, pa));
+        let face_normal = cross(e1, e2);
+        // sine of the corner angle at a, squared, so the test does not depend on scale
+        if a == b
+            || b == c
+            || a == c
+            || dot(face_normal, face_normal) <= 1e-12 * dot(e1, e1) * dot(e2, e2)
+        {
+            issues.push(MeshIssue::DegenerateTriangle { triangle });
+            closed = false;
+            continue;
+        }
+
+        let keys = [pa, pb, pc].map(|p| float_key(&p));
+        let mut sorted = keys.clone();
+        sorted.sort();
+        if let Some(&first) = triangle_keys.get(&sorted) {
+            issues.push(MeshIssue::DuplicateTriangle { triangle, first });
+        } else {
+            triangle_keys.insert(sorted, triangle);
+        }
+
+        if let Some(normals) = normals {
+            let [na, nb, nc] = [a, b, c].map(|i| normals[i]);
+            let vertex_normal = [
+                na[0] + nb[0] + nc[0],
+                na[1] + nb[1] + nc[1],
+                na[2] + nb[2] + nc[2],
+            ];
+            if dot(face_normal, vertex_normal) <= 0.0 {
+                issues.push(MeshIssue::WindingAgainstNormals { triangle });
+            }
+        }
+
+        for k in 0..3 {
+            let edge = (keys[k].clone(), keys[(k + 1) % 3].clone());
+            if let Some(&other) = edges.get(&edge) {
+                issues.push(MeshIssue::InconsistentWinding { triangle, other });
+            } else {
+                edges.insert(edge, triangle);
+            }
+        }
+        volume += dot(pa, cross(pb, pc));
+    }
+
+    // closed when every edge is matched by the same edge running the other way
+    closed &= !edges.is_empty()
+        && edges
+            .keys()
+            .all(|(from, to)| edges.contains_key(&(to.clone(), from.clone())));
+    if closed && volume < 0.0 {
+        issues.push(MeshIssue::InsideOut);
+    }
+
+    MeshReport {
+        vertex_count,
+        triangle_count: triangles.len(),
+        issues,
+    }
+}
@@ -0,0 +1,543 @@
+//! Hard-coded cube datasets and procedural shape generators.
+use std::f32::consts::PI;
+
+/// Non-indexed cube: 36 vertices as (positions, colors, uvs, normals).
+#[allow(clippy::type_complexity)]
+pub fn cube_data() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<[i8; 2]>, Vec<[i8; 3]>) {
+    let positions = [
+        // front (0, 0, 1)
+        [-1, -1, 1],
+        [1, -1, 1],
+        [-1, 1, 1],
+        [-1, 1, 1],
+        [1, -1, 1],
+        [1, 1, 1],
+        // right (1, 0, 0)
+        [1, -1, 1],
+        [1, -1, -1],
+        [1, 1, 1],
+        [1, 1, 1],
+        [1, -1, -1],
+        [1, 1, -1],
+        // back (0, 0, -1)
+        [1, -1, -1],
+        [-1, -1, -1],
+        [1, 1, -1],
+        [1, 1, -1],
+        [-1, -1, -1],
+        [-1, 1, -1],
+        // left (-1, 0, 0)
+        [-1, -1, -1],
+        [-1, -1, 1],
+        [-1, 1, -1],
+        [-1, 1, -1],
+        [-1, -1, 1],
+        [-1, 1, 1],
+        // top (0, 1, 0)
+        [-1, 1, 1],
+        [1, 1, 1],
+        [-1, 1, -1],
+        [-1, 1, -1],
+        [1, 1, 1],
+        [1, 1, -1],
+        // bottom (0, -1, 0)
+        [-1, -1, -1],
+        [1, -1, -1],
+        [-1, -1, 1],
+        [-1, -1, 1],
+        [1, -1, -1],
+        [1, -1, 1],
+    ];
+    let colors = [
+        // front - blue
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        // right - red
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        // back - yellow
+        [1, 1, 0],
+        [1, 1, 0],
+        [1, 1, 0],
+        [1, 1, 0],
+        [1, 1, 0],
+        [1, 1, 0],
+        // left - aqua
+        [0, 1, 1],
+        [0, 1, 1],
+        [0, 1, 1],
+        [0, 1, 1],
+        [0, 1, 1],
+        [0, 1, 1],
+        // top - green
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        // bottom - fuchsia
+        [1, 0, 1],
+        [1, 0, 1],
+        [1, 0, 1],
+        [1, 0, 1],
+        [1, 0, 1],
+        [1, 0, 1],
+    ];
+
+    let uvs = [
+        // front
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+        // right
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+        // back
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+        // left
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+        // top
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+        // bottom
+        [0, 0],
+        [1, 0],
+        [0, 1],
+        [0, 1],
+        [1, 0],
+        [1, 1],
+    ];
+    let normals = [
+        // front
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        [0, 0, 1],
+        // right
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        [1, 0, 0],
+        // back
+        [0, 0, -1],
+        [0, 0, -1],
+        [0, 0, -1],
+        [0, 0, -1],
+        [0, 0, -1],
+        [0, 0, -1],
+        // left
+        [-1, 0, 0],
+        [-1, 0, 0],
+        [-1, 0, 0],
+        [-1, 0, 0],
+        [-1, 0, 0],
+        [-1, 0, 0],
+        // top
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        [0, 1, 0],
+        // bottom
+        [0, -1, 0],
+        [0, -1, 0],
+        [0, -1, 0],
+        [0, -1, 0],
+        [0, -1, 0],
+        [0, -1, 0],
+    ];
+    // return data
+    (
+        positions.to_vec(),
+        colors.to_vec(),
+        uvs.to_vec(),
+        normals.to_vec(),
+    )
+}
+
+/// Indexed cube as (positions, colors, indices).
+pub fn cube_data_index() -> (Vec<[i8; 3]>, Vec<[i8; 3]>, Vec<u16>) {
+    let positions = [
+        [-1, -1, 1],  // vertex a
+        [1, -1, 1],   // vertex b
+        [1, 1, 1],    // vertex c
+        [-1, 1, 1],   // vertex d
+        [-1, -1, -1], // vertex e
+        [1, -1, -1],  // vertex f
+        [1, 1, -1],   // vertex g
+        [-1, 1, -1],  // vertex h
+    ];
+    let colors = [
+        [0, 0, 1], // vertex a
+        [1, 0, 1], // vertex b
+        [1, 1, 1], // vertex c
+        [0, 1, 1], // vertex d
+        [0, 0, 0], // vertex e
+        [1, 0, 0], // vertex f
+        [1, 1, 0], // vertex g
+        [0, 1, 0], // vertex h
+    ];
+    let indices = [
+        0, 1, 2, 2, 3, 0, // front
+        1, 5, 6, 6, 2, 1, // right
+        4, 7, 6, 6, 5, 4, // back
+        0, 3, 7, 7, 4, 0, // left
+        3, 2, 6, 6, 7, 3, // top
+        0, 4, 5, 5, 1, 0, // bottom
+    ];
+    (positions.to_vec(), colors.to_vec(), indices.to_vec())
+}
+
+/// Positions, normals, UVs and triangle-list indices produced by the shape generators.
+/// Triangles wind counter-clockwise when seen from the side their normals point to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub positions: Vec<[f32; 3]>,
+    pub normals: Vec<[f32; 3]>,
+    pub uvs: Vec<[f32; 2]>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Appends `other`, offsetting its indices past the existing vertices.
+    pub fn append(&mut self, other: MeshData) {
+        let offset = self.positions.len() as u32;
+        self.positions.extend(other.positions);
+        self.normals.extend(other.normals);
+        self.uvs.extend(other.uvs);
+        self.indices
+            .extend(other.indices.iter().map(|i| i + offset));
+    }
+}
+
+// a point of the profile curve revolved by `lathe`: distance from the y axis, height,
+// the profile normal as (outward, up) and the v texture coordinate
+struct ProfilePoint {
+    radius: f32,
+    y: f32,
+    normal: [f32; 2],
+    v: f32,
+}
+
+// revolve a profile, listed from top to bottom, around the y axis; rows whose radius is
+// zero collapse into a pole, so the triangles touching them are skipped
+fn lathe(profile: &[ProfilePoint], sectors: u32) -> MeshData {
+    let mut data = MeshData::default();
+    for p in profile {
+        for j in 0..=sectors {
+            let u = j as f32 / sectors as f32;
+            let (sin, cos) = (2.0 * PI * u).sin_cos();
+            data.positions.push([p.radius * sin, p.y, p.radius * cos]);
+            let n = [p.normal[0] * sin, p.normal[1], p.normal[0] * cos];
+            data.normals.push(normalize(n));
+            data.uvs.push([u, p.v]);
+        }
+    }
+
+    let columns = sectors + 1;
+    for i in 0..profile.len() as u32 - 1 {
+        for j in 0..sectors {
+            let a = i * columns + j;
+            let b = a + columns;
+            let d = a + 1;
+            let c = b + 1;
+            if profile[i as usize].radius != 0.0 {
+                data.indices.extend_from_slice(&[a, b, d]);
+            }
+            if profile[i as usize + 1].radius != 0.0 {
+                data.indices.extend_from_slice(&[d, b, c]);
+            }
+        }
+    }
+    data
+}
+
+// flat disc at height y facing up or down
+fn disc(radius: f32, y: f32, facing_up: bool, sectors: u32) -> MeshData {
+    let mut data = MeshData::default();
+    let ny = if facing_up { 1.0 } else { -1.0 };
+    data.positions.push([0.0, y, 0.0]);
+    data.normals.push([0.0, ny, 0.0]);
+    data.uvs.push([0.5, 0.5]);
+    for j in 0..=sectors {
+        let (sin, cos) = (2.0 * PI * j as f32 / sectors as f32).sin_cos();
+        data.positions.push([radius * sin, y, radius * cos]);
+        data.normals.push([0.0, ny, 0.0]);
+        data.uvs.push([0.5 + 0.5 * sin, 0.5 - 0.5 * cos * ny]);
+    }
+    for j in 1..=sectors {
+        if facing_up {
+            data.indices.extend_from_slice(&[0, j, j + 1]);
+        } else {
+            data.indices.extend_from_slice(&[0, j + 1, j]);
+        }
+    }
+    data
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let len = (v[0] * v[0] + v[1] * v[1] + v[2] * v[2]).sqrt();
+    if len == 0.0 {
+        v
+    } else {
+        [v[0] / len, v[1] / len, v[2] / len]
+    }
+}
+
+/// UV sphere centered at the origin with `sectors` longitude and `stacks` latitude
+/// segments.
+pub fn uv_sphere_data(radius: f32, sectors: u32, stacks: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let stacks = stacks.max(2);
+    let profile: Vec<ProfilePoint> = (0..=stacks)
+        .map(|i| {
+            let v = i as f32 / stacks as f32;
+            let (sin, cos) = (PI * v).sin_cos();
+            ProfilePoint {
+                radius: if i == 0 || i == stacks {
+                    0.0
+                } else {
+                    radius * sin
+                },
+                y: radius * cos,
+                normal: [sin, cos],
+                v,
+            }
+        })
+        .collect();
+    lathe(&profile, sectors)
+}
+
+/// Sphere built by splitting each face of an icosahedron `subdivisions` times. UVs are
+/// spherical coordinates, so the texture seam is not split.
+pub fn icosphere_data(radius: f32, subdivisions: u32) -> MeshData {
+    let t = (1.0 + 5f32.sqrt()) / 2.0;
+    let mut directions: Vec<[f32; 3]> = [
+        [-1.0, t, 0.0],
+        [1.0, t, 0.0],
+        [-1.0, -t, 0.0],
+        [1.0, -t, 0.0],
+        [0.0, -1.0, t],
+        [0.0, 1.0, t],
+        [0.0, -1.0, -t],
+        [0.0, 1.0, -t],
+        [t, 0.0, -1.0],
+        [t, 0.0, 1.0],
+        [-t, 0.0, -1.0],
+        [-t, 0.0, 1.0],
+    ]
+    .iter()
+    .map(|&v| normalize(v))
+    .collect();
+    let mut faces: Vec<[u32; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        let mut midpoints = std::collections::HashMap::new();
+        let mut midpoint = |a: u32, b: u32, directions: &mut Vec<[f32; 3]>| -> u32 {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let (pa, pb) = (directions[a as usize], directions[b as usize]);
+                directions.push(normalize([pa[0] + pb[0], pa[1] + pb[1], pa[2] + pb[2]]));
+                directions.len() as u32 - 1
+            })
+        };
+        let mut next = Vec::with_capacity(faces.len() * 4);
+        for [a, b, c] in faces {
+            let ab = midpoint(a, b, &mut directions);
+            let bc = midpoint(b, c, &mut directions);
+            let ca = midpoint(c, a, &mut directions);
+            next.extend_from_slice(&[[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]);
+        }
+        faces = next;
+    }
+
+    MeshData {
+        positions: directions
+            .iter()
+            .map(|n| [n[0] * radius, n[1] * radius, n[2] * radius])
+            .collect(),
+        uvs: directions
+            .iter()
+            .map(|n| {
+                [
+                    0.5 + n[0].atan2(n[2]) / (2.0 * PI),
+                    n[1].clamp(-1.0, 1.0).acos() / PI,
+                ]
+            })
+            .collect(),
+        normals: directions,
+        indices: faces.into_iter().flatten().collect(),
+    }
+}
+
+// side of a truncated cone from radius `top` at y = height / 2 down to `bottom`
+fn frustum_side(bottom: f32, top: f32, height: f32, sectors: u32, stacks: u32) -> MeshData {
+    let normal = [height, bottom - top];
+    let profile: Vec<ProfilePoint> = (0..=stacks)
+        .map(|i| {
+            let v = i as f32 / stacks as f32;
+            ProfilePoint {
+                radius: top + (bottom - top) * v,
+                y: height * (0.5 - v),
+                normal,
+                v,
+            }
+        })
+        .collect();
+    lathe(&profile, sectors)
+}
+
+/// Capped cylinder along the y axis, centered at the origin, with `sectors` segments
+/// around and `stacks` along its height.
+pub fn cylinder_data(radius: f32, height: f32, sectors: u32, stacks: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let mut data = frustum_side(radius, radius, height, sectors, stacks.max(1));
+    data.append(disc(radius, 0.5 * height, true, sectors));
+    data.append(disc(radius, -0.5 * height, false, sectors));
+    data
+}
+
+/// Cone with its apex on the positive y axis and a capped base, centered at the origin.
+pub fn cone_data(radius: f32, height: f32, sectors: u32, stacks: u32) -> MeshData {
+    let sectors = sectors.max(3);
+    let mut data = frustum_side(radius, 0.0, height, sectors, stacks.max(1));
+    data.append(disc(radius, -0.5 * height, false, sectors));
+    data
+}
+
+/// Torus around the y axis: `major_radius` to the center of the tube, `minor_radius`
+/// of the tube itself.
+pub fn torus_data(
+    major_radius: f32,
+    minor_radius: f32,
+    major_segments: u32,
+    minor_segments: u32,
+) -> MeshData {
+    let minor_segments = minor_segments.max(3);
+    // walk the tube cross-section from its top over the outside, bottom and inside
+    let profile: Vec<ProfilePoint> = (0..=minor_segments)
+        .map(|i| {
+            let v = i as f32 / minor_segments as f32;
+            let (sin, cos) = (0.5 * PI - 2.0 * PI * v).sin_cos();
+            ProfilePoint {
+                radius: major_radius + minor_radius * cos,
+                y: minor_radius * sin,
+                normal: [cos, sin],
+                v,
+            }
+        })
+        .collect();
+    lathe(&profile, major_segments.max(3))
+}
+
+/// Flat grid in the xz plane facing +y, centered at the origin.
+pub fn plane_data(width: f32, depth: f32, x_segments: u32, z_segments: u32) -> MeshData {
+    let x_segments = x_segments.max(1);
+    let z_segments = z_segments.max(1);
+    let mut data = MeshData::default();
+    for i in 0..=z_segments {
+        let v = i as f32 / z_segments as f32;
+        for j in 0..=x_segments {
+            let u = j as f32 / x_segments as f32;
+            data.positions
+                .push([width * (u - 0.5), 0.0, depth * (v - 0.5)]);
+            data.normals.push([0.0, 1.0, 0.0]);
+            data.uvs.push([u, v]);
+        }
+    }
+    let columns = x_segments + 1;
+    for i in 0..z_segments {
+        for j in 0..x_segments {
+            let a = i * columns + j;
+            let b = a + columns;
+            data.indices
+                .extend_from_slice(&[a, b, a + 1, a + 1, b, b + 1]);
+        }
+    }
+    data
+}
+
+/// Capsule along the y axis: a cylinder of length `height` closed by two hemispheres of
+/// `radius`, each with `hemisphere_stacks` latitude segments.
+pub fn capsule_data(radius: f32, height: f32, sectors: u32, hemisphere_stacks: u32) -> MeshData {
+    let stacks = hemisphere_stacks.max(1);
+    let total_length = height + PI * radius;
+    let mut profile = Vec::with_capacity(2 * stacks as usize + 2);
+    for (center, range) in [
+        (0.5 * height, 0..=stacks),
+        (-0.5 * height, stacks..=2 * stacks),
+    ] {
+        for i in range {
+            let phi = 0.5 * PI * i as f32 / stacks as f32;
+            let (sin, cos) = phi.sin_cos();
+            // v follows the arc length along the profile
+            let arc = radius * phi + if center < 0.0 { height } else { 0.0 };
+            profile.push(ProfilePoint {
+                radius: if i == 0 || i == 2 * stacks {
+                    0.0
+                } else {
+                    radius * sin
+                },
+                y: center + radius * cos,
+                normal: [sin, cos],
+                v: arc / total_length,
+            });
+        }
+    }
+    lathe(&profile, sectors.max(3))
+}
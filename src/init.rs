@@ -0,0 +1,254 @@
+//! wgpu device and render target setup.
+use crate::msaa::SAMPLE_COUNTS;
+use std::ops::Deref;
+use winit::window::Window;
+
+/// Device, queue and render target shared by the examples. The target is either a
+/// window surface ([`InitWgpu::init_wgpu`]) or an owned offscreen texture
+/// ([`InitWgpu::init_headless`]).
+pub struct InitWgpu<'a> {
+    pub surface: Option<wgpu::Surface<'a>>,
+    pub offscreen: Option<wgpu::Texture>,
+    /// Kept for [`wgpu::Instance::generate_report`], which counts live objects.
+    pub instance: wgpu::Instance,
+    pub adapter: wgpu::Adapter,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub config: wgpu::SurfaceConfiguration,
+    pub size: winit::dpi::PhysicalSize<u32>,
+}
+
+/// The texture a frame is rendered into: either the next swapchain image of a window
+/// surface or the texture owned by a headless [`InitWgpu`].
+pub enum Frame<'t> {
+    Surface(wgpu::SurfaceTexture),
+    Offscreen(&'t wgpu::Texture),
+}
+
+impl Frame<'_> {
+    pub fn texture(&self) -> &wgpu::Texture {
+        match self {
+            Frame::Surface(output) => &output.texture,
+            Frame::Offscreen(texture) => texture,
+        }
+    }
+
+    /// Presents a surface frame; offscreen frames stay in their texture.
+    pub fn present(self) {
+        if let Frame::Surface(output) = self {
+            output.present();
+        }
+    }
+}
+
+impl<'a> InitWgpu<'a> {
+    /// Creates a device for `window` and configures its surface. Pass an `Arc<Window>` to
+    /// get an `InitWgpu<'static>` that can live next to the window it renders to.
+    pub async fn init_wgpu<W>(window: W) -> Self
+    where
+        W: Deref<Target = Window> + wgpu::WindowHandle + 'a,
+    {
+        let size = window.inner_size();
+
+        let instance: wgpu::Instance = wgpu::Instance::default();
+
+        let surface = instance.create_surface(window).unwrap();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: Some(&surface),
+                force_fallback_adapter: false,
+            })
+            .await
+            .unwrap();
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: adapter_format_features(&adapter),
+                    required_limits: wgpu::Limits::default(),
+                    memory_hints: wgpu::MemoryHints::MemoryUsage,
+                },
+                None,
+                // Trace path
+            )
+            .await
+            .unwrap();
+
+        let mut config = surface
+            .get_default_config(&adapter, size.width, size.height)
+            .unwrap();
+        config.present_mode = wgpu::PresentMode::Fifo;
+        config.usage = wgpu::TextureUsages::RENDER_ATTACHMENT;
+
+        surface.configure(&device, &config);
+
+        Self {
+            surface: Some(surface),
+            offscreen: None,
+            instance,
+            adapter,
+            device,
+            queue,
+            config,
+            size,
+        }
+    }
+
+    /// Surfaceless variant of [`InitWgpu::init_wgpu`]: renders into an owned texture of the
+    /// given size and format on a fallback (software) adapter, so scenes can run without a
+    /// display.
+    pub async fn init_headless(width: u32, height: u32, format: wgpu::TextureFormat) -> Self {
+        let size = winit::dpi::PhysicalSize::new(width.max(1), height.max(1));
+
+        let instance: wgpu::Instance = wgpu::Instance::default();
+
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+                force_fallback_adapter: true,
+            })
+            .await
+            .expect("Failed to find a fallback adapter");
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    label: None,
+                    required_features: adapter_format_features(&adapter),
+                    // software adapters may not reach the default limits
+                    required_limits: wgpu::Limits::downlevel_defaults()
+                        .using_resolution(adapter.limits()),
+                    memory_hints: wgpu::MemoryHints::MemoryUsage,
+                },
+                None,
+            )
+            .await
+            .expect("Failed to create device");
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width: size.width,
+            height: size.height,
+            present_mode: wgpu::PresentMode::Fifo,
+            desired_maximum_frame_latency: 2,
+            alpha_mode: wgpu::CompositeAlphaMode::Auto,
+            view_formats: vec![],
+        };
+        let offscreen = create_offscreen_texture(&device, &config);
+
+        Self {
+            surface: None,
+            offscreen: Some(offscreen),
+            instance,
+            adapter,
+            device,
+            queue,
+            config,
+            size,
+        }
+    }
+
+    /// Reconfigures the surface, or recreates the offscreen texture, for a new size.
+    pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
+        if new_size.width > 0 && new_size.height > 0 {
+            self.size = new_size;
+            self.config.width = new_size.width;
+            self.config.height = new_size.height;
+            if let Some(surface) = &self.surface {
+                surface.configure(&self.device, &self.config);
+            }
+            if self.offscreen.is_some() {
+                self.offscreen = Some(create_offscreen_texture(&self.device, &self.config));
+            }
+        }
+    }
+
+    /// Acquires the texture to render the next frame into.
+    pub fn get_current_frame(&self) -> Result<Frame<'_>, wgpu::SurfaceError> {
+        match (&self.surface, &self.offscreen) {
+            (Some(surface), _) => Ok(Frame::Surface(surface.get_current_texture()?)),
+            (None, Some(texture)) => Ok(Frame::Offscreen(texture)),
+            (None, None) => Err(wgpu::SurfaceError::Lost),
+        }
+    }
+
+    /// Sample counts `format` can be rendered with: those in the adapter's format
+    /// features, or only the WebGPU guarantees if the device cannot use those.
+    pub fn supported_sample_counts(&self, format: wgpu::TextureFormat) -> Vec<u32> {
+        let features = if self
+            .device
+            .features()
+            .contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES)
+        {
+            self.adapter.get_texture_format_features(format)
+        } else {
+            format.guaranteed_format_features(self.device.features())
+        };
+        SAMPLE_COUNTS
+            .into_iter()
+            .filter(|&count| features.flags.sample_count_supported(count))
+            .collect()
+    }
+
+    /// Validates a requested MSAA sample count, which must be 1, 2, 4 or 8, and lowers
+    /// it to the largest count all of `formats` support, with a warning.
+    pub fn sample_count(
+        &self,
+        requested: u32,
+        formats: &[wgpu::TextureFormat],
+    ) -> anyhow::Result<u32> {
+        anyhow::ensure!(
+            SAMPLE_COUNTS.contains(&requested),
+            "unsupported sample count {}, expected one of {:?}",
+            requested,
+            SAMPLE_COUNTS
+        );
+        let supported: Vec<Vec<u32>> = formats
+            .iter()
+            .map(|&format| self.supported_sample_counts(format))
+            .collect();
+        let count = SAMPLE_COUNTS
+            .into_iter()
+            .rev()
+            .filter(|&count| count <= requested)
+            .find(|count| supported.iter().all(|counts| counts.contains(count)))
+            .unwrap_or(1);
+        if count != requested {
+            log::warn!(
+                "{}x MSAA is not supported for {:?}, using {}x",
+                requested,
+                formats,
+                count
+            );
+        }
+        Ok(count)
+    }
+}
+
+// per-adapter format features allow more sample counts than WebGPU guarantees
+fn adapter_format_features(adapter: &wgpu::Adapter) -> wgpu::Features {
+    adapter.features() & wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
+}
+
+fn create_offscreen_texture(
+    device: &wgpu::Device,
+    config: &wgpu::SurfaceConfiguration,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Offscreen Texture"),
+        size: wgpu::Extent3d {
+            width: config.width,
+            height: config.height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: config.format,
+        usage: config.usage | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
@@ -0,0 +1,119 @@
+//! Per-instance vertex data for drawing many copies of a mesh with one draw call.
+//!
+//! An [`InstanceBuffer`] holds one [`Instance`] per copy in a vertex buffer that the
+//! pipeline steps once per instance, see [`Instance::layout`], and is drawn with
+//! [`GpuMesh::draw_instanced`](crate::geometry::GpuMesh::draw_instanced).
+use bytemuck::{Pod, Zeroable};
+use cgmath::Matrix4;
+
+use crate::geometry::MeshLayout;
+
+/// Model matrix and color of one copy of a mesh.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Instance {
+    pub model: [[f32; 4]; 4],
+    pub color: [f32; 4],
+}
+
+impl Instance {
+    pub fn new(model: Matrix4<f32>, color: [f32; 4]) -> Self {
+        Self {
+            model: model.into(),
+            color,
+        }
+    }
+
+    /// Instance-stepped layout with the four columns of the model matrix at
+    /// `first_location` to `first_location + 3` and the color right after them. Pick
+    /// `first_location` past the mesh's own attributes.
+    pub fn layout(first_location: u32) -> MeshLayout {
+        let column = wgpu::VertexFormat::Float32x4;
+        let attributes = (0..5)
+            .map(|i| wgpu::VertexAttribute {
+                format: column,
+                offset: i as wgpu::BufferAddress * column.size(),
+                shader_location: first_location + i,
+            })
+            .collect();
+        MeshLayout {
+            array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes,
+        }
+    }
+}
+
+/// A vertex buffer of [`Instance`]s. It grows to the next power of two when an update
+/// holds more instances than fit, and never shrinks, so counts that go up and down
+/// settle without reallocating.
+pub struct InstanceBuffer {
+    pub buffer: wgpu::Buffer,
+    len: usize,
+    capacity: usize,
+}
+
+impl InstanceBuffer {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, instances: &[Instance]) -> Self {
+        let mut buffer = Self::with_capacity(device, instances.len());
+        buffer.update(device, queue, instances);
+        buffer
+    }
+
+    /// An empty buffer with room for `capacity` instances, at least one.
+    pub fn with_capacity(device: &wgpu::Device, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            buffer: create_instance_buffer(device, capacity),
+            len: 0,
+            capacity,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Instances that fit before the buffer has to grow.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Replaces the instances, growing the buffer if they do not fit. Returns whether
+    /// a new buffer was allocated.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        instances: &[Instance],
+    ) -> bool {
+        let grown = instances.len() > self.capacity;
+        if grown {
+            self.capacity = instances.len().next_power_of_two();
+            self.buffer = create_instance_buffer(device, self.capacity);
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice(instances));
+        self.len = instances.len();
+        grown
+    }
+
+    /// The part of the buffer holding the current instances; wgpu does not allow empty
+    /// slices, so check [`InstanceBuffer::is_empty`] first.
+    pub fn slice(&self) -> wgpu::BufferSlice<'_> {
+        let size = (self.len * std::mem::size_of::<Instance>()) as wgpu::BufferAddress;
+        self.buffer.slice(..size)
+    }
+}
+
+fn create_instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Instance Buffer"),
+        size: (capacity * std::mem::size_of::<Instance>()) as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,20 @@
+//! Reusable pieces of the wgpu step-by-step examples: device setup, transforms,
+//! geometry data, instancing, cameras, lighting and shadows, textures, depth and MSAA
+//! targets, a scene graph with frustum culling, ray picking, frame capture and a
+//! minimal app runner.
+pub mod app;
+pub mod camera;
+pub mod capture;
+pub mod culling;
+pub mod depth;
+pub mod geometry;
+pub mod init;
+pub mod instance;
+pub mod lighting;
+pub mod msaa;
+pub mod picking;
+pub mod scene;
+pub mod texture;
+pub mod transforms;
+
+pub use init::{Frame, InitWgpu};
@@ -0,0 +1,103 @@
+// Camera, light and shadow block shared by the lit shaders, bound at group 0. The
+// structs mirror `LightUniform` and `FrameUniform` in src/lighting/mod.rs and
+// `ShadowUniform` in src/lighting/shadow.rs.
+
+const MAX_LIGHTS: u32 = 8u;
+const MAX_CASCADES: u32 = 4u;
+
+const LIGHT_DIRECTIONAL: u32 = 0u;
+const LIGHT_POINT: u32 = 1u;
+const LIGHT_SPOT: u32 = 2u;
+
+struct Light {
+    position: vec3<f32>,
+    kind: u32,
+    direction: vec3<f32>,
+    inner_cos: f32,
+    ambient: vec3<f32>,
+    outer_cos: f32,
+    diffuse: vec3<f32>,
+    specular: vec3<f32>,
+    // constant, linear and quadratic distance falloff
+    attenuation: vec3<f32>,
+};
+
+struct Frame {
+    view_projection: mat4x4<f32>,
+    camera_position: vec3<f32>,
+    light_count: u32,
+    lights: array<Light, MAX_LIGHTS>,
+};
+
+struct Shadows {
+    // world to light clip space per layer, finest cascade first
+    view_projections: array<mat4x4<f32>, MAX_CASCADES>,
+    // index of the shadowed light, or 0xffffffff for none
+    light: u32,
+    layer_count: u32,
+    pcf_kernel: u32,
+    texel_size: f32,
+};
+
+@group(0) @binding(0)
+var<uniform> frame: Frame;
+@group(0) @binding(1)
+var<uniform> shadows: Shadows;
+@group(0) @binding(2)
+var shadow_map: texture_depth_2d_array;
+@group(0) @binding(3)
+var shadow_sampler: sampler_comparison;
+
+// direction from the surface towards the light
+fn light_direction(light: Light, world_position: vec3<f32>) -> vec3<f32> {
+    if light.kind == LIGHT_DIRECTIONAL {
+        return normalize(-light.direction);
+    }
+    return normalize(light.position - world_position);
+}
+
+// distance falloff, 1 for directional lights
+fn light_attenuation(light: Light, world_position: vec3<f32>) -> f32 {
+    if light.kind == LIGHT_DIRECTIONAL {
+        return 1.0;
+    }
+    let d = distance(light.position, world_position);
+    let a = light.attenuation;
+    return 1.0 / max(a.x + a.y * d + a.z * d * d, 1e-4);
+}
+
+// soft edge between the inner and outer cone, 1 for other lights
+fn spot_factor(light: Light, world_position: vec3<f32>) -> f32 {
+    if light.kind != LIGHT_SPOT {
+        return 1.0;
+    }
+    let cos_angle = dot(normalize(world_position - light.position), normalize(light.direction));
+    return smoothstep(light.outer_cos, light.inner_cos, cos_angle);
+}
+
+// fraction of light `index` reaching the surface, from 0 in full shadow to 1; the
+// first, finest layer containing the position is filtered over a pcf_kernel² grid
+fn shadow_factor(index: u32, world_position: vec3<f32>) -> f32 {
+    if index != shadows.light {
+        return 1.0;
+    }
+    for (var layer = 0u; layer < shadows.layer_count; layer++) {
+        let clip = shadows.view_projections[layer] * vec4<f32>(world_position, 1.0);
+        let ndc = clip.xyz / clip.w;
+        let uv = vec2<f32>(ndc.x * 0.5 + 0.5, 0.5 - ndc.y * 0.5);
+        let outside = any(uv < vec2<f32>(0.0)) || any(uv > vec2<f32>(1.0));
+        if clip.w <= 0.0 || outside || ndc.z < 0.0 || ndc.z > 1.0 {
+            continue;
+        }
+        let center = f32(shadows.pcf_kernel - 1u) * 0.5;
+        var lit = 0.0;
+        for (var y = 0u; y < shadows.pcf_kernel; y++) {
+            for (var x = 0u; x < shadows.pcf_kernel; x++) {
+                let offset = (vec2<f32>(f32(x), f32(y)) - center) * shadows.texel_size;
+                lit += textureSampleCompareLevel(shadow_map, shadow_sampler, uv + offset, layer, ndc.z);
+            }
+        }
+        return lit / f32(shadows.pcf_kernel * shadows.pcf_kernel);
+    }
+    return 1.0;
+}
@@ -0,0 +1,285 @@
+//! Directional, point and spot lights, their shadow maps, and the per-frame camera,
+//! light and shadow bindings shared by the lit pipelines.
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+
+pub mod pbr;
+pub mod phong;
+pub mod shadow;
+
+pub use pbr::{PbrMaterialBinding, PbrObject, PbrPipeline, PbrTextures};
+pub use phong::{PhongMaterial, PhongObject, PhongPipeline};
+pub use shadow::{ShadowMap, ShadowPipeline, ShadowSettings};
+
+/// Lights beyond this count are dropped by [`FrameUniform::new`].
+pub const MAX_LIGHTS: usize = 8;
+
+/// WGSL declarations of [`FrameUniform`] and the [`ShadowMap`] bindings at group 0 plus
+/// the attenuation, spot cone and shadow helpers, prepended to the lit shaders.
+pub const LIGHTS_WGSL: &str = include_str!("lights.wgsl");
+
+/// Ambient, diffuse and specular contributions of a light, in linear RGB.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightColor {
+    pub ambient: [f32; 3],
+    pub diffuse: [f32; 3],
+    pub specular: [f32; 3],
+}
+
+impl LightColor {
+    /// `color` for diffuse and specular, scaled by `ambient` for the ambient term.
+    pub fn new(color: [f32; 3], ambient: f32) -> Self {
+        Self {
+            ambient: color.map(|c| c * ambient),
+            diffuse: color,
+            specular: color,
+        }
+    }
+}
+
+/// Distance falloff `1 / (constant + linear·d + quadratic·d²)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attenuation {
+    pub constant: f32,
+    pub linear: f32,
+    pub quadratic: f32,
+}
+
+impl Default for Attenuation {
+    /// Falls to about 2% at 50 units.
+    fn default() -> Self {
+        Self {
+            constant: 1.0,
+            linear: 0.09,
+            quadratic: 0.032,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Light {
+    /// Parallel rays travelling along `direction`, like sunlight.
+    Directional {
+        direction: Vector3<f32>,
+        color: LightColor,
+    },
+    /// Light radiating from `position` in every direction.
+    Point {
+        position: Point3<f32>,
+        color: LightColor,
+        attenuation: Attenuation,
+    },
+    /// A cone from `position` along `direction`: full intensity within `inner_angle`
+    /// of the axis, fading to none at `outer_angle`.
+    Spot {
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        inner_angle: Rad<f32>,
+        outer_angle: Rad<f32>,
+        color: LightColor,
+        attenuation: Attenuation,
+    },
+}
+
+/// One light as laid out in the `Light` struct of `lights.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct LightUniform {
+    pub position: [f32; 3],
+    /// 0 directional, 1 point, 2 spot.
+    pub kind: u32,
+    pub direction: [f32; 3],
+    pub inner_cos: f32,
+    pub ambient: [f32; 3],
+    pub outer_cos: f32,
+    pub diffuse: [f32; 3],
+    _padding0: f32,
+    pub specular: [f32; 3],
+    _padding1: f32,
+    pub attenuation: [f32; 3],
+    _padding2: f32,
+}
+
+impl From<&Light> for LightUniform {
+    fn from(light: &Light) -> Self {
+        let mut uniform = LightUniform::default();
+        let (color, attenuation) = match *light {
+            Light::Directional { direction, color } => {
+                uniform.direction = direction.into();
+                (color, Attenuation::default())
+            }
+            Light::Point {
+                position,
+                color,
+                attenuation,
+            } => {
+                uniform.kind = 1;
+                uniform.position = position.into();
+                (color, attenuation)
+            }
+            Light::Spot {
+                position,
+                direction,
+                inner_angle,
+                outer_angle,
+                color,
+                attenuation,
+            } => {
+                uniform.kind = 2;
+                uniform.position = position.into();
+                uniform.direction = direction.into();
+                uniform.inner_cos = inner_angle.cos();
+                uniform.outer_cos = outer_angle.cos();
+                (color, attenuation)
+            }
+        };
+        uniform.ambient = color.ambient;
+        uniform.diffuse = color.diffuse;
+        uniform.specular = color.specular;
+        uniform.attenuation = [
+            attenuation.constant,
+            attenuation.linear,
+            attenuation.quadratic,
+        ];
+        uniform
+    }
+}
+
+/// Camera and lights for one frame, the `Frame` struct of `lights.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct FrameUniform {
+    pub view_projection: [[f32; 4]; 4],
+    pub camera_position: [f32; 3],
+    pub light_count: u32,
+    pub lights: [LightUniform; MAX_LIGHTS],
+}
+
+impl FrameUniform {
+    pub fn new(
+        view_projection: Matrix4<f32>,
+        camera_position: Point3<f32>,
+        lights: &[Light],
+    ) -> Self {
+        if lights.len() > MAX_LIGHTS {
+            log::warn!(
+                "{} lights given, only the first {} are used",
+                lights.len(),
+                MAX_LIGHTS
+            );
+        }
+        let mut uniform = Self {
+            view_projection: view_projection.into(),
+            camera_position: camera_position.into(),
+            light_count: lights.len().min(MAX_LIGHTS) as u32,
+            lights: [LightUniform::default(); MAX_LIGHTS],
+        };
+        for (slot, light) in uniform.lights.iter_mut().zip(lights) {
+            *slot = light.into();
+        }
+        uniform
+    }
+}
+
+/// The matrix that carries normals to world space: the inverse transpose of `model`,
+/// without its translation. Falls back to `model` itself if it is singular.
+pub fn normal_matrix(model: Matrix4<f32>) -> Matrix4<f32> {
+    let linear = Matrix3::from_cols(model.x.truncate(), model.y.truncate(), model.z.truncate());
+    let normal = linear.invert().unwrap_or(linear).transpose();
+    Matrix4::from(normal)
+}
+
+// a bind group layout with one uniform buffer at binding 0
+fn uniform_layout(device: &wgpu::Device, label: &str) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some(label),
+    })
+}
+
+// the group 0 layout, buffer and bind group of a `FrameUniform`, followed by the
+// uniform, texture and comparison sampler of `shadow_map`
+fn frame_binding(
+    device: &wgpu::Device,
+    shadow_map: &ShadowMap,
+) -> (wgpu::BindGroupLayout, wgpu::Buffer, wgpu::BindGroup) {
+    let fragment_entry = |binding, ty| wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty,
+        count: None,
+    };
+    let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            },
+            fragment_entry(
+                1,
+                wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+            ),
+            fragment_entry(
+                2,
+                wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Depth,
+                    view_dimension: wgpu::TextureViewDimension::D2Array,
+                    multisampled: false,
+                },
+            ),
+            fragment_entry(
+                3,
+                wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Comparison),
+            ),
+        ],
+        label: Some("Frame Bind Group Layout"),
+    });
+    let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Frame Uniform Buffer"),
+        size: std::mem::size_of::<FrameUniform>() as wgpu::BufferAddress,
+        usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        layout: &layout,
+        entries: &[
+            wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 1,
+                resource: shadow_map.buffer.as_entire_binding(),
+            },
+            wgpu::BindGroupEntry {
+                binding: 2,
+                resource: wgpu::BindingResource::TextureView(&shadow_map.view),
+            },
+            wgpu::BindGroupEntry {
+                binding: 3,
+                resource: wgpu::BindingResource::Sampler(&shadow_map.sampler),
+            },
+        ],
+        label: Some("Frame Bind Group"),
+    });
+    (layout, buffer, bind_group)
+}
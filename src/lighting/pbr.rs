@@ -0,0 +1,371 @@
+//! Metallic-roughness shading with a Cook-Torrance BRDF (GGX distribution, Smith
+//! geometry, Schlick Fresnel) for meshes with positions, normals and uvs at locations
+//! 0, 1 and 2. Materials are the glTF [`PbrMaterial`]s; their texture maps are
+//! optional and default to white.
+use bytemuck::{Pod, Zeroable};
+use cgmath::Matrix4;
+use wgpu::util::DeviceExt;
+
+use super::{frame_binding, normal_matrix, uniform_layout, FrameUniform, ShadowMap, LIGHTS_WGSL};
+use crate::geometry::gltf::AlphaMode;
+use crate::geometry::{GpuMesh, MeshLayout, PbrMaterial};
+
+/// Model and normal matrix of one object, the `Object` struct of `pbr.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PbrObjectUniform {
+    pub model: [[f32; 4]; 4],
+    pub normal: [[f32; 4]; 4],
+}
+
+impl PbrObjectUniform {
+    pub fn new(model: Matrix4<f32>) -> Self {
+        Self {
+            model: model.into(),
+            normal: normal_matrix(model).into(),
+        }
+    }
+}
+
+/// Material factors, the `Material` struct of `pbr.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct PbrMaterialUniform {
+    pub base_color: [f32; 4],
+    pub emissive: [f32; 3],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub occlusion_strength: f32,
+    pub normal_scale: f32,
+    /// Negative unless the material is alpha-masked.
+    pub alpha_cutoff: f32,
+    pub has_normal_texture: u32,
+    _padding: [u32; 3],
+}
+
+impl PbrMaterialUniform {
+    pub fn new(material: &PbrMaterial, has_normal_texture: bool) -> Self {
+        Self {
+            base_color: material.base_color_factor,
+            emissive: material.emissive_factor,
+            metallic: material.metallic_factor,
+            roughness: material.roughness_factor,
+            occlusion_strength: material.occlusion_strength,
+            normal_scale: material.normal_scale,
+            alpha_cutoff: match material.alpha_mode {
+                AlphaMode::Mask => material.alpha_cutoff,
+                AlphaMode::Opaque | AlphaMode::Blend => -1.0,
+            },
+            has_normal_texture: has_normal_texture as u32,
+            _padding: [0; 3],
+        }
+    }
+}
+
+/// Texture maps of a material; missing maps sample as white, which leaves the factors
+/// unchanged. The base color and emissive maps should use an sRGB format.
+#[derive(Clone, Copy, Default)]
+pub struct PbrTextures<'a> {
+    pub base_color: Option<&'a wgpu::TextureView>,
+    /// Metalness in blue, roughness in green.
+    pub metallic_roughness: Option<&'a wgpu::TextureView>,
+    /// Tangent-space normals; the tangent frame is derived in the fragment shader.
+    pub normal: Option<&'a wgpu::TextureView>,
+    pub occlusion: Option<&'a wgpu::TextureView>,
+    pub emissive: Option<&'a wgpu::TextureView>,
+    /// Defaults to a linear, repeating sampler.
+    pub sampler: Option<&'a wgpu::Sampler>,
+}
+
+/// Uniform buffer and bind group of one object drawn with a [`PbrPipeline`].
+pub struct PbrObject {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl PbrObject {
+    pub fn update(&self, queue: &wgpu::Queue, model: Matrix4<f32>) {
+        let uniform = PbrObjectUniform::new(model);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+}
+
+/// Uniform buffer and the material bind group with its textures.
+pub struct PbrMaterialBinding {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+    has_normal_texture: bool,
+}
+
+impl PbrMaterialBinding {
+    /// Rewrites the factors; the bound textures stay the same.
+    pub fn update(&self, queue: &wgpu::Queue, material: &PbrMaterial) {
+        let uniform = PbrMaterialUniform::new(material, self.has_normal_texture);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+}
+
+/// The PBR pipeline with its frame uniform (group 0). Objects bring their matrices at
+/// group 1 and materials their factors and textures at group 2.
+pub struct PbrPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub frame_buffer: wgpu::Buffer,
+    pub frame_bind_group: wgpu::BindGroup,
+    object_layout: wgpu::BindGroupLayout,
+    material_layout: wgpu::BindGroupLayout,
+    white: wgpu::TextureView,
+    sampler: wgpu::Sampler,
+}
+
+fn texture_entry(binding: u32) -> wgpu::BindGroupLayoutEntry {
+    wgpu::BindGroupLayoutEntry {
+        binding,
+        visibility: wgpu::ShaderStages::FRAGMENT,
+        ty: wgpu::BindingType::Texture {
+            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+            view_dimension: wgpu::TextureViewDimension::D2,
+            multisampled: false,
+        },
+        count: None,
+    }
+}
+
+impl PbrPipeline {
+    /// `vertex_layout` must put `Float32x3` positions and normals at locations 0 and 1
+    /// and `Float32x2` uvs at location 2, as [`crate::geometry::Mesh::layout`] does for
+    /// meshes with normals and uvs. `sample_count` must match the color and depth
+    /// targets.
+    ///
+    /// The lights are shadowed as set up by the last [`ShadowMap::update`] of
+    /// `shadow_map`.
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        color_format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        sample_count: u32,
+        vertex_layout: &MeshLayout,
+        shadow_map: &ShadowMap,
+    ) -> Self {
+        let source = format!("{}\n{}", LIGHTS_WGSL, include_str!("pbr.wgsl"));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("PBR Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let (frame_layout, frame_buffer, frame_bind_group) = frame_binding(device, shadow_map);
+        let object_layout = uniform_layout(device, "PBR Object Bind Group Layout");
+        let material_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+                texture_entry(2),
+                texture_entry(3),
+                texture_entry(4),
+                texture_entry(5),
+                texture_entry(6),
+            ],
+            label: Some("PBR Material Bind Group Layout"),
+        });
+
+        let white = device
+            .create_texture_with_data(
+                queue,
+                &wgpu::TextureDescriptor {
+                    label: Some("White Texture"),
+                    size: wgpu::Extent3d {
+                        width: 1,
+                        height: 1,
+                        depth_or_array_layers: 1,
+                    },
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    usage: wgpu::TextureUsages::TEXTURE_BINDING,
+                    view_formats: &[],
+                },
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &[255; 4],
+            )
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("PBR Material Sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("PBR Pipeline Layout"),
+            bind_group_layouts: &[&frame_layout, &object_layout, &material_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("PBR Pipeline"),
+            cache: None,
+            multiview: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_layout.buffer_layout()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+        });
+
+        Self {
+            pipeline,
+            frame_buffer,
+            frame_bind_group,
+            object_layout,
+            material_layout,
+            white,
+            sampler,
+        }
+    }
+
+    pub fn set_frame(&self, queue: &wgpu::Queue, frame: &FrameUniform) {
+        queue.write_buffer(&self.frame_buffer, 0, bytemuck::bytes_of(frame));
+    }
+
+    pub fn create_object(&self, device: &wgpu::Device, model: Matrix4<f32>) -> PbrObject {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("PBR Object Uniform Buffer"),
+            contents: bytemuck::bytes_of(&PbrObjectUniform::new(model)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.object_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("PBR Object Bind Group"),
+        });
+        PbrObject { buffer, bind_group }
+    }
+
+    pub fn create_material(
+        &self,
+        device: &wgpu::Device,
+        material: &PbrMaterial,
+        textures: &PbrTextures<'_>,
+    ) -> PbrMaterialBinding {
+        let has_normal_texture = textures.normal.is_some();
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("PBR Material Uniform Buffer"),
+            contents: bytemuck::bytes_of(&PbrMaterialUniform::new(material, has_normal_texture)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let maps = [
+            textures.base_color,
+            textures.metallic_roughness,
+            textures.normal,
+            textures.occlusion,
+            textures.emissive,
+        ]
+        .map(|map| map.unwrap_or(&self.white));
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.material_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(
+                        textures.sampler.unwrap_or(&self.sampler),
+                    ),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(maps[0]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 3,
+                    resource: wgpu::BindingResource::TextureView(maps[1]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 4,
+                    resource: wgpu::BindingResource::TextureView(maps[2]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 5,
+                    resource: wgpu::BindingResource::TextureView(maps[3]),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 6,
+                    resource: wgpu::BindingResource::TextureView(maps[4]),
+                },
+            ],
+            label: Some("PBR Material Bind Group"),
+        });
+        PbrMaterialBinding {
+            buffer,
+            bind_group,
+            has_normal_texture,
+        }
+    }
+
+    /// Draws `mesh` with the matrices of `object` and the factors and maps of
+    /// `material`.
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        object: &PbrObject,
+        material: &PbrMaterialBinding,
+        mesh: &GpuMesh,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.frame_bind_group, &[]);
+        render_pass.set_bind_group(1, &object.bind_group, &[]);
+        render_pass.set_bind_group(2, &material.bind_group, &[]);
+        mesh.draw(render_pass);
+    }
+}
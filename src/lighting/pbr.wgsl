@@ -0,0 +1,152 @@
+// Metallic-roughness shading with a Cook-Torrance BRDF: GGX distribution, Smith
+// geometry and Schlick Fresnel. Prepended with lights.wgsl.
+
+const PI: f32 = 3.14159265;
+
+struct Object {
+    model: mat4x4<f32>,
+    // inverse transpose of the model matrix, for normals under non-uniform scale
+    normal: mat4x4<f32>,
+};
+
+struct Material {
+    base_color: vec4<f32>,
+    emissive: vec3<f32>,
+    metallic: f32,
+    roughness: f32,
+    occlusion_strength: f32,
+    normal_scale: f32,
+    // fragments with a lower alpha are discarded; negative keeps every fragment
+    alpha_cutoff: f32,
+    has_normal_texture: u32,
+};
+
+@group(1) @binding(0)
+var<uniform> object: Object;
+
+@group(2) @binding(0)
+var<uniform> material: Material;
+@group(2) @binding(1)
+var material_sampler: sampler;
+@group(2) @binding(2)
+var base_color_texture: texture_2d<f32>;
+// metalness in blue, roughness in green
+@group(2) @binding(3)
+var metallic_roughness_texture: texture_2d<f32>;
+@group(2) @binding(4)
+var normal_texture: texture_2d<f32>;
+@group(2) @binding(5)
+var occlusion_texture: texture_2d<f32>;
+@group(2) @binding(6)
+var emissive_texture: texture_2d<f32>;
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) uv: vec2<f32>,
+) -> Output {
+    let world = object.model * vec4<f32>(position, 1.0);
+    var output: Output;
+    output.position = frame.view_projection * world;
+    output.world_position = world.xyz;
+    output.world_normal = (object.normal * vec4<f32>(normal, 0.0)).xyz;
+    output.uv = uv;
+    return output;
+}
+
+fn distribution_ggx(n_dot_h: f32, roughness: f32) -> f32 {
+    let a = roughness * roughness;
+    let a2 = a * a;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    return a2 / (PI * d * d);
+}
+
+fn geometry_schlick_ggx(n_dot_x: f32, roughness: f32) -> f32 {
+    let r = roughness + 1.0;
+    let k = r * r / 8.0;
+    return n_dot_x / (n_dot_x * (1.0 - k) + k);
+}
+
+fn geometry_smith(n_dot_v: f32, n_dot_l: f32, roughness: f32) -> f32 {
+    return geometry_schlick_ggx(n_dot_v, roughness) * geometry_schlick_ggx(n_dot_l, roughness);
+}
+
+fn fresnel_schlick(cos_theta: f32, f0: vec3<f32>) -> vec3<f32> {
+    return f0 + (1.0 - f0) * pow(clamp(1.0 - cos_theta, 0.0, 1.0), 5.0);
+}
+
+// tangent frame from screen-space derivatives, for meshes without tangents
+fn perturb_normal(n: vec3<f32>, position: vec3<f32>, uv: vec2<f32>, sample: vec3<f32>) -> vec3<f32> {
+    let dp_dx = dpdx(position);
+    let dp_dy = dpdy(position);
+    let duv_dx = dpdx(uv);
+    let duv_dy = dpdy(uv);
+    let det = duv_dx.x * duv_dy.y - duv_dy.x * duv_dx.y;
+    var t = (duv_dy.y * dp_dx - duv_dx.y * dp_dy) / select(det, 1e-6, abs(det) < 1e-12);
+    t = t - n * dot(n, t);
+    if dot(t, t) < 1e-12 {
+        return n;
+    }
+    t = normalize(t);
+    let b = cross(n, t);
+    return normalize(mat3x3<f32>(t, b, n) * sample);
+}
+
+@fragment
+fn fs_main(input: Output) -> @location(0) vec4<f32> {
+    let base_sample = textureSample(base_color_texture, material_sampler, input.uv);
+    let mr_sample = textureSample(metallic_roughness_texture, material_sampler, input.uv);
+    let normal_sample = textureSample(normal_texture, material_sampler, input.uv).xyz;
+    let occlusion_sample = textureSample(occlusion_texture, material_sampler, input.uv).r;
+    let emissive_sample = textureSample(emissive_texture, material_sampler, input.uv).rgb;
+    let geometric_normal = normalize(input.world_normal);
+    let mapped_normal = perturb_normal(
+        geometric_normal,
+        input.world_position,
+        input.uv,
+        (normal_sample * 2.0 - 1.0) * vec3<f32>(material.normal_scale, material.normal_scale, 1.0),
+    );
+
+    let base_color = material.base_color * base_sample;
+    if base_color.a < material.alpha_cutoff {
+        discard;
+    }
+    let metallic = clamp(material.metallic * mr_sample.b, 0.0, 1.0);
+    let roughness = clamp(material.roughness * mr_sample.g, 0.04, 1.0);
+    let occlusion = mix(1.0, occlusion_sample, material.occlusion_strength);
+
+    let n = select(geometric_normal, mapped_normal, material.has_normal_texture != 0u);
+    let v = normalize(frame.camera_position - input.world_position);
+    let n_dot_v = max(dot(n, v), 1e-4);
+    let f0 = mix(vec3<f32>(0.04), base_color.rgb, metallic);
+
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < min(frame.light_count, MAX_LIGHTS); i++) {
+        let light = frame.lights[i];
+        let l = light_direction(light, input.world_position);
+        let h = normalize(l + v);
+        let n_dot_l = max(dot(n, l), 0.0);
+        let attenuation = light_attenuation(light, input.world_position);
+        let shadow = shadow_factor(i, input.world_position);
+        let radiance = light.diffuse * attenuation * spot_factor(light, input.world_position) * shadow;
+
+        let d = distribution_ggx(max(dot(n, h), 0.0), roughness);
+        let g = geometry_smith(n_dot_v, n_dot_l, roughness);
+        let f = fresnel_schlick(max(dot(h, v), 0.0), f0);
+        let specular = d * g * f / (4.0 * n_dot_v * max(n_dot_l, 1e-4));
+        let k_d = (1.0 - f) * (1.0 - metallic);
+
+        color += (k_d * base_color.rgb / PI + specular) * radiance * n_dot_l;
+        color += light.ambient * attenuation * base_color.rgb * occlusion;
+    }
+    color += material.emissive * emissive_sample;
+    return vec4<f32>(color, base_color.a);
+}
@@ -0,0 +1,189 @@
+//! Blinn-Phong shading: a render pipeline for meshes with positions at location 0 and
+//! normals at location 1, lit by the lights of a [`FrameUniform`].
+use bytemuck::{Pod, Zeroable};
+use cgmath::Matrix4;
+use wgpu::util::DeviceExt;
+
+use super::{frame_binding, normal_matrix, uniform_layout, FrameUniform, ShadowMap, LIGHTS_WGSL};
+use crate::geometry::{GpuMesh, MeshLayout};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PhongMaterial {
+    /// Base color for the ambient and diffuse terms; alpha is written out unchanged.
+    pub diffuse: [f32; 4],
+    pub specular: [f32; 3],
+    /// Specular exponent; higher is a smaller, sharper highlight.
+    pub shininess: f32,
+}
+
+impl Default for PhongMaterial {
+    fn default() -> Self {
+        Self {
+            diffuse: [0.8, 0.8, 0.8, 1.0],
+            specular: [0.5, 0.5, 0.5],
+            shininess: 32.0,
+        }
+    }
+}
+
+/// Model matrices and material of one object, the `Object` struct of `phong.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ObjectUniform {
+    pub model: [[f32; 4]; 4],
+    pub normal: [[f32; 4]; 4],
+    pub diffuse: [f32; 4],
+    pub specular: [f32; 3],
+    pub shininess: f32,
+}
+
+impl ObjectUniform {
+    pub fn new(model: Matrix4<f32>, material: &PhongMaterial) -> Self {
+        Self {
+            model: model.into(),
+            normal: normal_matrix(model).into(),
+            diffuse: material.diffuse,
+            specular: material.specular,
+            shininess: material.shininess,
+        }
+    }
+}
+
+/// Uniform buffer and bind group of one object drawn with a [`PhongPipeline`].
+pub struct PhongObject {
+    pub buffer: wgpu::Buffer,
+    pub bind_group: wgpu::BindGroup,
+}
+
+impl PhongObject {
+    pub fn update(&self, queue: &wgpu::Queue, model: Matrix4<f32>, material: &PhongMaterial) {
+        let uniform = ObjectUniform::new(model, material);
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+    }
+}
+
+/// The Blinn-Phong pipeline with its frame uniform (group 0); objects bring their own
+/// uniform at group 1.
+pub struct PhongPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+    pub frame_buffer: wgpu::Buffer,
+    pub frame_bind_group: wgpu::BindGroup,
+    object_layout: wgpu::BindGroupLayout,
+}
+
+impl PhongPipeline {
+    /// `vertex_layout` must put a `Float32x3` position at location 0 and a `Float32x3`
+    /// normal at location 1, as [`crate::geometry::Mesh::layout`] does for meshes with
+    /// normals; further attributes are ignored. `sample_count` must match the color and
+    /// depth targets.
+    ///
+    /// The lights are shadowed as set up by the last [`ShadowMap::update`] of
+    /// `shadow_map`.
+    pub fn new(
+        device: &wgpu::Device,
+        color_format: wgpu::TextureFormat,
+        depth_format: Option<wgpu::TextureFormat>,
+        sample_count: u32,
+        vertex_layout: &MeshLayout,
+        shadow_map: &ShadowMap,
+    ) -> Self {
+        let source = format!("{}\n{}", LIGHTS_WGSL, include_str!("phong.wgsl"));
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Blinn-Phong Shader"),
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        });
+
+        let (frame_layout, frame_buffer, frame_bind_group) = frame_binding(device, shadow_map);
+        let object_layout = uniform_layout(device, "Phong Object Bind Group Layout");
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Blinn-Phong Pipeline Layout"),
+            bind_group_layouts: &[&frame_layout, &object_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Blinn-Phong Pipeline"),
+            cache: None,
+            multiview: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_layout.buffer_layout()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: color_format,
+                    blend: Some(wgpu::BlendState::REPLACE),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: depth_format.map(|format| wgpu::DepthStencilState {
+                format,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                ..Default::default()
+            },
+        });
+
+        Self {
+            pipeline,
+            frame_buffer,
+            frame_bind_group,
+            object_layout,
+        }
+    }
+
+    pub fn set_frame(&self, queue: &wgpu::Queue, frame: &FrameUniform) {
+        queue.write_buffer(&self.frame_buffer, 0, bytemuck::bytes_of(frame));
+    }
+
+    pub fn create_object(
+        &self,
+        device: &wgpu::Device,
+        model: Matrix4<f32>,
+        material: &PhongMaterial,
+    ) -> PhongObject {
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Phong Object Uniform Buffer"),
+            contents: bytemuck::bytes_of(&ObjectUniform::new(model, material)),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &self.object_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: buffer.as_entire_binding(),
+            }],
+            label: Some("Phong Object Bind Group"),
+        });
+        PhongObject { buffer, bind_group }
+    }
+
+    /// Draws `mesh` with the uniform of `object`.
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        object: &PhongObject,
+        mesh: &GpuMesh,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.frame_bind_group, &[]);
+        render_pass.set_bind_group(1, &object.bind_group, &[]);
+        mesh.draw(render_pass);
+    }
+}
@@ -0,0 +1,53 @@
+// Blinn-Phong shading; prepended with lights.wgsl.
+
+struct Object {
+    model: mat4x4<f32>,
+    // inverse transpose of the model matrix, for normals under non-uniform scale
+    normal: mat4x4<f32>,
+    diffuse: vec4<f32>,
+    specular: vec3<f32>,
+    shininess: f32,
+};
+
+@group(1) @binding(0)
+var<uniform> object: Object;
+
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) world_position: vec3<f32>,
+    @location(1) world_normal: vec3<f32>,
+};
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>, @location(1) normal: vec3<f32>) -> Output {
+    let world = object.model * vec4<f32>(position, 1.0);
+    var output: Output;
+    output.position = frame.view_projection * world;
+    output.world_position = world.xyz;
+    output.world_normal = (object.normal * vec4<f32>(normal, 0.0)).xyz;
+    return output;
+}
+
+@fragment
+fn fs_main(input: Output) -> @location(0) vec4<f32> {
+    let n = normalize(input.world_normal);
+    let v = normalize(frame.camera_position - input.world_position);
+    let base = object.diffuse.rgb;
+
+    var color = vec3<f32>(0.0);
+    for (var i = 0u; i < min(frame.light_count, MAX_LIGHTS); i++) {
+        let light = frame.lights[i];
+        let l = light_direction(light, input.world_position);
+        let h = normalize(l + v);
+        let n_dot_l = max(dot(n, l), 0.0);
+        var specular = 0.0;
+        if n_dot_l > 0.0 {
+            specular = pow(max(dot(n, h), 0.0), object.shininess);
+        }
+        let attenuation = light_attenuation(light, input.world_position);
+        let spot = spot_factor(light, input.world_position) * shadow_factor(i, input.world_position);
+        let diffuse = light.diffuse * base * n_dot_l;
+        color += attenuation * (light.ambient * base + spot * (diffuse + light.specular * object.specular * specular));
+    }
+    return vec4<f32>(color, object.diffuse.a);
+}
@@ -0,0 +1,491 @@
+//! Shadow maps for directional and spot lights.
+//!
+//! A [`ShadowMap`] renders the depth of shadow casters as seen from one light into the
+//! layers of a depth texture array, which the lit pipelines test fragments against with
+//! a comparison sampler and percentage-closer filtering (PCF). A spot light uses one
+//! perspective layer. A directional light splits the camera frustum into cascades, each
+//! covered by its own orthographic layer, so shadows near the camera get the most
+//! texels.
+use bytemuck::{Pod, Zeroable};
+use cgmath::*;
+use wgpu::util::DeviceExt;
+
+use super::{uniform_layout, Light, MAX_LIGHTS};
+use crate::geometry::{GpuMesh, MeshLayout};
+use crate::transforms::{create_view, Projection};
+
+/// Layers of the shadow map, and so the most cascades a directional light can use.
+pub const MAX_CASCADES: usize = 4;
+
+pub const SHADOW_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// The `light` of a [`ShadowUniform`] when no light casts shadows.
+pub const NO_SHADOW_LIGHT: u32 = u32::MAX;
+
+// near plane of spot light shadow frusta
+const SPOT_NEAR: f32 = 0.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShadowSettings {
+    /// Width and height of each layer in texels, more than 2.
+    pub size: u32,
+    /// Cascades of a directional light, 1 to [`MAX_CASCADES`].
+    pub cascades: u32,
+    /// Blend between uniform (0) and logarithmic (1) cascade splits, see
+    /// [`cascade_splits`].
+    pub split_lambda: f32,
+    /// How far from the camera directional lights cast shadows, and how far spot
+    /// light shadows reach from the light.
+    pub distance: f32,
+    /// Width of the PCF kernel in texels: 1 takes a single bilinear comparison, 3 a
+    /// 3x3 grid of them, and so on.
+    pub pcf_kernel: u32,
+    /// Bias added to the depth of shadow casters, against self-shadowing acne. Read
+    /// when a [`ShadowPipeline`] is created.
+    pub depth_bias: wgpu::DepthBiasState,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            size: 1024,
+            cascades: 4,
+            split_lambda: 0.75,
+            distance: 50.0,
+            pcf_kernel: 3,
+            depth_bias: wgpu::DepthBiasState {
+                constant: 2,
+                slope_scale: 2.0,
+                clamp: 0.0,
+            },
+        }
+    }
+}
+
+/// Which light casts shadows and from where, the `Shadows` struct of `lights.wgsl`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct ShadowUniform {
+    /// World to light clip space for each used layer, finest cascade first.
+    pub view_projections: [[[f32; 4]; 4]; MAX_CASCADES],
+    /// Index into the lights of the [`FrameUniform`](super::FrameUniform), or
+    /// [`NO_SHADOW_LIGHT`].
+    pub light: u32,
+    pub layer_count: u32,
+    pub pcf_kernel: u32,
+    /// One texel in texture coordinates.
+    pub texel_size: f32,
+}
+
+impl Default for ShadowUniform {
+    fn default() -> Self {
+        Self {
+            view_projections: [Matrix4::identity().into(); MAX_CASCADES],
+            light: NO_SHADOW_LIGHT,
+            layer_count: 0,
+            pcf_kernel: 1,
+            texel_size: 0.0,
+        }
+    }
+}
+
+/// Distances from the camera at which `count` cascades between `near` and `far` begin
+/// and end, `count + 1` values. `lambda` blends evenly spaced splits (0) with
+/// logarithmic ones (1), which keep the texel density on screen constant.
+///
+/// `count` must be at least 1 and `far` greater than `near`. `near` may be 0, as for
+/// orthographic cameras, since the logarithmic splits start from no less than a
+/// thousandth of `far`; the first split is still `near`.
+pub fn cascade_splits(near: f32, far: f32, count: u32, lambda: f32) -> Vec<f32> {
+    debug_assert!(count > 0, "cascade_splits needs at least one cascade");
+    debug_assert!(
+        near >= 0.0 && far > near,
+        "cascade_splits needs 0 <= near < far, got {} and {}",
+        near,
+        far
+    );
+    let log_near = near.max(far * 1e-3);
+    (0..=count)
+        .map(|i| {
+            if i == 0 {
+                return near;
+            }
+            let t = i as f32 / count as f32;
+            let logarithmic = log_near * (far / log_near).powf(t);
+            let uniform = near + (far - near) * t;
+            lambda * logarithmic + (1.0 - lambda) * uniform
+        })
+        .collect()
+}
+
+/// The corners of the clip volume of `view_projection` in world space, those at depth
+/// 0 first. Panics if the matrix is singular.
+pub fn frustum_corners(view_projection: Matrix4<f32>) -> [Point3<f32>; 8] {
+    let inverse = view_projection
+        .invert()
+        .expect("view-projection is singular");
+    let mut corners = [Point3::origin(); 8];
+    for (i, corner) in corners.iter_mut().enumerate() {
+        let x = if i & 1 == 0 { -1.0 } else { 1.0 };
+        let y = if i & 2 == 0 { -1.0 } else { 1.0 };
+        let z = if i & 4 == 0 { 0.0 } else { 1.0 };
+        *corner = Point3::from_homogeneous(inverse * Vector4::new(x, y, z, 1.0));
+    }
+    corners
+}
+
+// an up vector that is not parallel to `direction`
+fn light_up(direction: Vector3<f32>) -> Vector3<f32> {
+    if direction.normalize().y.abs() > 0.99 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_y()
+    }
+}
+
+/// View-projection of a spot light's shadow: a square perspective frustum around the
+/// outer cone, reaching `range` along `direction`.
+pub fn spot_view_projection(
+    position: Point3<f32>,
+    direction: Vector3<f32>,
+    outer_angle: Rad<f32>,
+    range: f32,
+) -> Matrix4<f32> {
+    let view = create_view(position, position + direction, light_up(direction));
+    let fovy = Rad((outer_angle.0 * 2.0).min(Rad::from(Deg(170.0)).0));
+    Projection::perspective(fovy, 1.0, SPOT_NEAR, range).matrix() * view
+}
+
+/// View-projection of one cascade of a directional light shining along `direction`:
+/// an orthographic box around the bounding sphere of `corners`, a slice of the camera
+/// frustum from [`frustum_corners`], extended `caster_distance` towards the light so
+/// that casters outside the slice still shadow it.
+///
+/// The box only moves in whole texels of a `size`-texel map and its size is rounded,
+/// so shadow edges do not shimmer as the camera moves and turns. `size` must be more
+/// than 2, to leave room for the texel of margin on each side.
+pub fn cascade_view_projection(
+    direction: Vector3<f32>,
+    corners: &[Point3<f32>; 8],
+    caster_distance: f32,
+    size: u32,
+) -> Matrix4<f32> {
+    debug_assert!(
+        size > 2,
+        "shadow maps need more than 2 texels, got {}",
+        size
+    );
+    let center = corners
+        .iter()
+        .fold(Vector3::zero(), |sum, corner| sum + corner.to_vec())
+        / 8.0;
+    let radius = corners
+        .iter()
+        .map(|corner| (corner.to_vec() - center).magnitude())
+        .fold(0.0, f32::max);
+    let radius = (radius * 16.0).ceil() / 16.0;
+
+    let up = light_up(direction);
+    let rotation = create_view(Point3::origin(), Point3::from_vec(direction), up);
+    // the box is a texel wider on each side than the sphere, to allow for the snapping
+    let texel = 2.0 * radius / (size as f32 - 2.0);
+    let half_width = radius + texel;
+    let light_center = rotation.transform_vector(center);
+    let snapped = Vector3::new(
+        (light_center.x / texel).floor() * texel,
+        (light_center.y / texel).floor() * texel,
+        light_center.z,
+    );
+    let center = Point3::from_vec(rotation.transpose().transform_vector(snapped));
+
+    let eye = center - direction.normalize() * (radius + caster_distance);
+    let view = create_view(eye, center, up);
+    let depth = 2.0 * radius + caster_distance;
+    Projection::orthographic(2.0 * half_width, 1.0, 0.0, depth).matrix() * view
+}
+
+/// A depth texture array holding the shadow of one light, with its uniform and the
+/// comparison sampler the lit pipelines read it with.
+pub struct ShadowMap {
+    /// Changes to `size` take effect on a new map and to `depth_bias` on a new
+    /// pipeline; the others on the next [`ShadowMap::update`].
+    pub settings: ShadowSettings,
+    pub texture: wgpu::Texture,
+    /// Every layer, for sampling.
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+    /// Holds the [`ShadowUniform`].
+    pub buffer: wgpu::Buffer,
+    uniform: ShadowUniform,
+    layer_views: Vec<wgpu::TextureView>,
+    layer_buffers: Vec<wgpu::Buffer>,
+    layer_bind_groups: Vec<wgpu::BindGroup>,
+    layer_layout: wgpu::BindGroupLayout,
+}
+
+impl ShadowMap {
+    /// Creates the map, failing if `settings.size` is not more than 2 texels or
+    /// exceeds the device's texture size limit.
+    pub fn new(device: &wgpu::Device, settings: ShadowSettings) -> anyhow::Result<Self> {
+        let max_size = device.limits().max_texture_dimension_2d;
+        anyhow::ensure!(
+            settings.size > 2 && settings.size <= max_size,
+            "shadow map size {} is outside 3 to {}",
+            settings.size,
+            max_size
+        );
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Shadow Map"),
+            size: wgpu::Extent3d {
+                width: settings.size,
+                height: settings.size,
+                depth_or_array_layers: MAX_CASCADES as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: SHADOW_FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+            view_formats: &[],
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("Shadow Map View"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Shadow Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            // linear filtering blends four comparisons, so even one tap is soft
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            compare: Some(wgpu::CompareFunction::LessEqual),
+            ..Default::default()
+        });
+        let uniform = ShadowUniform::default();
+        let buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Shadow Uniform Buffer"),
+            contents: bytemuck::bytes_of(&uniform),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let layer_layout = uniform_layout(device, "Shadow Layer Bind Group Layout");
+        let mut layer_views = Vec::new();
+        let mut layer_buffers = Vec::new();
+        let mut layer_bind_groups = Vec::new();
+        for layer in 0..MAX_CASCADES as u32 {
+            layer_views.push(texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Shadow Layer View"),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                base_array_layer: layer,
+                array_layer_count: Some(1),
+                ..Default::default()
+            }));
+            let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Shadow Layer Uniform Buffer"),
+                size: std::mem::size_of::<[[f32; 4]; 4]>() as wgpu::BufferAddress,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            layer_bind_groups.push(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &layer_layout,
+                entries: &[wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: buffer.as_entire_binding(),
+                }],
+                label: Some("Shadow Layer Bind Group"),
+            }));
+            layer_buffers.push(buffer);
+        }
+
+        Ok(Self {
+            settings,
+            texture,
+            view,
+            sampler,
+            buffer,
+            uniform,
+            layer_views,
+            layer_buffers,
+            layer_bind_groups,
+            layer_layout,
+        })
+    }
+
+    /// The uniform written by the last [`ShadowMap::update`].
+    pub fn uniform(&self) -> &ShadowUniform {
+        &self.uniform
+    }
+
+    /// Layers [`ShadowMap::render`] draws into: the cascades of a directional light,
+    /// one for a spot light, none otherwise.
+    pub fn layer_count(&self) -> usize {
+        self.uniform.layer_count as usize
+    }
+
+    /// Fits the shadow of `lights[light]` to a camera with `camera_view` and
+    /// `projection` and writes the uniforms. Point lights, and `None`, cast no
+    /// shadows; call this whenever the camera or the light moves.
+    pub fn update(
+        &mut self,
+        queue: &wgpu::Queue,
+        lights: &[Light],
+        light: Option<usize>,
+        camera_view: Matrix4<f32>,
+        projection: &Projection,
+    ) {
+        let settings = self.settings;
+        let mut uniform = ShadowUniform {
+            pcf_kernel: settings.pcf_kernel.max(1),
+            texel_size: 1.0 / settings.size as f32,
+            ..Default::default()
+        };
+        let view_projections: Vec<Matrix4<f32>> = match light
+            .filter(|&i| i < MAX_LIGHTS)
+            .and_then(|i| lights.get(i))
+        {
+            Some(&Light::Directional { direction, .. }) => {
+                let cascades = settings.cascades.clamp(1, MAX_CASCADES as u32);
+                let far = projection.far.min(settings.distance);
+                let splits = cascade_splits(projection.near, far, cascades, settings.split_lambda);
+                splits
+                    .windows(2)
+                    .map(|range| {
+                        let slice = Projection {
+                            near: range[0],
+                            far: range[1],
+                            reverse_z: false,
+                            ..*projection
+                        };
+                        let corners = frustum_corners(slice.matrix() * camera_view);
+                        cascade_view_projection(
+                            direction,
+                            &corners,
+                            settings.distance,
+                            settings.size,
+                        )
+                    })
+                    .collect()
+            }
+            Some(&Light::Spot {
+                position,
+                direction,
+                outer_angle,
+                ..
+            }) => vec![spot_view_projection(
+                position,
+                direction,
+                outer_angle,
+                settings.distance,
+            )],
+            _ => Vec::new(),
+        };
+        if !view_projections.is_empty() {
+            uniform.light = light.unwrap() as u32;
+            uniform.layer_count = view_projections.len() as u32;
+        }
+        for (layer, matrix) in view_projections.iter().enumerate() {
+            uniform.view_projections[layer] = (*matrix).into();
+            let matrix: [[f32; 4]; 4] = (*matrix).into();
+            queue.write_buffer(&self.layer_buffers[layer], 0, bytemuck::bytes_of(&matrix));
+        }
+        queue.write_buffer(&self.buffer, 0, bytemuck::bytes_of(&uniform));
+        self.uniform = uniform;
+    }
+
+    /// Depth-only pipeline for casters with `vertex_layout`, which must put a
+    /// `Float32x3` position at location 0.
+    pub fn create_pipeline(
+        &self,
+        device: &wgpu::Device,
+        vertex_layout: &MeshLayout,
+    ) -> ShadowPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Shadow Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("shadow.wgsl").into()),
+        });
+        let object_layout = uniform_layout(device, "Shadow Object Bind Group Layout");
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Shadow Pipeline Layout"),
+            bind_group_layouts: &[&self.layer_layout, &object_layout],
+            push_constant_ranges: &[],
+        });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            cache: None,
+            multiview: None,
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[vertex_layout.buffer_layout()],
+                compilation_options: Default::default(),
+            },
+            fragment: None,
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: SHADOW_FORMAT,
+                depth_write_enabled: true,
+                depth_compare: wgpu::CompareFunction::LessEqual,
+                stencil: wgpu::StencilState::default(),
+                bias: self.settings.depth_bias,
+            }),
+            multisample: wgpu::MultisampleState::default(),
+        });
+        ShadowPipeline { pipeline }
+    }
+
+    /// Clears each used layer and calls `draw` with a pass rendering into it, the
+    /// layer's view-projection bound at group 0. Draw the casters with
+    /// [`ShadowPipeline::draw`].
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        mut draw: impl FnMut(&mut wgpu::RenderPass<'_>),
+    ) {
+        for layer in 0..self.layer_count() {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Shadow Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.layer_views[layer],
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: wgpu::StoreOp::Store,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+            render_pass.set_bind_group(0, &self.layer_bind_groups[layer], &[]);
+            draw(&mut render_pass);
+        }
+    }
+}
+
+/// Renders shadow casters into the layers of a [`ShadowMap`].
+pub struct ShadowPipeline {
+    pub pipeline: wgpu::RenderPipeline,
+}
+
+impl ShadowPipeline {
+    /// Draws `mesh` with the object bind group of a lit pipeline, e.g.
+    /// [`PhongObject::bind_group`](super::PhongObject), whose uniform starts with the
+    /// model matrix.
+    pub fn draw(
+        &self,
+        render_pass: &mut wgpu::RenderPass<'_>,
+        object: &wgpu::BindGroup,
+        mesh: &GpuMesh,
+    ) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, object, &[]);
+        mesh.draw(render_pass);
+    }
+}
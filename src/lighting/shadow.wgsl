@@ -0,0 +1,18 @@
+// Depth-only pass rendering shadow casters from the light's view.
+
+// the light's view-projection for the layer being rendered
+@group(0) @binding(0)
+var<uniform> light_view_projection: mat4x4<f32>;
+
+// the leading model matrix of the lit pipelines' object uniforms
+struct Object {
+    model: mat4x4<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> object: Object;
+
+@vertex
+fn vs_main(@location(0) position: vec3<f32>) -> @builtin(position) vec4<f32> {
+    return light_view_projection * object.model * vec4<f32>(position, 1.0);
+}
@@ -0,0 +1,112 @@
+//! Multisample anti-aliasing: a multisampled color buffer that is resolved into the
+//! frame texture at the end of each pass.
+
+/// Sample counts wgpu can render with. Which of them a format supports depends on the
+/// adapter, see [`InitWgpu::sample_count`](crate::InitWgpu::sample_count).
+pub const SAMPLE_COUNTS: [u32; 4] = [1, 2, 4, 8];
+
+/// The multisampled color target of a pass. With a sample count of 1 there is no
+/// buffer and passes draw straight into the frame texture.
+pub struct MsaaTarget {
+    pub texture: Option<wgpu::Texture>,
+    pub view: Option<wgpu::TextureView>,
+    format: wgpu::TextureFormat,
+    sample_count: u32,
+}
+
+impl MsaaTarget {
+    /// Creates the target for frames of `format`; validate `sample_count` with
+    /// [`InitWgpu::sample_count`](crate::InitWgpu::sample_count) first.
+    pub fn new(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let mut target = Self {
+            texture: None,
+            view: None,
+            format,
+            sample_count,
+        };
+        target.allocate(device, width, height);
+        target
+    }
+
+    fn allocate(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        if self.sample_count == 1 {
+            return;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("MSAA Color Texture"),
+            size: wgpu::Extent3d {
+                width: width.max(1),
+                height: height.max(1),
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        });
+        self.view = Some(texture.create_view(&wgpu::TextureViewDescriptor::default()));
+        self.texture = Some(texture);
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.sample_count
+    }
+
+    /// Multisample state for pipelines drawing into this target.
+    pub fn multisample_state(&self) -> wgpu::MultisampleState {
+        wgpu::MultisampleState {
+            count: self.sample_count,
+            ..Default::default()
+        }
+    }
+
+    /// Recreates the buffer if the size changed; zero sizes are ignored. Returns
+    /// whether a new texture was allocated.
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) -> bool {
+        let resized = match &self.texture {
+            Some(texture) => (width, height) != (texture.width(), texture.height()),
+            None => false,
+        };
+        if width == 0 || height == 0 || !resized {
+            return false;
+        }
+        self.allocate(device, width, height);
+        true
+    }
+
+    /// Attachment that clears to `clear` and leaves the result in `frame`: drawn into
+    /// the multisampled buffer and resolved, or drawn into `frame` directly.
+    pub fn color_attachment<'a>(
+        &'a self,
+        frame: &'a wgpu::TextureView,
+        clear: wgpu::Color,
+    ) -> wgpu::RenderPassColorAttachment<'a> {
+        match &self.view {
+            // the samples are not needed once resolved
+            Some(view) => wgpu::RenderPassColorAttachment {
+                view,
+                resolve_target: Some(frame),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Discard,
+                },
+            },
+            None => wgpu::RenderPassColorAttachment {
+                view: frame,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(clear),
+                    store: wgpu::StoreOp::Store,
+                },
+            },
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Picking by ray casting: rays from the cursor through the scene, and their
+//! intersections with boxes, triangles and meshes.
+use cgmath::*;
+
+use crate::geometry::{Aabb, Mesh};
+use crate::scene::{DrawCommand, NodeId};
+use crate::transforms::Projection;
+
+/// Normalized device coordinates of a pixel position, with y pointing up and the
+/// window corners at ±1.
+pub fn screen_to_ndc(x: f32, y: f32, width: u32, height: u32) -> Point2<f32> {
+    Point2::new(2.0 * x / width as f32 - 1.0, 1.0 - 2.0 * y / height as f32)
+}
+
+/// Half-line from `origin` along `direction`, which is unit length unless the ray was
+/// moved with [`Ray::transform`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self {
+            origin,
+            direction: direction.normalize(),
+        }
+    }
+
+    /// Ray from the near plane through `ndc` under a view-projection matrix in wgpu
+    /// clip space, as built with `OPENGL_TO_WGPU_MATRIX` or [`Projection`], where
+    /// depth runs from 0 at the near plane to 1 at the far plane. Returns `None` if the
+    /// matrix cannot be inverted.
+    pub fn from_ndc(ndc: Point2<f32>, view_projection: Matrix4<f32>) -> Option<Self> {
+        unproject(ndc, view_projection, 0.0)
+    }
+
+    /// Like [`Ray::from_ndc`] for a camera whose projection may use reverse-z, where
+    /// the near plane sits at depth 1.
+    pub fn from_camera(
+        ndc: Point2<f32>,
+        view: Matrix4<f32>,
+        projection: &Projection,
+    ) -> Option<Self> {
+        let near_depth = if projection.reverse_z { 1.0 } else { 0.0 };
+        unproject(ndc, projection.matrix() * view, near_depth)
+    }
+
+    pub fn at(&self, distance: f32) -> Point3<f32> {
+        self.origin + self.direction * distance
+    }
+
+    /// The ray in the space `matrix` maps into. The direction keeps the length the
+    /// matrix gives it, so distances along the new ray match distances along this one.
+    pub fn transform(&self, matrix: Matrix4<f32>) -> Self {
+        Self {
+            origin: matrix.transform_point(self.origin),
+            direction: matrix.transform_vector(self.direction),
+        }
+    }
+
+    /// Distances at which the ray enters and leaves the box, by clipping it against the
+    /// three pairs of axis-aligned slabs. A ray starting inside enters at 0.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<(f32, f32)> {
+        let (mut near, mut far) = (0.0_f32, f32::INFINITY);
+        for axis in 0..3 {
+            let origin = self.origin[axis];
+            let direction = self.direction[axis];
+            if direction == 0.0 {
+                // parallel to the slabs: inside them for the whole ray or never
+                if origin < aabb.min[axis] || origin > aabb.max[axis] {
+                    return None;
+                }
+                continue;
+            }
+            let inverse = 1.0 / direction;
+            let a = (aabb.min[axis] - origin) * inverse;
+            let b = (aabb.max[axis] - origin) * inverse;
+            near = near.max(a.min(b));
+            far = far.min(a.max(b));
+            if near > far {
+                return None;
+            }
+        }
+        Some((near, far))
+    }
+
+    /// Möller–Trumbore intersection with the triangle `a, b, c`, from either side.
+    /// Returns the distance along the ray and the weights of `b` and `c` in the hit
+    /// point.
+    pub fn intersect_triangle(
+        &self,
+        a: Point3<f32>,
+        b: Point3<f32>,
+        c: Point3<f32>,
+    ) -> Option<(f32, f32, f32)> {
+        let ab = b - a;
+        let ac = c - a;
+        let p = self.direction.cross(ac);
+        let determinant = ab.dot(p);
+        // the ray runs parallel to the triangle's plane
+        if determinant.abs() < 1e-8 {
+            return None;
+        }
+        let inverse = 1.0 / determinant;
+        let to_origin = self.origin - a;
+        let u = to_origin.dot(p) * inverse;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = to_origin.cross(ab);
+        let v = self.direction.dot(q) * inverse;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let distance = ac.dot(q) * inverse;
+        (distance >= 0.0).then_some((distance, u, v))
+    }
+}
+
+// ray from the point at `near_depth` towards the one at depth 0.5, which is finite for
+// infinite projections too
+fn unproject(ndc: Point2<f32>, view_projection: Matrix4<f32>, near_depth: f32) -> Option<Ray> {
+    let inverse = view_projection.invert()?;
+    let point =
+        |depth: f32| Point3::from_homogeneous(inverse * Vector4::new(ndc.x, ndc.y, depth, 1.0));
+    let near = point(near_depth);
+    Some(Ray::new(near, point(0.5) - near))
+}
+
+/// The closest intersection of a ray with a mesh.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PickHit {
+    /// Distance along the ray.
+    pub distance: f32,
+    /// Index of the triangle; its corners are indices `3 * triangle` to
+    /// `3 * triangle + 2`, or those vertices in meshes without indices.
+    pub triangle: usize,
+    /// Weights of the triangle's three corners in the hit point; they sum to one and
+    /// also interpolate normals, UVs and colors.
+    pub barycentrics: [f32; 3],
+    /// The hit point in the ray's space.
+    pub point: Point3<f32>,
+}
+
+/// Casts `ray` at `mesh` placed by the `model` matrix and returns the closest hit on
+/// either side of any triangle. Rays that miss the mesh's bounding box skip the
+/// triangle tests.
+pub fn pick_mesh(ray: &Ray, mesh: &Mesh, model: Matrix4<f32>) -> Option<PickHit> {
+    // intersect in object space, where the mesh's own positions and bounds apply
+    let local = ray.transform(model.invert()?);
+    local.intersect_aabb(&mesh.aabb()?)?;
+
+    let position = |i: u32| Point3::from(mesh.positions[i as usize]);
+    let indices: Box<dyn Iterator<Item = u32>> = match &mesh.indices {
+        Some(indices) => indices.iter(),
+        None => Box::new(0..mesh.vertex_count() as u32),
+    };
+    let corners: Vec<u32> = indices.collect();
+    corners
+        .chunks_exact(3)
+        .enumerate()
+        .filter_map(|(triangle, corners)| {
+            let [a, b, c] = [corners[0], corners[1], corners[2]].map(position);
+            let (distance, u, v) = local.intersect_triangle(a, b, c)?;
+            Some(PickHit {
+                distance,
+                triangle,
+                barycentrics: [1.0 - u - v, u, v],
+                point: ray.at(distance),
+            })
+        })
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+/// Casts `ray` at every draw command, with `meshes` indexed by mesh handle, and returns
+/// the node that was hit first.
+pub fn pick_draw_commands(
+    ray: &Ray,
+    commands: &[DrawCommand],
+    meshes: &[Mesh],
+) -> Option<(NodeId, PickHit)> {
+    commands
+        .iter()
+        .filter_map(|command| {
+            let mesh = meshes.get(command.mesh)?;
+            Some((command.node, pick_mesh(ray, mesh, command.world)?))
+        })
+        .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+}
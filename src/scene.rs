@@ -0,0 +1,270 @@
+//! A scene graph of nodes with local [`Transform`]s, cached world matrices and optional
+//! mesh and material handles, flattened into draw commands for the renderer and
+//! optionally culled against the view frustum.
+use cgmath::{Matrix4, SquareMatrix};
+
+use crate::culling::Frustum;
+use crate::geometry::{Aabb, GltfScene};
+use crate::transforms::Transform;
+
+/// Index of a node in its [`SceneGraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+#[derive(Clone, Debug)]
+pub struct SceneNode {
+    pub name: String,
+    /// Index of the mesh to draw, into whatever mesh list the renderer keeps.
+    pub mesh: Option<usize>,
+    /// Index of the material to draw the mesh with.
+    pub material: Option<usize>,
+    /// Hidden nodes and their descendants emit no draw commands.
+    pub visible: bool,
+    local: Transform,
+    world: Matrix4<f32>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    dirty: bool,
+}
+
+impl SceneNode {
+    pub fn local(&self) -> &Transform {
+        &self.local
+    }
+
+    /// The world matrix as of the last [`SceneGraph::update`].
+    pub fn world(&self) -> Matrix4<f32> {
+        self.world
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+/// One mesh to draw with its world matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DrawCommand {
+    pub node: NodeId,
+    pub mesh: usize,
+    pub material: Option<usize>,
+    pub world: Matrix4<f32>,
+}
+
+/// Nodes stored in a flat list and linked by [`NodeId`]. Changing a local transform
+/// marks the node dirty; [`SceneGraph::update`] then recomputes the world matrices of
+/// dirty nodes and their descendants only.
+#[derive(Clone, Debug, Default)]
+pub struct SceneGraph {
+    nodes: Vec<SceneNode>,
+    roots: Vec<NodeId>,
+}
+
+impl SceneGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a node without mesh under `parent`, or as a root.
+    pub fn add(&mut self, name: &str, local: Transform, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(SceneNode {
+            name: name.to_string(),
+            mesh: None,
+            material: None,
+            visible: true,
+            local,
+            world: Matrix4::identity(),
+            parent: None,
+            children: Vec::new(),
+            dirty: true,
+        });
+        self.roots.push(id);
+        if parent.is_some() {
+            self.set_parent(id, parent);
+        }
+        id
+    }
+
+    /// Adds a node drawing `mesh` with `material`.
+    pub fn add_mesh(
+        &mut self,
+        name: &str,
+        local: Transform,
+        parent: Option<NodeId>,
+        mesh: usize,
+        material: Option<usize>,
+    ) -> NodeId {
+        let id = self.add(name, local, parent);
+        self.nodes[id.0].mesh = Some(mesh);
+        self.nodes[id.0].material = material;
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    pub fn node(&self, id: NodeId) -> &SceneNode {
+        &self.nodes[id.0]
+    }
+
+    /// Mutable access to the node's name, mesh, material and visibility; transforms
+    /// and links go through the graph so the dirty flags stay correct.
+    pub fn node_mut(&mut self, id: NodeId) -> &mut SceneNode {
+        &mut self.nodes[id.0]
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name).map(NodeId)
+    }
+
+    pub fn set_local(&mut self, id: NodeId, local: Transform) {
+        let node = &mut self.nodes[id.0];
+        node.local = local;
+        node.dirty = true;
+    }
+
+    /// Moves `id` under `parent`, or makes it a root. Its local transform is kept, so it
+    /// moves with its new parent.
+    ///
+    /// # Panics
+    /// If `parent` is `id` itself or one of its descendants.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(parent) = parent {
+            assert!(
+                !self.is_ancestor_or_self(id, parent),
+                "cannot move node {:?} under its own descendant {:?}",
+                id,
+                parent
+            );
+        }
+        match self.nodes[id.0].parent {
+            Some(old) => self.nodes[old.0].children.retain(|&c| c != id),
+            None => self.roots.retain(|&r| r != id),
+        }
+        match parent {
+            Some(parent) => self.nodes[parent.0].children.push(id),
+            None => self.roots.push(id),
+        }
+        let node = &mut self.nodes[id.0];
+        node.parent = parent;
+        node.dirty = true;
+    }
+
+    fn is_ancestor_or_self(&self, ancestor: NodeId, mut id: NodeId) -> bool {
+        loop {
+            if id == ancestor {
+                return true;
+            }
+            match self.nodes[id.0].parent {
+                Some(parent) => id = parent,
+                None => return false,
+            }
+        }
+    }
+
+    /// Recomputes the world matrices that are out of date and returns how many were
+    /// recomputed.
+    pub fn update(&mut self) -> usize {
+        let mut updated = 0;
+        let mut stack: Vec<(NodeId, Matrix4<f32>, bool)> = self
+            .roots
+            .iter()
+            .rev()
+            .map(|&id| (id, Matrix4::identity(), false))
+            .collect();
+        while let Some((id, parent_world, parent_changed)) = stack.pop() {
+            let node = &mut self.nodes[id.0];
+            let changed = node.dirty || parent_changed;
+            if changed {
+                node.world = parent_world * node.local.matrix();
+                node.dirty = false;
+                updated += 1;
+            }
+            let world = node.world;
+            stack.extend(node.children.iter().rev().map(|&c| (c, world, changed)));
+        }
+        updated
+    }
+
+    /// Updates the world matrices, then lists every visible mesh node in depth-first
+    /// order.
+    pub fn draw_commands(&mut self) -> Vec<DrawCommand> {
+        self.update();
+        let mut commands = Vec::new();
+        let mut stack: Vec<NodeId> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id.0];
+            if !node.visible {
+                continue;
+            }
+            if let Some(mesh) = node.mesh {
+                commands.push(DrawCommand {
+                    node: id,
+                    mesh,
+                    material: node.material,
+                    world: node.world,
+                });
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        commands
+    }
+
+    /// Like [`SceneGraph::draw_commands`], but leaves out nodes whose mesh bounds, moved
+    /// into world space, lie outside `frustum`. `bounds` is indexed by mesh handle;
+    /// meshes without an entry are always drawn.
+    pub fn visible_draw_commands(
+        &mut self,
+        frustum: &Frustum,
+        bounds: &[Aabb],
+    ) -> Vec<DrawCommand> {
+        let mut commands = self.draw_commands();
+        commands.retain(|command| {
+            bounds
+                .get(command.mesh)
+                .is_none_or(|aabb| frustum.intersects_aabb(&aabb.transform(command.world)))
+        });
+        commands
+    }
+
+    /// Builds a graph with the nodes of an imported glTF file; node ids equal glTF node
+    /// indices and mesh handles index [`GltfScene::meshes`]. Nodes that are not part of
+    /// the file's scene are hidden.
+    pub fn from_gltf(gltf: &GltfScene) -> Self {
+        let mut graph = Self::new();
+        for (i, node) in gltf.nodes.iter().enumerate() {
+            let name = node.name.clone().unwrap_or_else(|| format!("node{}", i));
+            graph.add(&name, Transform::from_matrix(node.local), None);
+            graph.nodes[i].mesh = node.mesh;
+        }
+        for (i, node) in gltf.nodes.iter().enumerate() {
+            for &child in &node.children {
+                graph.set_parent(NodeId(child), Some(NodeId(i)));
+            }
+        }
+        // keep the scene's root order; nodes outside the scene stay in the graph hidden
+        let mut roots: Vec<NodeId> = gltf.roots.iter().map(|&r| NodeId(r)).collect();
+        for root in graph.roots.clone() {
+            if !roots.contains(&root) {
+                graph.nodes[root.0].visible = false;
+                roots.push(root);
+            }
+        }
+        graph.roots = roots;
+        graph
+    }
+}
@@ -0,0 +1,294 @@
+//! Mip chains for uploaded textures. Each level is a 2x2 box filter of the level
+//! above, rendered on the GPU by [`MipmapGenerator`] or computed on the CPU by
+//! [`generate_mip_levels`]; both do the same float arithmetic, so the CPU levels
+//! serve as a reference for the GPU ones.
+use super::TextureData;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use wgpu::util::DeviceExt;
+
+/// Number of levels in a full mip chain, down to 1x1.
+pub fn mip_level_count(width: u32, height: u32) -> u32 {
+    32 - width.max(height).max(1).leading_zeros()
+}
+
+/// Size of `level`, halving each dimension and stopping at 1.
+pub fn mip_level_size(width: u32, height: u32, level: u32) -> (u32, u32) {
+    ((width >> level).max(1), (height >> level).max(1))
+}
+
+/// Whether `format` can have its mip levels generated, on the CPU or the GPU.
+pub fn supports_format(format: wgpu::TextureFormat) -> bool {
+    matches!(
+        format,
+        wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Rgba16Float
+    )
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// the `SrgbTables` uniform of `mipmap.wgsl`: the linear value of each 8-bit code,
+// then the 255 midpoints between consecutive codes, padded to 256
+#[repr(C)]
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SrgbTables {
+    decode: [f32; 256],
+    midpoints: [f32; 256],
+}
+
+impl SrgbTables {
+    fn new() -> Self {
+        let mut tables = Self {
+            decode: [0.0; 256],
+            midpoints: [f32::INFINITY; 256],
+        };
+        for code in 0..256 {
+            tables.decode[code] = srgb_to_linear(code as f32 / 255.0);
+        }
+        for code in 0..255 {
+            tables.midpoints[code] = srgb_to_linear((code as f32 + 0.5) / 255.0);
+        }
+        tables
+    }
+
+    fn encode(&self, linear: f32) -> u8 {
+        self.midpoints[..255].partition_point(|&m| linear >= m) as u8
+    }
+}
+
+// one level from the level above, with the arithmetic of `mipmap.wgsl`
+fn downsample(data: &TextureData, width: u32, height: u32, srgb: &SrgbTables) -> Vec<u8> {
+    let texel = |x: u32, y: u32| {
+        let (x, y) = (x.min(data.width - 1), y.min(data.height - 1));
+        (y * data.width + x) as usize
+    };
+    let mut bytes = Vec::with_capacity((width * height) as usize * 8);
+    for y in 0..height {
+        for x in 0..width {
+            let box_texels = [
+                texel(2 * x, 2 * y),
+                texel(2 * x + 1, 2 * y),
+                texel(2 * x, 2 * y + 1),
+                texel(2 * x + 1, 2 * y + 1),
+            ];
+            for c in 0..4 {
+                match data.format {
+                    wgpu::TextureFormat::Rgba16Float => {
+                        let halves: &[u16] = bytemuck::cast_slice(&data.bytes);
+                        let t =
+                            box_texels.map(|i| half::f16::from_bits(halves[i * 4 + c]).to_f32());
+                        let mean = (t[0] + t[1] + t[2] + t[3]) * 0.25;
+                        bytes.extend_from_slice(&half::f16::from_f32(mean).to_bits().to_ne_bytes());
+                    }
+                    wgpu::TextureFormat::Rgba8UnormSrgb if c < 3 => {
+                        let t = box_texels.map(|i| srgb.decode[data.bytes[i * 4 + c] as usize]);
+                        bytes.push(srgb.encode((t[0] + t[1] + t[2] + t[3]) * 0.25));
+                    }
+                    _ => {
+                        let sum: f32 = box_texels
+                            .iter()
+                            .map(|&i| data.bytes[i * 4 + c] as f32)
+                            .sum();
+                        bytes.push((sum * 0.25).round_ties_even() as u8);
+                    }
+                }
+            }
+        }
+    }
+    bytes
+}
+
+/// The full mip chain of `data`, starting with a copy of `data` itself. Texels outside
+/// odd-sized levels are clamped to the edge, as in `mipmap.wgsl`; the color channels of
+/// sRGB textures are averaged in linear space.
+pub fn generate_mip_levels(data: &TextureData) -> Vec<TextureData> {
+    assert!(
+        supports_format(data.format),
+        "no mipmaps for {:?} textures",
+        data.format
+    );
+    let srgb = SrgbTables::new();
+    let mut levels = vec![data.clone()];
+    for level in 1..mip_level_count(data.width, data.height) {
+        let (width, height) = mip_level_size(data.width, data.height, level);
+        let above = levels.last().unwrap();
+        let bytes = downsample(above, width, height, &srgb);
+        levels.push(TextureData {
+            width,
+            height,
+            format: data.format,
+            bytes,
+        });
+    }
+    levels
+}
+
+/// Renders mip chains with one pass per level, each drawing a fullscreen triangle
+/// that reads the level above. Pipelines are created on first use for each format.
+pub struct MipmapGenerator {
+    shader: wgpu::ShaderModule,
+    layout: wgpu::BindGroupLayout,
+    srgb_tables: wgpu::Buffer,
+    pipeline_layout: wgpu::PipelineLayout,
+    pipelines: Mutex<HashMap<wgpu::TextureFormat, Arc<wgpu::RenderPipeline>>>,
+}
+
+impl MipmapGenerator {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("Mipmap Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("mipmap.wgsl").into()),
+        });
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+            label: Some("Mipmap Bind Group Layout"),
+        });
+        let srgb_tables = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("sRGB Tables Buffer"),
+            contents: bytemuck::bytes_of(&SrgbTables::new()),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mipmap Pipeline Layout"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        Self {
+            shader,
+            layout,
+            srgb_tables,
+            pipeline_layout,
+            pipelines: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn pipeline(
+        &self,
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+    ) -> Arc<wgpu::RenderPipeline> {
+        let mut pipelines = self.pipelines.lock().unwrap_or_else(|e| e.into_inner());
+        pipelines
+            .entry(format)
+            .or_insert_with(|| {
+                let entry_point = match format {
+                    wgpu::TextureFormat::Rgba8Unorm => "fs_unorm",
+                    wgpu::TextureFormat::Rgba8UnormSrgb => "fs_srgb",
+                    _ => "fs_float",
+                };
+                Arc::new(
+                    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                        cache: None,
+                        multiview: None,
+                        label: Some("Mipmap Pipeline"),
+                        layout: Some(&self.pipeline_layout),
+                        vertex: wgpu::VertexState {
+                            module: &self.shader,
+                            entry_point: "vs_main",
+                            buffers: &[],
+                            compilation_options: Default::default(),
+                        },
+                        fragment: Some(wgpu::FragmentState {
+                            module: &self.shader,
+                            entry_point,
+                            targets: &[Some(format.into())],
+                            compilation_options: Default::default(),
+                        }),
+                        primitive: wgpu::PrimitiveState::default(),
+                        depth_stencil: None,
+                        multisample: wgpu::MultisampleState::default(),
+                    }),
+                )
+            })
+            .clone()
+    }
+
+    /// Records passes filling every level of `texture` below the first. The texture
+    /// needs `TEXTURE_BINDING | RENDER_ATTACHMENT` usage.
+    pub fn generate(
+        &self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        texture: &wgpu::Texture,
+    ) {
+        let format = texture.format();
+        assert!(
+            supports_format(format),
+            "no mipmaps for {:?} textures",
+            format
+        );
+        let pipeline = self.pipeline(device, format);
+        let level_view = |level: u32| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: Some("Mip Level View"),
+                base_mip_level: level,
+                mip_level_count: Some(1),
+                ..Default::default()
+            })
+        };
+        for level in 1..texture.mip_level_count() {
+            let source = level_view(level - 1);
+            let target = level_view(level);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: &self.layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: self.srgb_tables.as_entire_binding(),
+                    },
+                ],
+                label: Some("Mipmap Bind Group"),
+            });
+            let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("Mipmap Pass"),
+                timestamp_writes: None,
+                occlusion_query_set: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+            pass.set_pipeline(&pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.draw(0..3, 0..1);
+        }
+    }
+}
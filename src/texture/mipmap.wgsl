@@ -0,0 +1,85 @@
+// Fills one mip level from the level above with a 2x2 box filter. Texels are read
+// with textureLoad and averaged explicitly, and 8-bit results are rounded here, so
+// the levels do not depend on the sampler hardware and match `generate_mip_levels`.
+
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    // one triangle covering the whole target
+    let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    return vec4<f32>(uv * 2.0 - 1.0, 0.0, 1.0);
+}
+
+fn box_texels(position: vec4<f32>) -> array<vec4<f32>, 4> {
+    let last = vec2<i32>(textureDimensions(source)) - 1;
+    let p = vec2<i32>(position.xy) * 2;
+    return array<vec4<f32>, 4>(
+        textureLoad(source, min(p, last), 0),
+        textureLoad(source, min(p + vec2<i32>(1, 0), last), 0),
+        textureLoad(source, min(p + vec2<i32>(0, 1), last), 0),
+        textureLoad(source, min(p + vec2<i32>(1, 1), last), 0),
+    );
+}
+
+// 16-bit float levels
+@fragment
+fn fs_float(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let t = box_texels(position);
+    return (t[0] + t[1] + t[2] + t[3]) * 0.25;
+}
+
+// the mean of four 8-bit values, rounded to nearest with ties to even
+fn mean_unorm8(t: array<vec4<f32>, 4>) -> vec4<f32> {
+    let sum = round(t[0] * 255.0) + round(t[1] * 255.0) + round(t[2] * 255.0)
+        + round(t[3] * 255.0);
+    return round(sum * 0.25) / 255.0;
+}
+
+@fragment
+fn fs_unorm(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    return mean_unorm8(box_texels(position));
+}
+
+// sRGB conversions go through tables built on the CPU rather than `pow`, whose
+// precision varies between GPUs: the linear value of each 8-bit code, and the
+// midpoints between consecutive codes
+struct SrgbTables {
+    decode: array<vec4<f32>, 64>,
+    midpoints: array<vec4<f32>, 64>,
+}
+
+@group(0) @binding(1)
+var<uniform> srgb: SrgbTables;
+
+fn srgb_decode(code: u32) -> f32 {
+    return srgb.decode[code / 4u][code % 4u];
+}
+
+// the code whose range holds `linear`, found by bisection over the midpoints
+fn srgb_encode(linear: f32) -> u32 {
+    var code = 0u;
+    for (var step = 128u; step > 0u; step = step >> 1u) {
+        let k = code + step - 1u;
+        if linear >= srgb.midpoints[k / 4u][k % 4u] {
+            code = code + step;
+        }
+    }
+    return code;
+}
+
+// sRGB levels are decoded by textureLoad and encoded again on write. The loaded
+// values are snapped back to their codes and the chosen code is written as its exact
+// linear value, so the hardware conversions cannot shift the result.
+@fragment
+fn fs_srgb(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let t = box_texels(position);
+    var color = mean_unorm8(t);
+    for (var c = 0; c < 3; c = c + 1) {
+        let sum = srgb_decode(srgb_encode(t[0][c])) + srgb_decode(srgb_encode(t[1][c]))
+            + srgb_decode(srgb_encode(t[2][c])) + srgb_decode(srgb_encode(t[3][c]));
+        color[c] = srgb_decode(srgb_encode(sum * 0.25));
+    }
+    return color;
+}
@@ -0,0 +1,342 @@
+//! Sampled textures: decoding PNG, JPEG and Radiance HDR images with the `image`
+//! crate, choosing an sRGB, linear or float format, uploading with mip chains and
+//! creating samplers.
+use anyhow::Context;
+use std::path::Path;
+use wgpu::util::DeviceExt;
+
+pub mod mipmap;
+
+pub use mipmap::MipmapGenerator;
+
+/// How the 8-bit channels of an image are encoded. Colors (base color, emissive, UI)
+/// are sRGB; data such as normals, roughness or masks is linear.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear,
+}
+
+/// Decoded pixels in the layout of `format`, ready for upload.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureData {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub bytes: Vec<u8>,
+}
+
+impl TextureData {
+    /// Tightly packed RGBA8 pixels.
+    pub fn rgba8(width: u32, height: u32, bytes: Vec<u8>, color_space: ColorSpace) -> Self {
+        assert_eq!(
+            bytes.len(),
+            (width * height * 4) as usize,
+            "{}x{} RGBA8 pixels need {} bytes",
+            width,
+            height,
+            width * height * 4
+        );
+        let format = match color_space {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::Linear => wgpu::TextureFormat::Rgba8Unorm,
+        };
+        Self {
+            width,
+            height,
+            format,
+            bytes,
+        }
+    }
+
+    /// Converts to RGBA. Float images, such as HDR files, become `Rgba16Float` and are
+    /// taken as linear whatever `color_space` says; 16-bit images keep their precision
+    /// as `Rgba16Float` when linear and drop to 8 bits when sRGB; the rest is RGBA8.
+    pub fn from_image(image: &image::DynamicImage, color_space: ColorSpace) -> Self {
+        use image::DynamicImage::*;
+        let float = matches!(image, ImageRgb32F(_) | ImageRgba32F(_));
+        let wide = matches!(
+            image,
+            ImageLuma16(_) | ImageLumaA16(_) | ImageRgb16(_) | ImageRgba16(_)
+        );
+        if float || (wide && color_space == ColorSpace::Linear) {
+            let pixels = image.to_rgba32f();
+            let halves: Vec<u16> = pixels
+                .as_raw()
+                .iter()
+                .map(|&v| half::f16::from_f32(v).to_bits())
+                .collect();
+            Self {
+                width: image.width(),
+                height: image.height(),
+                format: wgpu::TextureFormat::Rgba16Float,
+                bytes: bytemuck::cast_slice(&halves).to_vec(),
+            }
+        } else {
+            let pixels = image.to_rgba8();
+            Self::rgba8(
+                image.width(),
+                image.height(),
+                pixels.into_raw(),
+                color_space,
+            )
+        }
+    }
+
+    /// Decodes an encoded image, guessing the format from its contents.
+    pub fn decode(bytes: &[u8], color_space: ColorSpace) -> anyhow::Result<Self> {
+        let image = image::load_from_memory(bytes)?;
+        Ok(Self::from_image(&image, color_space))
+    }
+
+    /// Reads and decodes an image file, guessing the format from its extension.
+    pub fn open<P: AsRef<Path>>(path: P, color_space: ColorSpace) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let image = image::open(path).with_context(|| format!("loading {}", path.display()))?;
+        Ok(Self::from_image(&image, color_space))
+    }
+
+    pub fn bytes_per_pixel(&self) -> u32 {
+        self.format.block_copy_size(None).unwrap()
+    }
+}
+
+/// Filtering and addressing of a sampler.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SamplerOptions {
+    pub address_mode_u: wgpu::AddressMode,
+    pub address_mode_v: wgpu::AddressMode,
+    pub address_mode_w: wgpu::AddressMode,
+    pub mag_filter: wgpu::FilterMode,
+    pub min_filter: wgpu::FilterMode,
+    pub mipmap_filter: wgpu::FilterMode,
+    /// Maximum anisotropy, 1 to 16; 1 turns anisotropic filtering off.
+    pub anisotropy_clamp: u16,
+}
+
+impl Default for SamplerOptions {
+    /// Linear filtering, repeating in every direction.
+    fn default() -> Self {
+        Self::linear(wgpu::AddressMode::Repeat)
+    }
+}
+
+impl SamplerOptions {
+    pub fn linear(address_mode: wgpu::AddressMode) -> Self {
+        Self {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp: 1,
+        }
+    }
+
+    /// Unfiltered texels, for pixel art and lookup tables.
+    pub fn nearest(address_mode: wgpu::AddressMode) -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Self::linear(address_mode)
+        }
+    }
+
+    /// Anisotropic filtering, which keeps textures sharp at grazing angles. It only
+    /// works with linear filtering, so all three filters are switched to linear.
+    pub fn with_anisotropy(self, max_anisotropy: u16) -> Self {
+        Self {
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Linear,
+            anisotropy_clamp: max_anisotropy.clamp(1, 16),
+            ..self
+        }
+    }
+
+    pub fn create(&self, device: &wgpu::Device) -> wgpu::Sampler {
+        device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Texture Sampler"),
+            address_mode_u: self.address_mode_u,
+            address_mode_v: self.address_mode_v,
+            address_mode_w: self.address_mode_w,
+            mag_filter: self.mag_filter,
+            min_filter: self.min_filter,
+            mipmap_filter: self.mipmap_filter,
+            anisotropy_clamp: self.anisotropy_clamp,
+            ..Default::default()
+        })
+    }
+}
+
+/// How [`Texture::from_data`] fills the levels below the uploaded image.
+#[derive(Clone, Copy)]
+pub enum Mipmaps<'a> {
+    /// A single level, for textures that are never minified.
+    None,
+    /// Levels computed with [`mipmap::generate_mip_levels`] and uploaded with the image.
+    Cpu,
+    /// Levels rendered after the upload.
+    Gpu(&'a MipmapGenerator),
+}
+
+/// An uploaded 2D texture with its default view and a sampler.
+pub struct Texture {
+    pub texture: wgpu::Texture,
+    pub view: wgpu::TextureView,
+    pub sampler: wgpu::Sampler,
+}
+
+impl Texture {
+    /// Decodes a PNG, JPEG or HDR file and uploads it.
+    pub fn load<P: AsRef<Path>>(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        path: P,
+        color_space: ColorSpace,
+        sampler: &SamplerOptions,
+        mipmaps: Mipmaps,
+    ) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let data = TextureData::open(path, color_space)?;
+        let label = path.display().to_string();
+        Ok(Self::from_data(
+            device,
+            queue,
+            &data,
+            Some(&label),
+            sampler,
+            mipmaps,
+        ))
+    }
+
+    /// Uploads decoded pixels. The texture can also be copied from, for read-back.
+    pub fn from_data(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        data: &TextureData,
+        label: Option<&str>,
+        sampler: &SamplerOptions,
+        mipmaps: Mipmaps,
+    ) -> Self {
+        let mip_level_count = match mipmaps {
+            Mipmaps::None => 1,
+            Mipmaps::Cpu | Mipmaps::Gpu(_) => mipmap::mip_level_count(data.width, data.height),
+        };
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+        if let Mipmaps::Gpu(_) = mipmaps {
+            usage |= wgpu::TextureUsages::RENDER_ATTACHMENT;
+        }
+        let descriptor = wgpu::TextureDescriptor {
+            label,
+            size: wgpu::Extent3d {
+                width: data.width,
+                height: data.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: data.format,
+            usage,
+            view_formats: &[],
+        };
+
+        let texture = match mipmaps {
+            Mipmaps::None => device.create_texture_with_data(
+                queue,
+                &descriptor,
+                wgpu::util::TextureDataOrder::LayerMajor,
+                &data.bytes,
+            ),
+            Mipmaps::Cpu => {
+                let bytes: Vec<u8> = mipmap::generate_mip_levels(data)
+                    .into_iter()
+                    .flat_map(|level| level.bytes)
+                    .collect();
+                device.create_texture_with_data(
+                    queue,
+                    &descriptor,
+                    wgpu::util::TextureDataOrder::LayerMajor,
+                    &bytes,
+                )
+            }
+            Mipmaps::Gpu(generator) => {
+                let texture = device.create_texture(&descriptor);
+                queue.write_texture(
+                    texture.as_image_copy(),
+                    &data.bytes,
+                    wgpu::ImageDataLayout {
+                        offset: 0,
+                        bytes_per_row: Some(data.width * data.bytes_per_pixel()),
+                        rows_per_image: None,
+                    },
+                    descriptor.size,
+                );
+                let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                    label: Some("Mipmap Encoder"),
+                });
+                generator.generate(device, &mut encoder, &texture);
+                queue.submit(Some(encoder.finish()));
+                texture
+            }
+        };
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Self {
+            texture,
+            view,
+            sampler: sampler.create(device),
+        }
+    }
+
+    /// Layout of [`Texture::bind_group`]: the texture at binding 0 and its filtering
+    /// sampler at binding 1, visible to the fragment stage.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("Texture Bind Group Layout"),
+        })
+    }
+
+    pub fn bind_group(
+        &self,
+        device: &wgpu::Device,
+        layout: &wgpu::BindGroupLayout,
+    ) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&self.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+            label: Some("Texture Bind Group"),
+        })
+    }
+}
@@ -0,0 +1,410 @@
+//! Model, view and projection matrices.
+use cgmath::*;
+use std::f32::consts::PI;
+
+/// Builds a model matrix from a translation, Euler rotation (radians, applied X then Y
+/// then Z) and non-uniform scale. Shorthand for [`Transform::from_euler`].
+pub fn create_transforms(
+    translation: [f32; 3],
+    rotation: [f32; 3],
+    scaling: [f32; 3],
+) -> Matrix4<f32> {
+    Transform::from_euler(translation, rotation, scaling).matrix()
+}
+
+/// Translation, rotation and non-uniform scale, applied scale first. The matrix form is
+/// T·R·S.
+///
+/// Composition and inversion are exact while scales are uniform; with non-uniform scale
+/// on a rotated parent the true result contains shear, which a `Transform` cannot hold,
+/// so the scales are simply multiplied.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vector3::new(0.0, 0.0, 0.0),
+        rotation: Quaternion::new(1.0, 0.0, 0.0, 0.0),
+        scale: Vector3::new(1.0, 1.0, 1.0),
+    };
+
+    pub fn from_translation(translation: Vector3<f32>) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quaternion<f32>) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_scale(scale: Vector3<f32>) -> Self {
+        Self {
+            scale,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Same convention as [`create_transforms`]: Euler angles in radians applied X,
+    /// then Y, then Z.
+    pub fn from_euler(translation: [f32; 3], rotation: [f32; 3], scaling: [f32; 3]) -> Self {
+        Self {
+            translation: translation.into(),
+            rotation: Quaternion::from_angle_z(Rad(rotation[2]))
+                * Quaternion::from_angle_y(Rad(rotation[1]))
+                * Quaternion::from_angle_x(Rad(rotation[0])),
+            scale: scaling.into(),
+        }
+    }
+
+    /// An object at `eye` turned so its -z axis faces `target` and its y axis leans
+    /// towards `up`, the way cameras look. Its inverse matrix is the view matrix from
+    /// [`create_view`].
+    pub fn look_at(eye: Point3<f32>, target: Point3<f32>, up: Vector3<f32>) -> Self {
+        let forward = (target - eye).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+        Self {
+            translation: eye.to_vec(),
+            rotation: Matrix3::from_cols(right, up, -forward).into(),
+            scale: Vector3::new(1.0, 1.0, 1.0),
+        }
+    }
+
+    /// Splits an affine matrix without shear back into its parts. A mirroring matrix
+    /// comes back with a negative x scale.
+    pub fn from_matrix(m: Matrix4<f32>) -> Self {
+        let mut x = m.x.truncate();
+        let y = m.y.truncate();
+        let z = m.z.truncate();
+        let mut scale = Vector3::new(x.magnitude(), y.magnitude(), z.magnitude());
+        if Matrix3::from_cols(x, y, z).determinant() < 0.0 {
+            scale.x = -scale.x;
+            x = -x;
+        }
+        let rotation = Matrix3::from_cols(x / scale.x.abs(), y / scale.y, z / scale.z);
+        Self {
+            translation: m.w.truncate(),
+            rotation: Quaternion::from(rotation).normalize(),
+            scale,
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    pub fn transform_point(&self, point: Point3<f32>) -> Point3<f32> {
+        Point3::from_vec(self.transform_vector(point.to_vec()) + self.translation)
+    }
+
+    /// Scales and rotates a direction without translating it.
+    pub fn transform_vector(&self, vector: Vector3<f32>) -> Vector3<f32> {
+        self.rotation
+            .rotate_vector(self.scale.mul_element_wise(vector))
+    }
+
+    /// `self` applied after `child`, as in `parent.then(child)` for a scene hierarchy.
+    pub fn then(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_vector(child.translation) + self.translation,
+            rotation: (self.rotation * child.rotation).normalize(),
+            scale: self.scale.mul_element_wise(child.scale),
+        }
+    }
+
+    pub fn inverse(&self) -> Transform {
+        let rotation = self.rotation.conjugate() / self.rotation.magnitude2();
+        let scale = Vector3::new(1.0 / self.scale.x, 1.0 / self.scale.y, 1.0 / self.scale.z);
+        Transform {
+            translation: -scale.mul_element_wise(rotation.rotate_vector(self.translation)),
+            rotation,
+            scale,
+        }
+    }
+
+    /// Linear interpolation of every part; the rotation is normalized along the shorter
+    /// arc, which is cheap but not constant-speed.
+    pub fn lerp(&self, other: &Transform, amount: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, amount),
+            rotation: self.rotation.nlerp(other.rotation, amount),
+            scale: self.scale.lerp(other.scale, amount),
+        }
+    }
+
+    /// Like [`Transform::lerp`] but with a constant-speed spherical rotation.
+    pub fn slerp(&self, other: &Transform, amount: f32) -> Transform {
+        Transform {
+            rotation: self.rotation.slerp(other.rotation, amount).normalize(),
+            ..self.lerp(other, amount)
+        }
+    }
+}
+
+impl std::ops::Mul for Transform {
+    type Output = Transform;
+
+    fn mul(self, child: Transform) -> Transform {
+        self.then(&child)
+    }
+}
+
+impl From<Transform> for Matrix4<f32> {
+    fn from(transform: Transform) -> Self {
+        transform.matrix()
+    }
+}
+
+impl From<Matrix4<f32>> for Transform {
+    fn from(m: Matrix4<f32>) -> Self {
+        Transform::from_matrix(m)
+    }
+}
+
+/// Maps OpenGL clip space depth (-1..1) produced by cgmath onto wgpu's 0..1 range.
+#[rustfmt::skip]
+pub const OPENGL_TO_WGPU_MATRIX: Matrix4<f32> = Matrix4::new(
+    1.0, 0.0, 0.0, 0.0,
+    0.0, 1.0, 0.0, 0.0,
+    0.0, 0.0, 0.5, 0.0,
+    0.0, 0.0, 0.5, 1.0,
+);
+
+/// Returns the view, projection and combined view-projection matrices.
+pub fn create_view_projection(
+    camera_position: Point3<f32>,
+    look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+    aspect: f32,
+    is_perspective: bool,
+) -> (Matrix4<f32>, Matrix4<f32>, Matrix4<f32>) {
+    // construct view matrix
+    let view_mat = Matrix4::look_at_rh(camera_position, look_direction, up_direction);
+    // construct projection matrix
+    let project_mat = create_projection(aspect, is_perspective);
+    // construct view-projection matrix
+    let view_project_mat = project_mat * view_mat;
+
+    // return various matrices
+    (view_mat, project_mat, view_project_mat)
+}
+
+/// Right-handed look-at view matrix.
+pub fn create_view(
+    camera_position: Point3<f32>,
+    look_direction: Point3<f32>,
+    up_direction: Vector3<f32>,
+) -> Matrix4<f32> {
+    Matrix4::look_at_rh(camera_position, look_direction, up_direction)
+}
+
+/// Forward direction for a yaw about +y (0 looks down -z) and a pitch above the horizon.
+pub fn fly_direction(yaw: Rad<f32>, pitch: Rad<f32>) -> Vector3<f32> {
+    let (sin_yaw, cos_yaw) = yaw.sin_cos();
+    let (sin_pitch, cos_pitch) = pitch.sin_cos();
+    Vector3::new(-sin_yaw * cos_pitch, sin_pitch, -cos_yaw * cos_pitch)
+}
+
+/// First-person view matrix from a position and yaw/pitch angles, always with the
+/// world y axis as up so the view never rolls.
+pub fn create_fly_view(
+    camera_position: Point3<f32>,
+    yaw: Rad<f32>,
+    pitch: Rad<f32>,
+) -> Matrix4<f32> {
+    Matrix4::look_to_rh(
+        camera_position,
+        fly_direction(yaw, pitch),
+        Vector3::unit_y(),
+    )
+}
+
+/// Projection matrix in wgpu clip space: a 72° perspective frustum, or a fixed
+/// orthographic box when `is_perspective` is false. Use [`Projection`] to choose the
+/// frustum.
+pub fn create_projection(aspect: f32, is_perspective: bool) -> Matrix4<f32> {
+    if is_perspective {
+        Projection::perspective(Rad(2.0 * PI / 5.0), aspect, 0.1, 100.0).matrix()
+    } else {
+        OPENGL_TO_WGPU_MATRIX * ortho(-4.0, 4.0, -3.0, 3.0, -1.0, 6.0)
+    }
+}
+
+/// The shape of a [`Projection`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ProjectionKind {
+    /// Symmetric perspective with a vertical field of view; the horizontal one follows
+    /// the aspect ratio.
+    Perspective { fovy: Rad<f32> },
+    /// Off-axis perspective given by its extents on the near plane, e.g. for stereo or
+    /// tiled rendering. The extents are absolute and do not follow the aspect ratio.
+    Frustum {
+        left: f32,
+        right: f32,
+        bottom: f32,
+        top: f32,
+    },
+    /// Orthographic box `height` units tall, centred on the view axis, whose width
+    /// follows the aspect ratio.
+    Orthographic { height: f32 },
+}
+
+/// A projection in wgpu clip space (depth 0..1) that keeps its parameters so the matrix
+/// can be rebuilt when the window is resized.
+///
+/// Perspective projections accept `far = f32::INFINITY`. With `reverse_z` depth is 1 at
+/// the near plane and 0 at the far plane, which spreads float precision evenly over
+/// deep scenes; clear depth to [`Projection::depth_clear_value`] and test with
+/// [`Projection::depth_compare`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Projection {
+    pub kind: ProjectionKind,
+    pub aspect: f32,
+    pub near: f32,
+    pub far: f32,
+    pub reverse_z: bool,
+}
+
+impl Projection {
+    pub fn perspective<A: Into<Rad<f32>>>(fovy: A, aspect: f32, near: f32, far: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Perspective { fovy: fovy.into() },
+            aspect,
+            near,
+            far,
+            reverse_z: false,
+        }
+    }
+
+    pub fn frustum(left: f32, right: f32, bottom: f32, top: f32, near: f32, far: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Frustum {
+                left,
+                right,
+                bottom,
+                top,
+            },
+            aspect: (right - left) / (top - bottom),
+            near,
+            far,
+            reverse_z: false,
+        }
+    }
+
+    pub fn orthographic(height: f32, aspect: f32, near: f32, far: f32) -> Self {
+        Self {
+            kind: ProjectionKind::Orthographic { height },
+            aspect,
+            near,
+            far,
+            reverse_z: false,
+        }
+    }
+
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        self
+    }
+
+    /// Updates the aspect ratio for a new surface size; zero sizes are ignored.
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    pub fn depth_compare(&self) -> wgpu::CompareFunction {
+        if self.reverse_z {
+            wgpu::CompareFunction::GreaterEqual
+        } else {
+            wgpu::CompareFunction::LessEqual
+        }
+    }
+
+    pub fn depth_clear_value(&self) -> f32 {
+        if self.reverse_z {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let (n, f) = (self.near, self.far);
+        match self.kind {
+            ProjectionKind::Perspective { fovy } => {
+                let y = 1.0 / (fovy / 2.0).tan();
+                let x = y / self.aspect;
+                self.perspective_matrix(x, y, 0.0, 0.0)
+            }
+            ProjectionKind::Frustum {
+                left,
+                right,
+                bottom,
+                top,
+            } => {
+                let x = 2.0 * n / (right - left);
+                let y = 2.0 * n / (top - bottom);
+                let cx = (right + left) / (right - left);
+                let cy = (top + bottom) / (top - bottom);
+                self.perspective_matrix(x, y, cx, cy)
+            }
+            ProjectionKind::Orthographic { height } => {
+                let x = 2.0 / (height * self.aspect);
+                let y = 2.0 / height;
+                // depth = (-z - near) / (far - near), or 1 minus that when reversed
+                let (a, b) = if self.reverse_z {
+                    (1.0 / (f - n), f / (f - n))
+                } else {
+                    (-1.0 / (f - n), -n / (f - n))
+                };
+                #[rustfmt::skip]
+                let m = Matrix4::new(
+                    x,   0.0, 0.0, 0.0,
+                    0.0, y,   0.0, 0.0,
+                    0.0, 0.0, a,   0.0,
+                    0.0, 0.0, b,   1.0,
+                );
+                m
+            }
+        }
+    }
+
+    // perspective matrix with clip w = -z; (cx, cy) shift the frustum off-axis
+    fn perspective_matrix(&self, x: f32, y: f32, cx: f32, cy: f32) -> Matrix4<f32> {
+        let (n, f) = (self.near, self.far);
+        // clip z = a * z + b, chosen so depth is 0 (1 if reversed) at z = -near and
+        // 1 (0 if reversed) at z = -far
+        let (a, b) = match (self.reverse_z, f.is_finite()) {
+            (false, true) => (f / (n - f), n * f / (n - f)),
+            (false, false) => (-1.0, -n),
+            (true, true) => (n / (f - n), n * f / (f - n)),
+            (true, false) => (0.0, n),
+        };
+        #[rustfmt::skip]
+        let m = Matrix4::new(
+            x,   0.0, 0.0,  0.0,
+            0.0, y,   0.0,  0.0,
+            cx,  cy,  a,   -1.0,
+            0.0, 0.0, b,    0.0,
+        );
+        m
+    }
+}
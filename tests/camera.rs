@@ -0,0 +1,195 @@
+use cgmath::*;
+use std::time::Duration;
+use wgpu_step_by_step::camera::{FlyCamera, OrbitCamera, OrbitMode};
+use wgpu_step_by_step::transforms;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{
+    DeviceEvent, DeviceId, ElementState, MouseButton, MouseScrollDelta, TouchPhase, WindowEvent,
+};
+use winit::keyboard::KeyCode;
+
+const SIZE: PhysicalSize<u32> = PhysicalSize::new(800, 600);
+
+fn orbit_camera() -> OrbitCamera {
+    OrbitCamera::new((3.0, 1.5, 3.0).into(), Point3::origin(), SIZE)
+}
+
+fn cursor(x: f64, y: f64) -> WindowEvent {
+    WindowEvent::CursorMoved {
+        device_id: DeviceId::dummy(),
+        position: PhysicalPosition::new(x, y),
+    }
+}
+
+fn button(button: MouseButton, state: ElementState) -> WindowEvent {
+    WindowEvent::MouseInput {
+        device_id: DeviceId::dummy(),
+        state,
+        button,
+    }
+}
+
+fn drag(camera: &mut OrbitCamera, which: MouseButton, from: (f64, f64), to: (f64, f64)) {
+    camera.input(&cursor(from.0, from.1));
+    assert!(camera.input(&button(which, ElementState::Pressed)));
+    assert!(camera.input(&cursor(to.0, to.1)));
+    assert!(camera.input(&button(which, ElementState::Released)));
+}
+
+#[test]
+fn orbit_camera_starts_at_the_given_eye() {
+    let camera = orbit_camera();
+    assert_relative_eq!(camera.eye(), Point3::new(3.0, 1.5, 3.0), epsilon = 1e-5);
+    let expected =
+        transforms::create_view((3.0, 1.5, 3.0).into(), Point3::origin(), Vector3::unit_y());
+    assert_relative_eq!(camera.view_matrix(), expected, epsilon = 1e-5);
+}
+
+#[test]
+fn orbit_keeps_distance_and_clamps_pitch() {
+    let mut camera = orbit_camera();
+    let distance = camera.distance;
+    drag(
+        &mut camera,
+        MouseButton::Left,
+        (400.0, 300.0),
+        (500.0, 300.0),
+    );
+    assert_relative_eq!(
+        (camera.eye() - camera.target).magnitude(),
+        distance,
+        epsilon = 1e-5
+    );
+    assert_relative_eq!(camera.eye().y, 1.5, epsilon = 1e-5);
+
+    // dragging far down would pass over the pole without the clamp
+    drag(
+        &mut camera,
+        MouseButton::Left,
+        (400.0, 0.0),
+        (400.0, 100_000.0),
+    );
+    assert_eq!(camera.pitch, camera.settings.max_pitch);
+    assert!(camera.up().y > 0.0);
+}
+
+#[test]
+fn pan_moves_target_and_eye_together() {
+    let mut camera = orbit_camera();
+    let offset = camera.eye() - camera.target;
+    drag(
+        &mut camera,
+        MouseButton::Right,
+        (400.0, 300.0),
+        (450.0, 280.0),
+    );
+    assert!(camera.target.distance(Point3::origin()) > 0.0);
+    assert_relative_eq!(camera.eye() - camera.target, offset, epsilon = 1e-5);
+}
+
+#[test]
+fn wheel_dollies_within_limits() {
+    let mut camera = orbit_camera();
+    let distance = camera.distance;
+    let wheel = |lines: f32| WindowEvent::MouseWheel {
+        device_id: DeviceId::dummy(),
+        delta: MouseScrollDelta::LineDelta(0.0, lines),
+        phase: TouchPhase::Moved,
+    };
+    assert!(camera.input(&wheel(1.0)));
+    assert!(camera.distance < distance);
+    camera.input(&wheel(-1.0));
+    assert_relative_eq!(camera.distance, distance, epsilon = 1e-5);
+    camera.input(&wheel(1000.0));
+    assert_eq!(camera.distance, camera.settings.min_distance);
+}
+
+#[test]
+fn arcball_follows_the_cursor() {
+    let mut camera = orbit_camera();
+    camera.set_mode(OrbitMode::Arcball);
+    assert_relative_eq!(camera.eye(), Point3::new(3.0, 1.5, 3.0), epsilon = 1e-5);
+
+    // a horizontal drag through the centre turns the camera about its own up axis
+    let up = camera.up();
+    drag(
+        &mut camera,
+        MouseButton::Left,
+        (300.0, 300.0),
+        (500.0, 300.0),
+    );
+    assert_relative_eq!(camera.up(), up, epsilon = 1e-5);
+    assert!(camera.eye().distance(Point3::new(3.0, 1.5, 3.0)) > 0.1);
+
+    // a vertical drag is free to roll over the top, unlike the turntable
+    drag(
+        &mut camera,
+        MouseButton::Left,
+        (400.0, 10.0),
+        (400.0, 590.0),
+    );
+    assert!(camera.up().y < 0.0);
+
+    camera.set_mode(OrbitMode::Turntable);
+    assert!(camera.up().y > 0.0);
+}
+
+#[test]
+fn fly_camera_matches_look_at() {
+    let camera = FlyCamera::looking_at((3.0, 1.5, 3.0).into(), Point3::origin());
+    let expected =
+        transforms::create_view((3.0, 1.5, 3.0).into(), Point3::origin(), Vector3::unit_y());
+    assert_relative_eq!(camera.view_matrix(), expected, epsilon = 1e-5);
+}
+
+#[test]
+fn fly_camera_moves_relative_to_view_scaled_by_dt() {
+    let mut camera = FlyCamera::new(Point3::origin(), Rad(0.0), Rad(0.0));
+    camera.settings.speed = 2.0;
+    assert!(camera.key(KeyCode::KeyW, ElementState::Pressed));
+    camera.update(Duration::from_millis(500));
+    assert_relative_eq!(camera.position, Point3::new(0.0, 0.0, -1.0), epsilon = 1e-6);
+
+    // strafing and rising stay independent of pitch, and sprint multiplies the speed
+    camera.key(KeyCode::KeyW, ElementState::Released);
+    camera.pitch = Rad(0.5);
+    camera.key(KeyCode::KeyD, ElementState::Pressed);
+    camera.key(KeyCode::ShiftLeft, ElementState::Pressed);
+    camera.update(Duration::from_millis(250));
+    assert_relative_eq!(camera.position, Point3::new(2.0, 0.0, -1.0), epsilon = 1e-6);
+
+    camera.key(KeyCode::KeyD, ElementState::Released);
+    camera.key(KeyCode::ShiftLeft, ElementState::Released);
+    camera.key(KeyCode::KeyE, ElementState::Pressed);
+    camera.update(Duration::from_secs(1));
+    assert_relative_eq!(camera.position, Point3::new(2.0, 2.0, -1.0), epsilon = 1e-6);
+    assert!(!camera.key(KeyCode::KeyZ, ElementState::Pressed));
+}
+
+#[test]
+fn fly_camera_looks_only_while_grabbed() {
+    let mut camera = FlyCamera::new(Point3::origin(), Rad(0.0), Rad(0.0));
+    let motion = DeviceEvent::MouseMotion {
+        delta: (100.0, 0.0),
+    };
+    camera.device_input(&motion);
+    assert_eq!(camera.yaw, Rad(0.0));
+
+    assert!(camera.input(&button(MouseButton::Left, ElementState::Pressed)));
+    assert!(camera.cursor_grabbed());
+    camera.device_input(&motion);
+    // moving the mouse right turns right, towards +x
+    assert!(camera.forward().x > 0.0);
+
+    // looking down is clamped and the view never rolls
+    camera.device_input(&DeviceEvent::MouseMotion {
+        delta: (0.0, 1.0e6),
+    });
+    assert_eq!(camera.pitch, -camera.settings.max_pitch);
+    let view = camera.view_matrix();
+    // the camera right axis, the first row of the view rotation, stays horizontal
+    assert_relative_eq!(view.y.x, 0.0, epsilon = 1e-6);
+
+    camera.input(&WindowEvent::Focused(false));
+    assert!(!camera.cursor_grabbed());
+}
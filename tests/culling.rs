@@ -0,0 +1,224 @@
+use cgmath::*;
+use wgpu_step_by_step::culling::{Frustum, Plane};
+use wgpu_step_by_step::geometry::{vertex_data, Aabb, BoundingSphere, Mesh};
+use wgpu_step_by_step::transforms::{create_view, create_view_projection, Projection};
+
+// camera five units up the z axis looking at the origin
+fn view_projection(is_perspective: bool) -> Matrix4<f32> {
+    let (_, _, view_projection) = create_view_projection(
+        Point3::new(0.0, 0.0, 5.0),
+        Point3::origin(),
+        Vector3::unit_y(),
+        1.5,
+        is_perspective,
+    );
+    view_projection
+}
+
+// the clip-space test the planes stand for
+fn in_clip_space(matrix: Matrix4<f32>, point: Point3<f32>) -> bool {
+    let clip = matrix * point.to_homogeneous();
+    clip.x.abs() <= clip.w && clip.y.abs() <= clip.w && 0.0 <= clip.z && clip.z <= clip.w
+}
+
+#[test]
+fn planes_measure_signed_distances() {
+    let plane = Plane::new(Vector3::unit_y(), Point3::new(3.0, 2.0, -1.0));
+    assert_eq!(plane.distance, -2.0);
+    assert_eq!(plane.signed_distance(Point3::new(7.0, 5.0, 4.0)), 3.0);
+    assert_eq!(plane.signed_distance(Point3::new(0.0, 0.0, 0.0)), -2.0);
+
+    // 2z + 4 >= 0 is the half-space z >= -2
+    let scaled = Plane::from_coefficients(Vector4::new(0.0, 0.0, 2.0, 4.0));
+    assert_eq!(scaled.normal, Vector3::unit_z());
+    assert_eq!(scaled.distance, 2.0);
+}
+
+#[test]
+fn perspective_planes_pass_through_the_frustum_edges() {
+    let frustum = Frustum::from_matrix(view_projection(true));
+    for plane in &frustum.planes {
+        assert_relative_eq!(plane.normal.magnitude(), 1.0, epsilon = 1e-5);
+    }
+    let [left, right, bottom, top, near, far] = frustum.planes;
+
+    // 72 degrees vertically with a 1.5 aspect ratio, 0.1 to 100 units from the eye
+    let tan_y = Deg(36.0).tan();
+    let tan_x = tan_y * 1.5;
+    let at = |x: f32, y: f32, depth: f32| Point3::new(x * depth, y * depth, 5.0 - depth);
+    for depth in [1.0, 10.0, 50.0] {
+        assert_relative_eq!(
+            left.signed_distance(at(-tan_x, 0.0, depth)),
+            0.0,
+            epsilon = 1e-3
+        );
+        assert_relative_eq!(
+            right.signed_distance(at(tan_x, 0.0, depth)),
+            0.0,
+            epsilon = 1e-3
+        );
+        assert_relative_eq!(
+            bottom.signed_distance(at(0.0, -tan_y, depth)),
+            0.0,
+            epsilon = 1e-3
+        );
+        assert_relative_eq!(
+            top.signed_distance(at(0.0, tan_y, depth)),
+            0.0,
+            epsilon = 1e-3
+        );
+    }
+    assert_relative_eq!(near.normal, -Vector3::unit_z(), epsilon = 1e-5);
+    assert_relative_eq!(near.signed_distance(at(0.0, 0.0, 0.1)), 0.0, epsilon = 1e-4);
+    assert_relative_eq!(far.normal, Vector3::unit_z(), epsilon = 1e-5);
+    assert_relative_eq!(
+        far.signed_distance(at(0.0, 0.0, 100.0)),
+        0.0,
+        epsilon = 1e-2
+    );
+
+    assert!(frustum.contains_point(Point3::origin()));
+    assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 6.0)));
+}
+
+#[test]
+fn orthographic_planes_bound_the_box() {
+    // x in -4..4, y in -3..3 and depth -1..6 from the eye
+    let frustum = Frustum::from_matrix(view_projection(false));
+    let expected = [
+        (Vector3::unit_x(), Point3::new(-4.0, 0.0, 0.0)),
+        (-Vector3::unit_x(), Point3::new(4.0, 0.0, 0.0)),
+        (Vector3::unit_y(), Point3::new(0.0, -3.0, 0.0)),
+        (-Vector3::unit_y(), Point3::new(0.0, 3.0, 0.0)),
+        (-Vector3::unit_z(), Point3::new(0.0, 0.0, 6.0)),
+        (Vector3::unit_z(), Point3::new(0.0, 0.0, -1.0)),
+    ];
+    for (plane, (normal, point)) in frustum.planes.iter().zip(expected) {
+        assert_relative_eq!(plane.normal, normal, epsilon = 1e-5);
+        assert_relative_eq!(plane.signed_distance(point), 0.0, epsilon = 1e-5);
+    }
+}
+
+#[test]
+fn planes_agree_with_the_clip_space_test() {
+    let view = create_view(
+        Point3::new(2.0, 3.0, 4.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vector3::unit_y(),
+    );
+    let projections = [
+        Projection::perspective(Deg(60.0), 1.3, 0.5, 12.0),
+        Projection::perspective(Deg(60.0), 1.3, 0.5, 12.0).with_reverse_z(true),
+        Projection::frustum(-0.2, 0.4, -0.3, 0.1, 0.5, 12.0),
+        Projection::orthographic(4.0, 1.3, 0.5, 12.0),
+    ];
+    for projection in projections {
+        let matrix = projection.matrix() * view;
+        let frustum = Frustum::from_matrix(matrix);
+        for x in -8..=8 {
+            for y in -8..=8 {
+                for z in -8..=8 {
+                    let point = Point3::new(x as f32, y as f32, z as f32) * 0.77;
+                    assert_eq!(
+                        frustum.contains_point(point),
+                        in_clip_space(matrix, point),
+                        "{:?} {:?}",
+                        projection,
+                        point
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn infinite_far_planes_hold_everything_beyond_the_near_plane() {
+    let view = create_view(
+        Point3::new(0.0, 0.0, 5.0),
+        Point3::origin(),
+        Vector3::unit_y(),
+    );
+    let far_away = Point3::new(0.0, 0.0, -1e6);
+    for projection in [
+        Projection::perspective(Deg(60.0), 1.0, 0.1, f32::INFINITY),
+        Projection::perspective(Deg(60.0), 1.0, 0.1, f32::INFINITY).with_reverse_z(true),
+    ] {
+        let frustum = Frustum::from_matrix(projection.matrix() * view);
+        // the depth-1 plane without reverse-z, the depth-0 plane with it
+        let infinite = if projection.reverse_z { 4 } else { 5 };
+        assert_eq!(frustum.planes[infinite].normal, Vector3::zero());
+        assert!(frustum.planes[infinite].distance > 0.0);
+        assert!(frustum.contains_point(far_away));
+        assert!(!frustum.contains_point(Point3::new(0.0, 0.0, 4.95)));
+    }
+}
+
+#[test]
+fn volumes_straddling_a_plane_intersect() {
+    let frustum = Frustum::from_matrix(view_projection(false));
+    let sphere = |x: f32| BoundingSphere::new(Point3::new(x, 0.0, 0.0), 1.0);
+    assert!(frustum.intersects_sphere(&sphere(0.0)));
+    assert!(frustum.intersects_sphere(&sphere(4.9)));
+    assert!(!frustum.intersects_sphere(&sphere(5.1)));
+
+    let aabb = |x: f32| Aabb::new(Point3::new(x, -1.0, -1.0), Point3::new(x + 2.0, 1.0, 1.0));
+    assert!(frustum.intersects_aabb(&aabb(3.9)));
+    assert!(!frustum.intersects_aabb(&aabb(4.1)));
+    assert!(!frustum.intersects_aabb(&aabb(-6.1)));
+    // a box larger than the frustum holds all of it
+    let huge = Aabb::new(
+        Point3::new(-50.0, -50.0, -50.0),
+        Point3::new(50.0, 50.0, 50.0),
+    );
+    assert!(frustum.intersects_aabb(&huge));
+}
+
+#[test]
+fn mesh_bounds_enclose_every_position() {
+    let (pos, _, _) = vertex_data::cube_data_index();
+    let cube = Mesh {
+        positions: pos.iter().map(|p| p.map(f32::from)).collect(),
+        ..Default::default()
+    };
+    let aabb = cube.aabb().unwrap();
+    assert_eq!(aabb.min, Point3::new(-1.0, -1.0, -1.0));
+    assert_eq!(aabb.max, Point3::new(1.0, 1.0, 1.0));
+    let sphere = cube.bounding_sphere().unwrap();
+    assert_eq!(sphere.center, Point3::origin());
+    assert_relative_eq!(sphere.radius, 3f32.sqrt());
+
+    let offset = Mesh {
+        positions: vec![[1.0, 2.0, 3.0], [3.0, 2.0, 3.0], [2.0, 5.0, 3.0]],
+        ..Default::default()
+    };
+    let sphere = offset.bounding_sphere().unwrap();
+    assert_eq!(sphere.center, Point3::new(2.0, 3.5, 3.0));
+    assert!(offset
+        .positions
+        .iter()
+        .all(|&p| sphere.contains(Point3::from(p))));
+
+    assert_eq!(Mesh::default().aabb(), None);
+    assert_eq!(Mesh::default().bounding_sphere(), None);
+}
+
+#[test]
+fn transformed_bounds_hold_the_transformed_corners() {
+    let aabb = Aabb::new(Point3::new(-1.0, 0.0, -2.0), Point3::new(1.0, 1.0, 2.0));
+    let matrix = Matrix4::from_translation(Vector3::new(3.0, 0.0, 0.0))
+        * Matrix4::from_angle_y(Deg(30.0))
+        * Matrix4::from_nonuniform_scale(2.0, 1.0, 0.5);
+    let moved = aabb.transform(matrix);
+    let corners = aabb.corners().map(|c| matrix.transform_point(c));
+    // the tight box around the moved corners
+    let tight = Aabb::from_points(corners).unwrap();
+    assert_relative_eq!(tight.min, moved.min, epsilon = 1e-5);
+    assert_relative_eq!(tight.max, moved.max, epsilon = 1e-5);
+
+    let sphere = BoundingSphere::from(aabb).transform(matrix);
+    for corner in corners {
+        assert!(sphere.contains(corner), "{:?}", corner);
+    }
+    assert_relative_eq!(sphere.radius, 2.0 * 5.25f32.sqrt());
+}
@@ -0,0 +1,114 @@
+use wgpu_step_by_step::depth::DepthTarget;
+use wgpu_step_by_step::InitWgpu;
+
+fn init() -> InitWgpu<'static> {
+    pollster::block_on(InitWgpu::init_headless(
+        64,
+        48,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ))
+}
+
+// an empty pass that clears the color target and the depth target
+fn render_frame(init: &InitWgpu, depth: &DepthTarget) {
+    let frame = init.get_current_frame().unwrap();
+    let view = frame
+        .texture()
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+            view: &view,
+            resolve_target: None,
+            ops: wgpu::Operations::default(),
+        })],
+        depth_stencil_attachment: Some(depth.attachment()),
+    });
+    init.queue.submit(Some(encoder.finish()));
+    init.device.poll(wgpu::Maintain::Wait);
+}
+
+// textures alive on the device, as counted by wgpu itself
+fn live_textures(init: &InitWgpu) -> usize {
+    let report = init.instance.generate_report().unwrap();
+    report
+        .hub_report(init.adapter.get_info().backend)
+        .textures
+        .num_allocated
+}
+
+#[test]
+fn depth_texture_is_only_allocated_on_resize() {
+    let mut init = init();
+    let mut depth = DepthTarget::new(
+        &init.device,
+        init.config.width,
+        init.config.height,
+        wgpu::TextureFormat::Depth24Plus,
+    );
+    let first = depth.texture.global_id();
+    let textures = live_textures(&init);
+    for _ in 0..10 {
+        render_frame(&init, &depth);
+    }
+    assert_eq!(live_textures(&init), textures);
+    assert_eq!(depth.texture.global_id(), first);
+
+    // the same size, or a minimized window, keeps the texture
+    assert!(!depth.resize(&init.device, 64, 48));
+    assert!(!depth.resize(&init.device, 0, 0));
+    assert_eq!(depth.texture.global_id(), first);
+
+    init.resize(winit::dpi::PhysicalSize::new(80, 60));
+    assert!(depth.resize(&init.device, 80, 60));
+    let second = depth.texture.global_id();
+    assert_ne!(second, first);
+    for _ in 0..10 {
+        render_frame(&init, &depth);
+    }
+    // the old depth and offscreen textures were released, not kept alongside
+    assert_eq!(live_textures(&init), textures);
+    assert_eq!(depth.texture.global_id(), second);
+    assert_eq!((depth.texture.width(), depth.texture.height()), (80, 60));
+}
+
+#[test]
+fn float_and_stencil_formats_render() {
+    let init = init();
+    for format in [
+        wgpu::TextureFormat::Depth32Float,
+        wgpu::TextureFormat::Depth24PlusStencil8,
+    ] {
+        let depth = DepthTarget::new(&init.device, 64, 48, format);
+        assert_eq!(depth.format(), format);
+        assert_eq!(
+            depth.attachment().stencil_ops.is_some(),
+            format == wgpu::TextureFormat::Depth24PlusStencil8
+        );
+        assert_eq!(depth.depth_stencil_state().format, format);
+        render_frame(&init, &depth);
+    }
+}
+
+#[test]
+fn reverse_z_clears_to_zero_and_tests_greater() {
+    let init = init();
+    let depth = DepthTarget::new(&init.device, 64, 48, wgpu::TextureFormat::Depth32Float);
+    assert_eq!(depth.clear_value(), 1.0);
+    assert_eq!(depth.depth_compare(), wgpu::CompareFunction::LessEqual);
+
+    let depth = depth.with_reverse_z(true);
+    assert_eq!(depth.clear_value(), 0.0);
+    assert_eq!(
+        depth.depth_stencil_state().depth_compare,
+        wgpu::CompareFunction::GreaterEqual
+    );
+    let ops = depth.attachment().depth_ops.unwrap();
+    assert_eq!(ops.load, wgpu::LoadOp::Clear(0.0));
+    render_frame(&init, &depth);
+}
@@ -0,0 +1,192 @@
+use cgmath::{assert_relative_eq, Matrix4, Vector3};
+use std::f32::consts::FRAC_PI_2;
+use std::path::PathBuf;
+use wgpu_step_by_step::geometry::gltf::{import_gltf, AlphaMode};
+use wgpu_step_by_step::geometry::{load_gltf, GltfScene, Indices};
+use wgpu_step_by_step::transforms::create_transforms;
+
+// a triangle whose third position is replaced through a sparse accessor, with
+// normalized u16 uvs, normalized u8 colors and u8 indices
+fn binary() -> Vec<u8> {
+    let mut bin: Vec<u8> = Vec::new();
+    for v in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+        bin.extend(v.to_le_bytes());
+    }
+    for v in [0u16, 0, 65535, 0, 0, 65535] {
+        bin.extend(v.to_le_bytes());
+    }
+    bin.extend([255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 0]);
+    bin.extend([0, 1, 2, 0]);
+    bin.extend([2, 0, 0, 0]);
+    for v in [0.0f32, 2.0, 0.0] {
+        bin.extend(v.to_le_bytes());
+    }
+    bin
+}
+
+fn document(buffer_uri: Option<&str>) -> String {
+    let uri = buffer_uri.map_or(String::new(), |uri| format!(r#""uri": "{}", "#, uri));
+    format!(
+        r#"{{
+  "asset": {{ "version": "2.0" }},
+  "scene": 0,
+  "scenes": [{{ "nodes": [0] }}],
+  "nodes": [
+    {{ "name": "parent", "translation": [1, 2, 3], "children": [1] }},
+    {{ "name": "child", "rotation": [0, 0, 0.70710677, 0.70710677], "scale": [2, 2, 2], "mesh": 0 }}
+  ],
+  "meshes": [{{
+    "name": "triangle",
+    "primitives": [{{
+      "attributes": {{ "POSITION": 0, "TEXCOORD_0": 1, "COLOR_0": 2 }},
+      "indices": 3,
+      "material": 0
+    }}]
+  }}],
+  "materials": [{{
+    "name": "gray",
+    "pbrMetallicRoughness": {{
+      "baseColorFactor": [0.5, 0.5, 0.5, 1],
+      "metallicFactor": 0.25,
+      "roughnessFactor": 0.75
+    }},
+    "emissiveFactor": [0.1, 0.2, 0.3],
+    "alphaMode": "MASK",
+    "alphaCutoff": 0.3,
+    "doubleSided": true
+  }}],
+  "accessors": [
+    {{
+      "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+      "min": [0, 0, 0], "max": [1, 2, 0],
+      "sparse": {{
+        "count": 1,
+        "indices": {{ "bufferView": 4, "componentType": 5121 }},
+        "values": {{ "bufferView": 5 }}
+      }}
+    }},
+    {{ "bufferView": 1, "componentType": 5123, "normalized": true, "count": 3, "type": "VEC2" }},
+    {{ "bufferView": 2, "componentType": 5121, "normalized": true, "count": 3, "type": "VEC4" }},
+    {{ "bufferView": 3, "componentType": 5121, "count": 3, "type": "SCALAR" }}
+  ],
+  "bufferViews": [
+    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+    {{ "buffer": 0, "byteOffset": 36, "byteLength": 12 }},
+    {{ "buffer": 0, "byteOffset": 48, "byteLength": 12 }},
+    {{ "buffer": 0, "byteOffset": 60, "byteLength": 3 }},
+    {{ "buffer": 0, "byteOffset": 64, "byteLength": 1 }},
+    {{ "buffer": 0, "byteOffset": 68, "byteLength": 12 }}
+  ],
+  "buffers": [{{ {}"byteLength": 80 }}]
+}}"#,
+        uri
+    )
+}
+
+fn glb() -> Vec<u8> {
+    let mut json = document(None).into_bytes();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let bin = binary();
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::new();
+    glb.extend(b"glTF");
+    glb.extend(2u32.to_le_bytes());
+    glb.extend((length as u32).to_le_bytes());
+    glb.extend((json.len() as u32).to_le_bytes());
+    glb.extend(b"JSON");
+    glb.extend(json);
+    glb.extend((bin.len() as u32).to_le_bytes());
+    glb.extend(b"BIN\0");
+    glb.extend(bin);
+    glb
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(name);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn check_scene(scene: &GltfScene) {
+    assert_eq!(scene.meshes.len(), 1);
+    assert_eq!(scene.meshes[0].name.as_deref(), Some("triangle"));
+    let primitive = &scene.meshes[0].primitives[0];
+    assert_eq!(primitive.material, Some(0));
+
+    let mesh = &primitive.mesh;
+    assert_eq!(
+        mesh.positions,
+        vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 2.0, 0.0]]
+    );
+    assert_eq!(mesh.uvs, Some(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]));
+    assert_eq!(mesh.colors.as_ref().unwrap()[1], [0.0, 1.0, 0.0, 1.0]);
+    assert!(mesh.normals.is_none());
+    assert_eq!(mesh.indices, Some(Indices::U16(vec![0, 1, 2])));
+
+    let material = &scene.materials[0];
+    assert_eq!(material.name.as_deref(), Some("gray"));
+    assert_eq!(material.base_color_factor, [0.5, 0.5, 0.5, 1.0]);
+    assert_eq!(material.metallic_factor, 0.25);
+    assert_eq!(material.roughness_factor, 0.75);
+    assert_eq!(material.emissive_factor, [0.1, 0.2, 0.3]);
+    assert_eq!(material.alpha_mode, AlphaMode::Mask);
+    assert_eq!(material.alpha_cutoff, 0.3);
+    assert!(material.double_sided);
+    assert!(material.base_color_texture.is_none());
+
+    assert_eq!(scene.roots, vec![0]);
+    let (parent, child) = (&scene.nodes[0], &scene.nodes[1]);
+    assert_eq!(parent.children, vec![1]);
+    assert_eq!(child.parent, Some(0));
+    let expected_local = create_transforms([0.0; 3], [0.0, 0.0, FRAC_PI_2], [2.0; 3]);
+    assert_relative_eq!(child.local, expected_local, epsilon = 1e-6);
+    let expected_world = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0)) * expected_local;
+    assert_relative_eq!(child.world, expected_world, epsilon = 1e-6);
+    assert_eq!(scene.mesh_instances(), vec![(0, child.world)]);
+}
+
+#[test]
+fn gltf_with_external_buffer() {
+    let dir = temp_dir("gltf_external");
+    std::fs::write(dir.join("triangle.bin"), binary()).unwrap();
+    std::fs::write(dir.join("triangle.gltf"), document(Some("triangle.bin"))).unwrap();
+    check_scene(&load_gltf(dir.join("triangle.gltf")).unwrap());
+}
+
+#[test]
+fn gltf_from_glb() {
+    let dir = temp_dir("gltf_binary");
+    std::fs::write(dir.join("triangle.glb"), glb()).unwrap();
+    check_scene(&load_gltf(dir.join("triangle.glb")).unwrap());
+    check_scene(&import_gltf(&glb(), None).unwrap());
+}
+
+#[test]
+fn gltf_only_reads_local_buffers() {
+    let dir = temp_dir("gltf_missing");
+    std::fs::write(dir.join("missing.gltf"), document(Some("missing.bin"))).unwrap();
+    assert!(load_gltf(dir.join("missing.gltf")).is_err());
+
+    let remote = document(Some("https://example.com/triangle.bin"));
+    assert!(import_gltf(remote.as_bytes(), Some(&dir)).is_err());
+    // without a base directory relative uris cannot be resolved
+    assert!(import_gltf(document(Some("triangle.bin")).as_bytes(), None).is_err());
+}
+
+#[test]
+fn gltf_rejects_node_cycles() {
+    let nodes = |nodes: &str| {
+        format!(
+            r#"{{ "asset": {{ "version": "2.0" }}, "scenes": [{{ "nodes": [0] }}], "nodes": {} }}"#,
+            nodes
+        )
+    };
+    let two_node_cycle = nodes(r#"[{ "children": [1] }, { "children": [0] }]"#);
+    let error = import_gltf(two_node_cycle.as_bytes(), None).unwrap_err();
+    assert!(format!("{:#}", error).contains("cycle"), "{:#}", error);
+
+    let own_child = nodes(r#"[{ "children": [0] }]"#);
+    assert!(import_gltf(own_child.as_bytes(), None).is_err());
+}
@@ -0,0 +1,263 @@
+// Golden-image regression tests: every example scene is rendered headlessly at a fixed
+// resolution and animation time and compared against the reference PNGs in tests/golden.
+// Run with UPDATE_GOLDEN=1 to (re)generate the references after an intended visual change.
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use wgpu_step_by_step::app::primitive;
+
+#[path = "../examples/ch04/triangle.rs"]
+#[allow(dead_code)]
+mod ch04_triangle;
+
+#[path = "../examples/ch06/line3d.rs"]
+#[allow(dead_code)]
+mod ch06_line3d;
+
+#[path = "../examples/ch06/cube_face_color.rs"]
+#[allow(dead_code)]
+mod ch06_cube_face_color;
+
+#[path = "../examples/ch06/cube_fly.rs"]
+#[allow(dead_code)]
+mod ch06_cube_fly;
+
+#[path = "../examples/ch06/cube_vertex_color.rs"]
+#[allow(dead_code)]
+mod ch06_cube_vertex_color;
+
+#[path = "../examples/ch06/cube_instances.rs"]
+#[allow(dead_code)]
+mod ch06_cube_instances;
+
+#[path = "../examples/ch07/blinn_phong.rs"]
+#[allow(dead_code)]
+mod ch07_blinn_phong;
+
+#[path = "../examples/ch07/pbr.rs"]
+#[allow(dead_code)]
+mod ch07_pbr;
+
+#[path = "../examples/ch07/textured_cube.rs"]
+#[allow(dead_code)]
+mod ch07_textured_cube;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 240;
+const ANIMATION_TIME: Duration = Duration::from_secs(1);
+
+// largest per-channel difference for a pixel to still count as matching
+const CHANNEL_TOLERANCE: u8 = 2;
+// share of pixels allowed to exceed the tolerance, to absorb rasterization differences on edges
+const MAX_MISMATCH_RATIO: f64 = 0.001;
+
+// the software adapter is shared process-wide, so scenes are rendered one at a time
+static GPU: Mutex<()> = Mutex::new(());
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.png", name))
+}
+
+fn check_golden(name: &str, actual: image::RgbaImage) {
+    let path = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        actual.save(&path).unwrap();
+        return;
+    }
+
+    let expected = image::open(&path)
+        .unwrap_or_else(|e| {
+            panic!(
+                "missing reference image {} ({}); run with UPDATE_GOLDEN=1 to create it",
+                path.display(),
+                e
+            )
+        })
+        .to_rgba8();
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "{}: image size differs from the reference",
+        name
+    );
+
+    let mut diff = image::RgbaImage::new(actual.width(), actual.height());
+    let mut mismatches = 0usize;
+    for (x, y, a) in actual.enumerate_pixels() {
+        let e = expected.get_pixel(x, y);
+        let max_delta =
+            a.0.iter()
+                .zip(e.0.iter())
+                .map(|(a, e)| a.abs_diff(*e))
+                .max()
+                .unwrap();
+        if max_delta > CHANNEL_TOLERANCE {
+            mismatches += 1;
+            diff.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        } else {
+            // dimmed reference so the mismatches stand out
+            let luma = (e.0[0] as u32 + e.0[1] as u32 + e.0[2] as u32) / 12;
+            diff.put_pixel(x, y, image::Rgba([luma as u8, luma as u8, luma as u8, 255]));
+        }
+    }
+
+    let total = (actual.width() * actual.height()) as usize;
+    if mismatches as f64 > total as f64 * MAX_MISMATCH_RATIO {
+        let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+        std::fs::create_dir_all(&out_dir).unwrap();
+        let actual_path = out_dir.join(format!("{}.actual.png", name));
+        let diff_path = out_dir.join(format!("{}.diff.png", name));
+        actual.save(&actual_path).unwrap();
+        diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: {} of {} pixels differ from {} (actual: {}, diff: {})",
+            name,
+            mismatches,
+            total,
+            path.display(),
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn render_ch03(
+    source: &'static str,
+    topology: wgpu::PrimitiveTopology,
+    strip_index_format: Option<wgpu::IndexFormat>,
+    num_vertices: u32,
+) -> image::RgbaImage {
+    let inputs = primitive::Inputs {
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(source)),
+        topology,
+        strip_index_format,
+    };
+    primitive::render_headless(inputs, num_vertices, WIDTH, HEIGHT).unwrap()
+}
+
+#[test]
+fn ch03_point_list() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = render_ch03(
+        include_str!("../examples/ch03/point_line.wgsl"),
+        wgpu::PrimitiveTopology::PointList,
+        None,
+        6,
+    );
+    check_golden("ch03_point_list", image);
+}
+
+#[test]
+fn ch03_line_list() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = render_ch03(
+        include_str!("../examples/ch03/point_line.wgsl"),
+        wgpu::PrimitiveTopology::LineList,
+        None,
+        6,
+    );
+    check_golden("ch03_line_list", image);
+}
+
+#[test]
+fn ch03_line_strip() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = render_ch03(
+        include_str!("../examples/ch03/point_line.wgsl"),
+        wgpu::PrimitiveTopology::LineStrip,
+        Some(wgpu::IndexFormat::Uint32),
+        6,
+    );
+    check_golden("ch03_line_strip", image);
+}
+
+#[test]
+fn ch03_triangle_list() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = render_ch03(
+        include_str!("../examples/ch03/triangles.wgsl"),
+        wgpu::PrimitiveTopology::TriangleList,
+        None,
+        9,
+    );
+    check_golden("ch03_triangle_list", image);
+}
+
+#[test]
+fn ch03_triangle_strip() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = render_ch03(
+        include_str!("../examples/ch03/triangles.wgsl"),
+        wgpu::PrimitiveTopology::TriangleStrip,
+        Some(wgpu::IndexFormat::Uint32),
+        9,
+    );
+    check_golden("ch03_triangle_strip", image);
+}
+
+#[test]
+fn ch04_triangle() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch04_triangle::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch04_triangle", image);
+}
+
+#[test]
+fn ch06_line3d() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch06_line3d::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch06_line3d", image);
+}
+
+#[test]
+fn ch06_cube_face_color() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch06_cube_face_color::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch06_cube_face_color", image);
+}
+
+#[test]
+fn ch06_cube_fly() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch06_cube_fly::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch06_cube_fly", image);
+}
+
+#[test]
+fn ch06_cube_vertex_color() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch06_cube_vertex_color::render_headless(WIDTH, HEIGHT, ANIMATION_TIME).unwrap();
+    check_golden("ch06_cube_vertex_color", image);
+}
+
+#[test]
+fn ch06_cube_instances() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch06_cube_instances::render_headless(WIDTH, HEIGHT, ANIMATION_TIME).unwrap();
+    check_golden("ch06_cube_instances", image);
+}
+
+#[test]
+fn ch07_blinn_phong() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch07_blinn_phong::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch07_blinn_phong", image);
+}
+
+#[test]
+fn ch07_pbr() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch07_pbr::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch07_pbr", image);
+}
+
+#[test]
+fn ch07_textured_cube() {
+    let _gpu = GPU.lock().unwrap_or_else(|e| e.into_inner());
+    let image = ch07_textured_cube::render_headless(WIDTH, HEIGHT).unwrap();
+    check_golden("ch07_textured_cube", image);
+}
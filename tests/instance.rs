@@ -0,0 +1,241 @@
+use cgmath::*;
+use wgpu_step_by_step::capture;
+use wgpu_step_by_step::geometry::Mesh;
+use wgpu_step_by_step::instance::{Instance, InstanceBuffer};
+use wgpu_step_by_step::InitWgpu;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+// moves each vertex by the instance model matrix and paints it in the instance color
+const SHADER: &str = "
+struct Output {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) model0: vec4<f32>,
+    @location(2) model1: vec4<f32>,
+    @location(3) model2: vec4<f32>,
+    @location(4) model3: vec4<f32>,
+    @location(5) color: vec4<f32>,
+) -> Output {
+    let model = mat4x4<f32>(model0, model1, model2, model3);
+    return Output(model * vec4<f32>(position, 1.0), color);
+}
+
+@fragment
+fn fs_main(@location(0) color: vec4<f32>) -> @location(0) vec4<f32> {
+    return color;
+}
+";
+
+fn init() -> InitWgpu<'static> {
+    pollster::block_on(InitWgpu::init_headless(32, 32, FORMAT))
+}
+
+#[test]
+fn layout_steps_per_instance_after_the_mesh_attributes() {
+    let layout = Instance::layout(3);
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Instance);
+    assert_eq!(layout.array_stride, 80);
+    let attributes: Vec<_> = layout
+        .attributes
+        .iter()
+        .map(|a| (a.shader_location, a.offset, a.format))
+        .collect();
+    let column = wgpu::VertexFormat::Float32x4;
+    assert_eq!(
+        attributes,
+        vec![
+            (3, 0, column),
+            (4, 16, column),
+            (5, 32, column),
+            (6, 48, column),
+            (7, 64, column),
+        ]
+    );
+}
+
+#[test]
+fn instances_store_the_model_matrix_by_column() {
+    let model = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0));
+    let instance = Instance::new(model, [0.5; 4]);
+    assert_eq!(instance.model[3], [1.0, 2.0, 3.0, 1.0]);
+    assert_eq!(instance.color, [0.5; 4]);
+}
+
+// buffers alive on the device, as counted by wgpu itself
+fn live_buffers(init: &InitWgpu) -> usize {
+    let report = init.instance.generate_report().unwrap();
+    report
+        .hub_report(init.adapter.get_info().backend)
+        .buffers
+        .num_allocated
+}
+
+#[test]
+fn buffer_grows_by_powers_of_two_and_never_shrinks() {
+    let init = init();
+    let instance = Instance::new(Matrix4::identity(), [1.0; 4]);
+    let before = live_buffers(&init);
+    let mut buffer = InstanceBuffer::with_capacity(&init.device, 0);
+    assert!(buffer.is_empty());
+    assert_eq!(buffer.capacity(), 1);
+    assert_eq!(live_buffers(&init), before + 1);
+
+    // (instances, grown, capacity)
+    let steps = [
+        (3, true, 4),
+        (4, false, 4),
+        (1, false, 4),
+        (0, false, 4),
+        (5, true, 8),
+        (100, true, 128),
+        (20, false, 128),
+    ];
+    for (count, grown, capacity) in steps {
+        let previous = buffer.buffer.global_id();
+        let instances = vec![instance; count];
+        assert_eq!(
+            buffer.update(&init.device, &init.queue, &instances),
+            grown,
+            "{}",
+            count
+        );
+        assert_eq!(buffer.buffer.global_id() != previous, grown, "{}", count);
+        assert_eq!(buffer.len(), count);
+        assert_eq!(buffer.capacity(), capacity);
+        assert_eq!(
+            buffer.buffer.size(),
+            (capacity * std::mem::size_of::<Instance>()) as u64
+        );
+        // a grown buffer replaces the old one rather than adding to it
+        init.device.poll(wgpu::Maintain::Wait);
+        assert_eq!(live_buffers(&init), before + 1, "{}", count);
+    }
+
+    let sized = InstanceBuffer::new(&init.device, &init.queue, &vec![instance; 6]);
+    assert_eq!((sized.len(), sized.capacity()), (6, 6));
+    assert_eq!(
+        sized.buffer.size(),
+        (6 * std::mem::size_of::<Instance>()) as u64
+    );
+}
+
+// a square covering one quarter of the target, drawn once per instance
+fn render_squares(init: &InitWgpu, instances: &InstanceBuffer) -> image::RgbaImage {
+    let square = Mesh {
+        positions: vec![
+            [-0.5, -0.5, 0.0],
+            [0.5, -0.5, 0.0],
+            [0.5, 0.5, 0.0],
+            [-0.5, -0.5, 0.0],
+            [0.5, 0.5, 0.0],
+            [-0.5, 0.5, 0.0],
+        ],
+        ..Default::default()
+    };
+    let mesh_layout = square.layout(0);
+    let instance_layout = Instance::layout(1);
+    let shader = init
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+    let pipeline = init
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[mesh_layout.buffer_layout(), instance_layout.buffer_layout()],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(FORMAT.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            multiview: None,
+            cache: None,
+        });
+    let mesh = square.upload(&init.device);
+
+    let frame = init.get_current_frame().unwrap();
+    let view = frame
+        .texture()
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &view,
+                resolve_target: None,
+                ops: wgpu::Operations::default(),
+            })],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&pipeline);
+        mesh.draw_instanced(&mut pass, instances);
+    }
+    init.queue.submit(Some(encoder.finish()));
+    capture::read_texture(&init.device, &init.queue, frame.texture()).unwrap()
+}
+
+#[test]
+fn each_instance_is_drawn_with_its_own_transform_and_color() {
+    let init = init();
+    let square = |x: f32, y: f32, color: [f32; 4]| {
+        Instance::new(Matrix4::from_translation(Vector3::new(x, y, 0.0)), color)
+    };
+    let red = [1.0, 0.0, 0.0, 1.0];
+    let green = [0.0, 1.0, 0.0, 1.0];
+    let blue = [0.0, 0.0, 1.0, 1.0];
+    let mut instances = InstanceBuffer::new(
+        &init.device,
+        &init.queue,
+        &[square(-0.5, 0.5, red), square(0.5, -0.5, green)],
+    );
+
+    // the top left and bottom right quarters, the rest left clear
+    let image = render_squares(&init, &instances);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(24, 24).0, [0, 255, 0, 255]);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 0, 0]);
+    assert_eq!(image.get_pixel(8, 24).0, [0, 0, 0, 0]);
+
+    // a third instance outgrows the buffer and is drawn as well
+    let grown = instances.update(
+        &init.device,
+        &init.queue,
+        &[
+            square(-0.5, 0.5, red),
+            square(0.5, -0.5, green),
+            square(0.5, 0.5, blue),
+        ],
+    );
+    assert!(grown);
+    let image = render_squares(&init, &instances);
+    assert_eq!(image.get_pixel(24, 8).0, [0, 0, 255, 255]);
+    assert_eq!(image.get_pixel(8, 8).0, [255, 0, 0, 255]);
+
+    // an empty buffer draws nothing
+    instances.update(&init.device, &init.queue, &[]);
+    let image = render_squares(&init, &instances);
+    assert!(image.pixels().all(|pixel| pixel.0 == [0, 0, 0, 0]));
+}
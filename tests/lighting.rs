@@ -0,0 +1,121 @@
+use cgmath::*;
+use std::mem;
+use wgpu_step_by_step::geometry::gltf::AlphaMode;
+use wgpu_step_by_step::geometry::PbrMaterial;
+use wgpu_step_by_step::lighting::pbr::PbrMaterialUniform;
+use wgpu_step_by_step::lighting::shadow::{ShadowUniform, MAX_CASCADES};
+use wgpu_step_by_step::lighting::{
+    normal_matrix, Attenuation, FrameUniform, Light, LightColor, LightUniform, MAX_LIGHTS,
+};
+
+fn spot() -> Light {
+    Light::Spot {
+        position: Point3::new(1.0, 2.0, 3.0),
+        direction: Vector3::new(0.0, -1.0, 0.0),
+        inner_angle: Deg(30.0).into(),
+        outer_angle: Deg(60.0).into(),
+        color: LightColor::new([1.0, 0.5, 0.25], 0.2),
+        attenuation: Attenuation {
+            constant: 1.0,
+            linear: 0.5,
+            quadratic: 0.25,
+        },
+    }
+}
+
+#[test]
+fn uniforms_match_the_wgsl_layout() {
+    // vec3 + scalar pairs, six per light
+    assert_eq!(mem::size_of::<LightUniform>(), 96);
+    assert_eq!(
+        mem::size_of::<FrameUniform>(),
+        64 + 16 + MAX_LIGHTS * mem::size_of::<LightUniform>()
+    );
+    assert_eq!(mem::size_of::<PbrMaterialUniform>(), 64);
+    assert_eq!(mem::size_of::<ShadowUniform>(), MAX_CASCADES * 64 + 16);
+}
+
+#[test]
+fn lights_pack_kind_cone_and_colors() {
+    let uniform = LightUniform::from(&spot());
+    assert_eq!(uniform.kind, 2);
+    assert_eq!(uniform.position, [1.0, 2.0, 3.0]);
+    assert_eq!(uniform.direction, [0.0, -1.0, 0.0]);
+    assert_relative_eq!(uniform.inner_cos, 0.75f32.sqrt(), epsilon = 1e-6);
+    assert_relative_eq!(uniform.outer_cos, 0.5, epsilon = 1e-6);
+    assert_eq!(uniform.ambient, [0.2, 0.1, 0.05]);
+    assert_eq!(uniform.diffuse, [1.0, 0.5, 0.25]);
+    assert_eq!(uniform.specular, [1.0, 0.5, 0.25]);
+    assert_eq!(uniform.attenuation, [1.0, 0.5, 0.25]);
+
+    let sun = Light::Directional {
+        direction: Vector3::new(0.0, -1.0, 0.0),
+        color: LightColor::new([1.0, 1.0, 1.0], 0.1),
+    };
+    assert_eq!(LightUniform::from(&sun).kind, 0);
+}
+
+#[test]
+fn frame_keeps_at_most_max_lights() {
+    let lights = vec![spot(); MAX_LIGHTS + 3];
+    let frame = FrameUniform::new(Matrix4::identity(), Point3::new(0.0, 1.0, 5.0), &lights);
+    assert_eq!(frame.light_count as usize, MAX_LIGHTS);
+    assert_eq!(frame.camera_position, [0.0, 1.0, 5.0]);
+    assert_eq!(frame.lights[MAX_LIGHTS - 1], LightUniform::from(&spot()));
+
+    let frame = FrameUniform::new(Matrix4::identity(), Point3::origin(), &lights[..2]);
+    assert_eq!(frame.light_count, 2);
+    assert_eq!(frame.lights[2], LightUniform::default());
+}
+
+#[test]
+fn normal_matrix_keeps_normals_perpendicular_under_non_uniform_scale() {
+    let model = Matrix4::from_translation(Vector3::new(5.0, -2.0, 1.0))
+        * Matrix4::from_angle_z(Deg(30.0))
+        * Matrix4::from_nonuniform_scale(4.0, 1.0, 0.5);
+    // the 45° slope x + y = 0 with its normal
+    let tangent = Vector4::new(1.0, -1.0, 0.0, 0.0);
+    let normal = Vector4::new(1.0, 1.0, 0.0, 0.0);
+
+    let world_tangent = model * tangent;
+    let world_normal = normal_matrix(model) * normal;
+    assert_relative_eq!(world_tangent.dot(world_normal), 0.0, epsilon = 1e-5);
+    // the model matrix itself would tilt the normal off the surface
+    assert!((model * normal).dot(world_tangent).abs() > 1.0);
+    // translation never reaches the normals
+    assert_eq!(normal_matrix(model).w, Vector4::unit_w());
+}
+
+#[test]
+fn pbr_material_uniform_takes_the_gltf_factors() {
+    let mut material = PbrMaterial {
+        base_color_factor: [0.5, 0.25, 1.0, 0.75],
+        metallic_factor: 0.3,
+        roughness_factor: 0.6,
+        emissive_factor: [1.0, 0.0, 0.5],
+        occlusion_strength: 0.5,
+        normal_scale: 2.0,
+        ..Default::default()
+    };
+    let uniform = PbrMaterialUniform::new(&material, true);
+    assert_eq!(uniform.base_color, [0.5, 0.25, 1.0, 0.75]);
+    assert_eq!(uniform.emissive, [1.0, 0.0, 0.5]);
+    assert_eq!(
+        (
+            uniform.metallic,
+            uniform.roughness,
+            uniform.occlusion_strength
+        ),
+        (0.3, 0.6, 0.5)
+    );
+    assert_eq!(uniform.normal_scale, 2.0);
+    assert_eq!(uniform.has_normal_texture, 1);
+    // only masked materials discard fragments
+    assert!(uniform.alpha_cutoff < 0.0);
+
+    material.alpha_mode = AlphaMode::Mask;
+    material.alpha_cutoff = 0.4;
+    let uniform = PbrMaterialUniform::new(&material, false);
+    assert_eq!(uniform.alpha_cutoff, 0.4);
+    assert_eq!(uniform.has_normal_texture, 0);
+}
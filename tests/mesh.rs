@@ -0,0 +1,90 @@
+use wgpu_step_by_step::geometry::{plane_data, Attribute, Indices, Mesh};
+use wgpu_step_by_step::InitWgpu;
+
+fn triangle() -> Mesh {
+    Mesh {
+        positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        uvs: Some(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]]),
+        colors: Some(vec![[1.0, 0.0, 0.0, 1.0]; 3]),
+        indices: Some(Indices::U16(vec![0, 1, 2])),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn layout_assigns_consecutive_locations() {
+    let mesh = triangle();
+    assert_eq!(
+        mesh.attributes(),
+        vec![Attribute::Position, Attribute::Uv, Attribute::Color]
+    );
+
+    let layout = mesh.layout(0);
+    assert_eq!(layout.array_stride, 36);
+    assert_eq!(layout.step_mode, wgpu::VertexStepMode::Vertex);
+    let attributes: Vec<_> = layout
+        .attributes
+        .iter()
+        .map(|a| (a.shader_location, a.offset, a.format))
+        .collect();
+    assert_eq!(
+        attributes,
+        vec![
+            (0, 0, wgpu::VertexFormat::Float32x3),
+            (1, 12, wgpu::VertexFormat::Float32x2),
+            (2, 20, wgpu::VertexFormat::Float32x4),
+        ]
+    );
+
+    let shifted = mesh.layout(3);
+    assert_eq!(shifted.attributes[0].shader_location, 3);
+    assert_eq!(shifted.attributes[2].shader_location, 5);
+}
+
+#[test]
+fn interleaved_packs_vertices_in_order() {
+    let mesh = triangle();
+    let bytes = mesh.interleaved();
+    assert_eq!(bytes.len(), 3 * 36);
+    let floats: &[f32] = bytemuck::cast_slice(&bytes);
+    assert_eq!(
+        &floats[9..18],
+        &[1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0]
+    );
+}
+
+#[test]
+#[should_panic(expected = "mesh has 3 positions but 2 colors")]
+fn interleaved_rejects_mismatched_attributes() {
+    let mut mesh = triangle();
+    mesh.colors = Some(vec![[1.0; 4]; 2]);
+    mesh.interleaved();
+}
+
+#[test]
+fn from_mesh_data_keeps_normals_and_uvs() {
+    let data = plane_data(1.0, 1.0, 2, 2);
+    let mesh = Mesh::from(data.clone());
+    assert_eq!(
+        mesh.attributes(),
+        vec![Attribute::Position, Attribute::Normal, Attribute::Uv]
+    );
+    let indices = mesh.indices.as_ref().unwrap();
+    assert_eq!(indices.format(), wgpu::IndexFormat::Uint32);
+    assert_eq!(indices.iter().collect::<Vec<_>>(), data.indices);
+}
+
+#[test]
+fn upload_creates_vertex_and_index_buffers() {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        4,
+        4,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let gpu_mesh = triangle().upload(&init.device);
+    assert_eq!(gpu_mesh.vertex_buffer.size(), 3 * 36);
+    let (index_buffer, format) = gpu_mesh.index_buffer.as_ref().unwrap();
+    assert_eq!(*format, wgpu::IndexFormat::Uint16);
+    assert_eq!(index_buffer.size(), 8);
+    assert_eq!((gpu_mesh.vertex_count, gpu_mesh.index_count), (3, 3));
+}
@@ -0,0 +1,135 @@
+use wgpu_step_by_step::capture;
+use wgpu_step_by_step::msaa::{MsaaTarget, SAMPLE_COUNTS};
+use wgpu_step_by_step::InitWgpu;
+
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+// a white triangle whose hypotenuse runs diagonally across the target
+const SHADER: &str = "
+@vertex
+fn vs_main(@builtin(vertex_index) index: u32) -> @builtin(position) vec4<f32> {
+    var corners = array<vec2<f32>, 3>(vec2(-1.0, -1.0), vec2(1.0, -1.0), vec2(-1.0, 0.7));
+    return vec4<f32>(corners[index], 0.0, 1.0);
+}
+
+@fragment
+fn fs_main() -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0);
+}
+";
+
+fn init() -> InitWgpu<'static> {
+    pollster::block_on(InitWgpu::init_headless(32, 32, FORMAT))
+}
+
+fn render_edge(init: &InitWgpu, sample_count: u32) -> image::RgbaImage {
+    let msaa = MsaaTarget::new(&init.device, 32, 32, FORMAT, sample_count);
+    let shader = init
+        .device
+        .create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: None,
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+    let pipeline = init
+        .device
+        .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: None,
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[],
+                compilation_options: Default::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[Some(FORMAT.into())],
+                compilation_options: Default::default(),
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: msaa.multisample_state(),
+            multiview: None,
+            cache: None,
+        });
+
+    let frame = init.get_current_frame().unwrap();
+    let view = frame
+        .texture()
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            color_attachments: &[Some(msaa.color_attachment(&view, wgpu::Color::BLACK))],
+            depth_stencil_attachment: None,
+        });
+        pass.set_pipeline(&pipeline);
+        pass.draw(0..3, 0..1);
+    }
+    init.queue.submit(Some(encoder.finish()));
+    capture::read_texture(&init.device, &init.queue, frame.texture()).unwrap()
+}
+
+// pixels that are neither background nor triangle
+fn blended_pixels(image: &image::RgbaImage) -> usize {
+    image.pixels().filter(|p| p[0] != 0 && p[0] != 255).count()
+}
+
+#[test]
+fn sample_counts_are_validated() {
+    let init = init();
+    let supported = init.supported_sample_counts(FORMAT);
+    assert_eq!(supported[0], 1);
+    assert!(supported.iter().all(|count| SAMPLE_COUNTS.contains(count)));
+
+    assert!(init.sample_count(3, &[FORMAT]).is_err());
+    assert!(init.sample_count(16, &[FORMAT]).is_err());
+    assert_eq!(init.sample_count(1, &[FORMAT]).unwrap(), 1);
+    // higher counts fall back to one every format supports
+    let count = init
+        .sample_count(8, &[FORMAT, wgpu::TextureFormat::Depth32Float])
+        .unwrap();
+    assert!(supported.contains(&count));
+    assert!(init
+        .supported_sample_counts(wgpu::TextureFormat::Depth32Float)
+        .contains(&count));
+}
+
+#[test]
+fn multisampled_edges_resolve_to_blended_pixels() {
+    let init = init();
+    let aliased = render_edge(&init, 1);
+    assert_eq!(blended_pixels(&aliased), 0);
+
+    let count = init.sample_count(4, &[FORMAT]).unwrap();
+    assert!(count > 1, "the adapter has no MSAA for {:?}", FORMAT);
+    let smooth = render_edge(&init, count);
+    assert!(blended_pixels(&smooth) >= 16);
+    // the interior is untouched by the resolve
+    assert_eq!(smooth.get_pixel(2, 29)[0], 255);
+    assert_eq!(smooth.get_pixel(29, 2)[0], 0);
+}
+
+#[test]
+fn resize_reallocates_only_multisampled_targets() {
+    let init = init();
+    let mut single = MsaaTarget::new(&init.device, 32, 32, FORMAT, 1);
+    assert!(single.texture.is_none());
+    assert!(!single.resize(&init.device, 64, 64));
+    assert_eq!(single.multisample_state().count, 1);
+
+    let count = init.sample_count(4, &[FORMAT]).unwrap();
+    let mut msaa = MsaaTarget::new(&init.device, 32, 32, FORMAT, count);
+    assert!(!msaa.resize(&init.device, 32, 32));
+    assert!(!msaa.resize(&init.device, 0, 0));
+    assert!(msaa.resize(&init.device, 64, 48));
+    let texture = msaa.texture.as_ref().unwrap();
+    assert_eq!((texture.width(), texture.height()), (64, 48));
+    assert_eq!(texture.sample_count(), count);
+}
@@ -0,0 +1,210 @@
+use wgpu_step_by_step::geometry::obj::{parse_mtl, parse_obj, triangulate};
+use wgpu_step_by_step::geometry::{load_obj, Indices, ObjError};
+
+fn indices(indices: &Option<Indices>) -> Vec<u32> {
+    indices.as_ref().unwrap().iter().collect()
+}
+
+fn parse_error(source: &str) -> (usize, String) {
+    match parse_obj(source, "test.obj") {
+        Err(ObjError::Parse { line, message, .. }) => (line, message),
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn quads_are_triangulated_and_deduplicated() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 2 0 0
+v 2 1 0
+f 1 2 3 4
+f 2 5 6 3
+";
+    let model = parse_obj(source, "test.obj").unwrap();
+    assert_eq!(model.meshes.len(), 1);
+    let mesh = &model.meshes[0].mesh;
+    // the shared edge is stored once
+    assert_eq!(mesh.positions.len(), 6);
+    assert_eq!(indices(&mesh.indices).len(), 12);
+    assert!(mesh.uvs.is_none());
+    // generated normals face the viewer of a counter-clockwise face
+    for normal in mesh.normals.as_ref().unwrap() {
+        assert!((normal[2] - 1.0).abs() < 1e-6, "{:?}", normal);
+    }
+}
+
+#[test]
+fn negative_indices_are_relative() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f -3 -2 -1
+v 5 5 5
+v 6 5 5
+v 5 6 5
+f -3 -2 -1
+";
+    let mesh = &parse_obj(source, "test.obj").unwrap().meshes[0].mesh;
+    assert_eq!(mesh.positions[3], [5.0, 5.0, 5.0]);
+    assert_eq!(indices(&mesh.indices), vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn missing_uvs_and_normals_per_vertex() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vn 0 0 -1
+f 1/1 2/2/1 3//1
+";
+    let mesh = &parse_obj(source, "test.obj").unwrap().meshes[0].mesh;
+    // texture coordinates are flipped to a top-left origin and absent ones are zero
+    assert_eq!(mesh.uvs, Some(vec![[0.0, 1.0], [1.0, 1.0], [0.0, 0.0]]));
+    let normals = mesh.normals.as_ref().unwrap();
+    assert_eq!(normals[0], [0.0, 0.0, 1.0]);
+    assert_eq!(normals[1], [0.0, 0.0, -1.0]);
+}
+
+#[test]
+fn vertex_colors_are_kept() {
+    let source = "
+v 0 0 0 1 0 0
+v 1 0 0 0 1 0
+v 0 1 0 0 0 1
+f 1 2 3
+";
+    let mesh = &parse_obj(source, "test.obj").unwrap().meshes[0].mesh;
+    assert_eq!(mesh.colors.as_ref().unwrap()[1], [0.0, 1.0, 0.0, 1.0]);
+
+    // with a w coordinate the color is the last three values
+    let source = "
+v 0 0 0 1 1 0 0
+v 1 0 0 1 0 1 0
+v 0 1 0 1 0 0.5 1
+f 1 2 3
+";
+    let mesh = &parse_obj(source, "test.obj").unwrap().meshes[0].mesh;
+    assert_eq!(mesh.colors.as_ref().unwrap()[2], [0.0, 0.5, 1.0, 1.0]);
+    assert_eq!(mesh.positions[2], [0.0, 1.0, 0.0]);
+    assert_eq!(
+        parse_error("v 0 0 0 1 0\n"),
+        (1, "expected 3, 4, 6 or 7 numbers, got 5".to_string())
+    );
+}
+
+#[test]
+fn groups_and_materials_split_meshes() {
+    let source = "
+v 0 0 0
+v 1 0 0
+v 0 1 0
+o first
+usemtl a
+f 1 2 3
+usemtl b
+f 1 2 3
+o second
+f 3 2 1
+";
+    let model = parse_obj(source, "test.obj").unwrap();
+    let meshes: Vec<_> = model
+        .meshes
+        .iter()
+        .map(|m| (m.name.as_str(), m.material.as_deref()))
+        .collect();
+    assert_eq!(
+        meshes,
+        vec![
+            ("first", Some("a")),
+            ("first", Some("b")),
+            ("second", Some("b"))
+        ]
+    );
+}
+
+#[test]
+fn concave_polygons_use_ear_clipping() {
+    // an L shape whose first vertex is the reflex corner, so a fan would overlap
+    let points = [
+        [1.0, 1.0, 0.0],
+        [0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0],
+        [2.0, 0.0, 0.0],
+        [2.0, 2.0, 0.0],
+        [1.0, 2.0, 0.0],
+    ];
+    let triangles = triangulate(&points);
+    assert_eq!(triangles.len(), 4);
+    let mut area = 0.0;
+    for [a, b, c] in triangles {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        let cross = (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0]);
+        assert!(cross > 0.0, "triangle winding flipped");
+        area += cross / 2.0;
+    }
+    assert!((area - 3.0).abs() < 1e-6);
+}
+
+#[test]
+fn errors_report_line_numbers() {
+    assert_eq!(
+        parse_error("v 0 0 0\nv 1 0\n"),
+        (2, "expected 3 to 7 numbers, got 2".to_string())
+    );
+    let (line, message) = parse_error("v 0 0 0\nv 1 0 0\nv 0 1 0\n\nf 1 2 4\n");
+    assert_eq!(line, 5);
+    assert!(message.contains("out of range"), "{}", message);
+    let (line, message) = parse_error("v 0 0 0\nf 0 1 1\n");
+    assert_eq!(line, 2);
+    assert!(message.contains("index 0"), "{}", message);
+    assert_eq!(parse_error("v 0 x 0\n").1, "invalid number 'x'");
+
+    // colors have to be on every vertex or on none, in either order
+    let mixed = "vertex colors on some vertices but not on others".to_string();
+    assert_eq!(
+        parse_error("v 0 0 0 1 0 0\nv 1 0 0\nv 0 1 0 0 1 0\nf 1 2 3\n"),
+        (2, mixed.clone())
+    );
+    assert_eq!(
+        parse_error("v 0 0 0\nv 1 0 0 1 0 0\nv 0 1 0 0 1 0\nf 1 2 3\n"),
+        (2, mixed)
+    );
+
+    match parse_mtl("Kd 1 1 1\n", "test.mtl") {
+        Err(ObjError::Parse { line: 1, .. }) => {}
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn load_obj_reads_material_libraries() {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/quad.obj");
+    let model = load_obj(path).unwrap();
+    assert_eq!(model.meshes.len(), 1);
+    let quad = &model.meshes[0];
+    assert_eq!(quad.name, "quad");
+    assert_eq!(quad.mesh.positions.len(), 4);
+    assert_eq!(indices(&quad.mesh.indices).len(), 6);
+
+    let material = model.material(quad).unwrap();
+    assert_eq!(material.name, "red");
+    assert_eq!(material.ambient, [0.1, 0.0, 0.0]);
+    assert_eq!(material.diffuse, [0.8, 0.1, 0.1]);
+    assert_eq!(material.specular, [0.5, 0.5, 0.5]);
+    assert_eq!(material.shininess, 32.0);
+    let texture = material.diffuse_texture.as_ref().unwrap();
+    assert!(texture.ends_with("data/textures/red.png"), "{:?}", texture);
+
+    assert!(matches!(
+        load_obj("tests/data/missing.obj"),
+        Err(ObjError::Io { .. })
+    ));
+}
@@ -0,0 +1,240 @@
+use cgmath::Transform as _;
+use cgmath::*;
+use wgpu_step_by_step::geometry::{vertex_data, Aabb, Indices, Mesh};
+use wgpu_step_by_step::picking::{pick_draw_commands, pick_mesh, screen_to_ndc, Ray};
+use wgpu_step_by_step::scene::SceneGraph;
+use wgpu_step_by_step::transforms::{create_view, create_view_projection, Projection, Transform};
+
+fn cube() -> Mesh {
+    let (pos, _, ind) = vertex_data::cube_data_index();
+    Mesh {
+        positions: pos.iter().map(|p| p.map(f32::from)).collect(),
+        indices: Some(Indices::U16(ind)),
+        ..Default::default()
+    }
+}
+
+// shortest distance between the ray's line and a point
+fn miss_distance(ray: &Ray, point: Point3<f32>) -> f32 {
+    let offset = point - ray.origin;
+    (offset - ray.direction * offset.dot(ray.direction)).magnitude()
+}
+
+#[test]
+fn screen_positions_map_to_ndc() {
+    assert_eq!(screen_to_ndc(0.0, 0.0, 800, 600), Point2::new(-1.0, 1.0));
+    assert_eq!(
+        screen_to_ndc(800.0, 600.0, 800, 600),
+        Point2::new(1.0, -1.0)
+    );
+    assert_eq!(screen_to_ndc(400.0, 300.0, 800, 600), Point2::new(0.0, 0.0));
+    assert_eq!(
+        screen_to_ndc(200.0, 450.0, 800, 600),
+        Point2::new(-0.5, -0.5)
+    );
+}
+
+#[test]
+fn rays_start_on_the_near_plane_and_follow_the_view() {
+    let eye = Point3::new(0.0, 0.0, 5.0);
+    let (_, _, view_projection) =
+        create_view_projection(eye, Point3::origin(), Vector3::unit_y(), 1.5, true);
+
+    let centre = Ray::from_ndc(Point2::new(0.0, 0.0), view_projection).unwrap();
+    assert_relative_eq!(centre.origin, Point3::new(0.0, 0.0, 4.9), epsilon = 1e-4);
+    assert_relative_eq!(centre.direction, -Vector3::unit_z(), epsilon = 1e-5);
+
+    // the top right corner of a 72 degree field of view
+    let corner = Ray::from_ndc(Point2::new(1.0, 1.0), view_projection).unwrap();
+    let tan_y = Deg(36.0).tan();
+    let expected = Vector3::new(tan_y * 1.5, tan_y, -1.0).normalize();
+    assert_relative_eq!(corner.direction, expected, epsilon = 1e-4);
+    assert_relative_eq!(miss_distance(&corner, eye), 0.0, epsilon = 1e-4);
+
+    // orthographic rays are parallel, offset across the view box
+    let (_, _, view_projection) =
+        create_view_projection(eye, Point3::origin(), Vector3::unit_y(), 1.5, false);
+    let ray = Ray::from_ndc(Point2::new(0.5, -1.0), view_projection).unwrap();
+    assert_relative_eq!(ray.direction, -Vector3::unit_z(), epsilon = 1e-5);
+    assert_relative_eq!(ray.origin, Point3::new(2.0, -3.0, 6.0), epsilon = 1e-4);
+}
+
+#[test]
+fn rays_pass_through_the_points_they_were_projected_from() {
+    let view = create_view(
+        Point3::new(3.0, 2.0, 6.0),
+        Point3::new(0.0, 0.5, 0.0),
+        Vector3::unit_y(),
+    );
+    let projections = [
+        Projection::perspective(Deg(60.0), 1.3, 0.1, 50.0),
+        Projection::perspective(Deg(60.0), 1.3, 0.1, 50.0).with_reverse_z(true),
+        Projection::perspective(Deg(60.0), 1.3, 0.1, f32::INFINITY),
+        Projection::perspective(Deg(60.0), 1.3, 0.1, f32::INFINITY).with_reverse_z(true),
+        Projection::orthographic(6.0, 1.3, 0.1, 50.0),
+    ];
+    let points = [
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, -0.5, 0.3),
+        Point3::new(-2.0, 1.0, -4.0),
+    ];
+    for projection in projections {
+        let matrix = projection.matrix() * view;
+        for point in points {
+            let clip = matrix * point.to_homogeneous();
+            let ndc = Point2::new(clip.x / clip.w, clip.y / clip.w);
+            let ray = Ray::from_camera(ndc, view, &projection).unwrap();
+            assert_relative_eq!(ray.direction.magnitude(), 1.0, epsilon = 1e-5);
+            assert_relative_eq!(miss_distance(&ray, point), 0.0, epsilon = 1e-3);
+            // the point lies ahead of the ray, not behind it
+            assert!(
+                (point - ray.origin).dot(ray.direction) > 0.0,
+                "{:?}",
+                projection
+            );
+        }
+    }
+}
+
+#[test]
+fn rays_clip_against_box_slabs() {
+    let aabb = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    let ray = Ray::new(Point3::new(-5.0, 0.5, 0.0), Vector3::unit_x());
+    assert_eq!(ray.intersect_aabb(&aabb), Some((4.0, 6.0)));
+
+    // diagonal through two corners
+    let diagonal = Ray::new(Point3::new(-2.0, -2.0, -2.0), Vector3::new(1.0, 1.0, 1.0));
+    let (near, far) = diagonal.intersect_aabb(&aabb).unwrap();
+    assert_relative_eq!(near, 3f32.sqrt(), epsilon = 1e-5);
+    assert_relative_eq!(far, 3.0 * 3f32.sqrt(), epsilon = 1e-5);
+
+    let inside = Ray::new(Point3::origin(), -Vector3::unit_y());
+    assert_eq!(inside.intersect_aabb(&aabb), Some((0.0, 1.0)));
+    let away = Ray::new(Point3::new(-5.0, 0.5, 0.0), -Vector3::unit_x());
+    assert_eq!(away.intersect_aabb(&aabb), None);
+    let passing = Ray::new(Point3::new(-5.0, 1.5, 0.0), Vector3::unit_x());
+    assert_eq!(passing.intersect_aabb(&aabb), None);
+    let skew = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.5, 0.0));
+    assert_eq!(skew.intersect_aabb(&aabb), None);
+}
+
+#[test]
+fn triangle_hits_report_distance_and_barycentrics() {
+    let (a, b, c) = (
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(2.0, 0.0, 0.0),
+        Point3::new(0.0, 2.0, 0.0),
+    );
+    let ray = Ray::new(Point3::new(0.5, 0.25, 3.0), -Vector3::unit_z());
+    let (distance, u, v) = ray.intersect_triangle(a, b, c).unwrap();
+    assert_relative_eq!(distance, 3.0);
+    assert_relative_eq!(u, 0.25);
+    assert_relative_eq!(v, 0.125);
+
+    // from behind the triangle, and at an edge
+    let back = Ray::new(Point3::new(0.5, 0.25, -1.0), Vector3::unit_z());
+    assert_relative_eq!(back.intersect_triangle(a, b, c).unwrap().0, 1.0);
+    let edge = Ray::new(Point3::new(1.0, 1.0, 1.0), -Vector3::unit_z());
+    assert!(edge.intersect_triangle(a, b, c).is_some());
+
+    let beside = Ray::new(Point3::new(1.5, 1.5, 3.0), -Vector3::unit_z());
+    assert_eq!(beside.intersect_triangle(a, b, c), None);
+    let receding = Ray::new(Point3::new(0.5, 0.25, 3.0), Vector3::unit_z());
+    assert_eq!(receding.intersect_triangle(a, b, c), None);
+    let parallel = Ray::new(Point3::new(-1.0, 0.5, 0.0), Vector3::unit_x());
+    assert_eq!(parallel.intersect_triangle(a, b, c), None);
+}
+
+#[test]
+fn mesh_picks_return_the_closest_triangle() {
+    let cube = cube();
+    // twice the size and two units back, so the front face is at z = 0
+    let model = Matrix4::from_translation(Vector3::new(0.0, 0.0, -2.0)) * Matrix4::from_scale(2.0);
+    let ray = Ray::new(Point3::new(0.5, 1.0, 10.0), -Vector3::unit_z());
+    let hit = pick_mesh(&ray, &cube, model).unwrap();
+    assert_relative_eq!(hit.distance, 10.0, epsilon = 1e-5);
+    assert_relative_eq!(hit.point, Point3::new(0.5, 1.0, 0.0), epsilon = 1e-5);
+    // the front face is made of the first two triangles
+    assert!(hit.triangle < 2, "{}", hit.triangle);
+
+    // the barycentrics rebuild the hit point from the triangle corners
+    let Some(Indices::U16(indices)) = &cube.indices else {
+        unreachable!()
+    };
+    let corners = &indices[3 * hit.triangle..3 * hit.triangle + 3];
+    let local = corners
+        .iter()
+        .zip(hit.barycentrics)
+        .fold(Vector3::zero(), |sum, (&i, weight)| {
+            sum + Vector3::from(cube.positions[i as usize]) * weight
+        });
+    assert_relative_eq!(hit.barycentrics.iter().sum::<f32>(), 1.0, epsilon = 1e-5);
+    assert_relative_eq!(
+        model.transform_point(Point3::from_vec(local)),
+        hit.point,
+        epsilon = 1e-5
+    );
+
+    // from inside the cube the far side is hit
+    let inside = Ray::new(Point3::new(0.0, 0.0, -2.0), Vector3::unit_x());
+    assert_relative_eq!(pick_mesh(&inside, &cube, model).unwrap().distance, 2.0);
+
+    let miss = Ray::new(Point3::new(2.5, 0.0, 10.0), -Vector3::unit_z());
+    assert_eq!(pick_mesh(&miss, &cube, model), None);
+
+    // meshes without indices are read three vertices at a time
+    let triangles = Mesh {
+        positions: vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, -1.0],
+            [1.0, 0.0, -1.0],
+            [0.0, 1.0, -1.0],
+        ],
+        ..Default::default()
+    };
+    let ray = Ray::new(Point3::new(0.2, 0.2, -5.0), Vector3::unit_z());
+    let hit = pick_mesh(&ray, &triangles, Matrix4::identity()).unwrap();
+    assert_eq!(hit.triangle, 1);
+    assert_relative_eq!(hit.distance, 4.0);
+}
+
+#[test]
+fn scene_picks_return_the_nearest_node() {
+    let mut graph = SceneGraph::new();
+    let near = graph.add_mesh(
+        "near",
+        Transform::from_translation(Vector3::new(0.0, 0.0, 2.0)),
+        None,
+        0,
+        None,
+    );
+    let far = graph.add_mesh("far", Transform::IDENTITY, None, 0, None);
+    let aside = graph.add_mesh(
+        "aside",
+        Transform::from_translation(Vector3::new(5.0, 0.0, 0.0)),
+        None,
+        0,
+        None,
+    );
+    let commands = graph.draw_commands();
+    let meshes = [cube()];
+
+    let ray = Ray::new(Point3::new(0.0, 0.0, 10.0), -Vector3::unit_z());
+    let (node, hit) = pick_draw_commands(&ray, &commands, &meshes).unwrap();
+    assert_eq!(node, near);
+    assert_relative_eq!(hit.distance, 7.0);
+
+    graph.node_mut(near).visible = false;
+    let commands = graph.draw_commands();
+    assert_eq!(pick_draw_commands(&ray, &commands, &meshes).unwrap().0, far);
+
+    let ray = Ray::new(Point3::new(5.0, 0.0, 10.0), -Vector3::unit_z());
+    assert_eq!(
+        pick_draw_commands(&ray, &commands, &meshes).unwrap().0,
+        aside
+    );
+    let ray = Ray::new(Point3::new(2.5, 0.0, 10.0), -Vector3::unit_z());
+    assert_eq!(pick_draw_commands(&ray, &commands, &meshes), None);
+}
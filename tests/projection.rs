@@ -0,0 +1,84 @@
+use cgmath::*;
+use std::f32::consts::PI;
+use wgpu_step_by_step::transforms::{self, Projection, OPENGL_TO_WGPU_MATRIX};
+
+// clip-space position of a view-space point after the perspective divide
+fn project(projection: &Projection, point: [f32; 3]) -> Vector3<f32> {
+    let clip = projection.matrix() * Vector4::new(point[0], point[1], point[2], 1.0);
+    clip.truncate() / clip.w
+}
+
+#[test]
+fn perspective_matches_cgmath() {
+    let projection = Projection::perspective(Deg(60.0), 1.5, 0.5, 50.0);
+    let expected = OPENGL_TO_WGPU_MATRIX * perspective(Deg(60.0), 1.5, 0.5, 50.0);
+    assert_relative_eq!(projection.matrix(), expected, epsilon = 1e-6);
+    assert_relative_eq!(
+        transforms::create_projection(1.5, true),
+        OPENGL_TO_WGPU_MATRIX * perspective(Rad(2.0 * PI / 5.0), 1.5, 0.1, 100.0),
+        epsilon = 1e-6
+    );
+    assert_eq!(projection.depth_clear_value(), 1.0);
+}
+
+#[test]
+fn depth_ranges() {
+    let standard = Projection::perspective(Deg(60.0), 1.0, 0.1, 100.0);
+    assert_relative_eq!(project(&standard, [0.0, 0.0, -0.1]).z, 0.0, epsilon = 1e-6);
+    assert_relative_eq!(
+        project(&standard, [0.0, 0.0, -100.0]).z,
+        1.0,
+        epsilon = 1e-6
+    );
+
+    let reverse = standard.with_reverse_z(true);
+    assert_relative_eq!(project(&reverse, [0.0, 0.0, -0.1]).z, 1.0, epsilon = 1e-6);
+    assert_relative_eq!(project(&reverse, [0.0, 0.0, -100.0]).z, 0.0, epsilon = 1e-6);
+    assert_eq!(reverse.depth_compare(), wgpu::CompareFunction::GreaterEqual);
+    assert_eq!(reverse.depth_clear_value(), 0.0);
+
+    let infinite = Projection::perspective(Deg(60.0), 1.0, 0.1, f32::INFINITY);
+    assert_relative_eq!(project(&infinite, [0.0, 0.0, -0.1]).z, 0.0, epsilon = 1e-6);
+    let far = project(&infinite, [0.0, 0.0, -1.0e6]).z;
+    assert!(far < 1.0 && far > 0.999, "{}", far);
+
+    let infinite_reverse = infinite.with_reverse_z(true);
+    assert_relative_eq!(
+        project(&infinite_reverse, [0.0, 0.0, -0.1]).z,
+        1.0,
+        epsilon = 1e-6
+    );
+    let far = project(&infinite_reverse, [0.0, 0.0, -1.0e6]).z;
+    assert!(far > 0.0 && far < 1.0e-6, "{}", far);
+}
+
+#[test]
+fn orthographic_follows_aspect() {
+    let mut projection = Projection::orthographic(6.0, 4.0 / 3.0, -1.0, 6.0);
+    let legacy = transforms::create_projection(4.0 / 3.0, false);
+    assert_relative_eq!(projection.matrix(), legacy, epsilon = 1e-6);
+
+    projection.resize(1600, 600);
+    let corner = project(&projection, [8.0, 3.0, -6.0]);
+    assert_relative_eq!(corner, Vector3::new(1.0, 1.0, 1.0), epsilon = 1e-6);
+    // zero sizes from minimised windows keep the previous aspect
+    projection.resize(0, 0);
+    assert_eq!(projection.aspect, 1600.0 / 600.0);
+
+    let reverse = projection.with_reverse_z(true);
+    assert_relative_eq!(project(&reverse, [0.0, 0.0, 1.0]).z, 1.0, epsilon = 1e-6);
+    assert_relative_eq!(project(&reverse, [0.0, 0.0, -6.0]).z, 0.0, epsilon = 1e-6);
+}
+
+#[test]
+fn off_axis_frustum() {
+    let projection = Projection::frustum(-0.1, 0.3, -0.1, 0.2, 0.5, 10.0);
+    let expected = OPENGL_TO_WGPU_MATRIX * frustum(-0.1, 0.3, -0.1, 0.2, 0.5, 10.0);
+    assert_relative_eq!(projection.matrix(), expected, epsilon = 1e-6);
+
+    // the near-plane corners land on the clip-space corners
+    let bottom_left = project(&projection, [-0.1, -0.1, -0.5]);
+    assert_relative_eq!(bottom_left, Vector3::new(-1.0, -1.0, 0.0), epsilon = 1e-6);
+    let top_right = project(&projection, [0.3, 0.2, -0.5]);
+    assert_relative_eq!(top_right, Vector3::new(1.0, 1.0, 0.0), epsilon = 1e-6);
+}
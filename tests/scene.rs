@@ -0,0 +1,187 @@
+use cgmath::*;
+use wgpu_step_by_step::culling::Frustum;
+use wgpu_step_by_step::geometry::gltf::GltfNode;
+use wgpu_step_by_step::geometry::{Aabb, GltfScene};
+use wgpu_step_by_step::scene::{NodeId, SceneGraph};
+use wgpu_step_by_step::transforms::{create_view, Projection, Transform};
+
+// sun -> earth -> moon, plus a separate mesh-less root
+fn solar_system() -> (SceneGraph, [NodeId; 4]) {
+    let mut graph = SceneGraph::new();
+    let sun = graph.add_mesh(
+        "sun",
+        Transform::from_euler([0.0, 1.0, 0.0], [0.0, 0.5, 0.0], [2.0, 2.0, 2.0]),
+        None,
+        0,
+        Some(0),
+    );
+    let earth = graph.add_mesh(
+        "earth",
+        Transform::from_euler([3.0, 0.0, 0.0], [0.2, 0.0, 0.0], [0.5, 0.5, 0.5]),
+        Some(sun),
+        1,
+        Some(1),
+    );
+    let moon = graph.add_mesh(
+        "moon",
+        Transform::from_translation(Vector3::new(0.0, 0.0, 2.0)),
+        Some(earth),
+        1,
+        None,
+    );
+    let camera_rig = graph.add("rig", Transform::IDENTITY, None);
+    (graph, [sun, earth, moon, camera_rig])
+}
+
+#[test]
+fn world_matrices_are_products_of_ancestors() {
+    let (mut graph, [sun, earth, moon, _]) = solar_system();
+    assert_eq!(graph.update(), 4);
+    let sun_world = graph.node(sun).local().matrix();
+    let earth_world = sun_world * graph.node(earth).local().matrix();
+    let moon_world = earth_world * graph.node(moon).local().matrix();
+    assert_relative_eq!(graph.node(sun).world(), sun_world, epsilon = 1e-6);
+    assert_relative_eq!(graph.node(earth).world(), earth_world, epsilon = 1e-6);
+    assert_relative_eq!(graph.node(moon).world(), moon_world, epsilon = 1e-6);
+}
+
+#[test]
+fn update_only_recomputes_dirty_subtrees() {
+    let (mut graph, [sun, earth, moon, rig]) = solar_system();
+    graph.update();
+    assert_eq!(graph.update(), 0);
+
+    graph.set_local(
+        earth,
+        Transform::from_translation(Vector3::new(4.0, 0.0, 0.0)),
+    );
+    assert_eq!(graph.update(), 2);
+    assert_relative_eq!(
+        graph.node(moon).world(),
+        graph.node(sun).world() * Matrix4::from_translation(Vector3::new(4.0, 0.0, 2.0)),
+        epsilon = 1e-5
+    );
+
+    graph.set_local(rig, Transform::from_scale(Vector3::new(2.0, 2.0, 2.0)));
+    graph.set_local(sun, Transform::IDENTITY);
+    assert_eq!(graph.update(), 4);
+}
+
+#[test]
+fn reparenting_keeps_the_local_transform() {
+    let (mut graph, [sun, _, moon, rig]) = solar_system();
+    graph.set_local(
+        rig,
+        Transform::from_translation(Vector3::new(0.0, -5.0, 0.0)),
+    );
+    graph.set_parent(moon, Some(rig));
+    graph.update();
+    assert_eq!(graph.node(moon).parent(), Some(rig));
+    assert_eq!(graph.node(rig).children(), &[moon]);
+    assert_relative_eq!(
+        graph.node(moon).world(),
+        Matrix4::from_translation(Vector3::new(0.0, -5.0, 2.0)),
+        epsilon = 1e-6
+    );
+
+    graph.set_parent(sun, Some(rig));
+    assert_eq!(graph.roots(), &[rig]);
+    graph.set_parent(moon, None);
+    assert_eq!(graph.roots(), &[rig, moon]);
+}
+
+#[test]
+#[should_panic(expected = "own descendant")]
+fn reparenting_under_a_descendant_panics() {
+    let (mut graph, [sun, _, moon, _]) = solar_system();
+    graph.set_parent(sun, Some(moon));
+}
+
+#[test]
+fn draw_commands_skip_hidden_subtrees() {
+    let (mut graph, [sun, earth, moon, _]) = solar_system();
+    let commands = graph.draw_commands();
+    let nodes: Vec<NodeId> = commands.iter().map(|c| c.node).collect();
+    assert_eq!(nodes, [sun, earth, moon]);
+    assert_eq!(commands[1].mesh, 1);
+    assert_eq!(commands[1].material, Some(1));
+    assert_eq!(commands[2].material, None);
+    assert_eq!(commands[2].world, graph.node(moon).world());
+
+    graph.node_mut(earth).visible = false;
+    let nodes: Vec<NodeId> = graph.draw_commands().iter().map(|c| c.node).collect();
+    assert_eq!(nodes, [sun]);
+    assert_eq!(graph.find("moon"), Some(moon));
+}
+
+#[test]
+fn visible_draw_commands_cull_meshes_outside_the_frustum() {
+    let (mut graph, [sun, earth, moon, _]) = solar_system();
+    let unit_cube = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+    let view = create_view(
+        Point3::new(0.0, 1.0, 20.0),
+        Point3::new(0.0, 1.0, 0.0),
+        Vector3::unit_y(),
+    );
+    let nodes = |graph: &mut SceneGraph, projection: Projection, bounds: &[Aabb]| {
+        let frustum = Frustum::from_matrix(projection.matrix() * view);
+        let commands = graph.visible_draw_commands(&frustum, bounds);
+        commands.iter().map(|c| c.node).collect::<Vec<_>>()
+    };
+
+    // six units wide around the sun; the earth and moon orbit more than five units out
+    let narrow = Projection::orthographic(4.0, 1.5, 0.1, 100.0);
+    assert_eq!(nodes(&mut graph, narrow, &[unit_cube; 2]), [sun]);
+    let wide = Projection::orthographic(20.0, 1.5, 0.1, 100.0);
+    assert_eq!(nodes(&mut graph, wide, &[unit_cube; 2]), [sun, earth, moon]);
+    // meshes without bounds are never culled
+    assert_eq!(nodes(&mut graph, narrow, &[unit_cube]), [sun, earth, moon]);
+}
+
+#[test]
+fn graph_from_gltf_matches_imported_world_matrices() {
+    let parent_local = Matrix4::from_translation(Vector3::new(1.0, 2.0, 3.0))
+        * Matrix4::from_angle_y(Deg(90.0))
+        * Matrix4::from_scale(2.0);
+    let child_local = Matrix4::from_translation(Vector3::new(0.0, 1.0, 0.0));
+    let node = |name: &str, parent, children, mesh, local: Matrix4<f32>, world| GltfNode {
+        name: Some(name.to_string()),
+        parent,
+        children,
+        mesh,
+        local,
+        world,
+    };
+    let scene = GltfScene {
+        meshes: Vec::new(),
+        materials: Vec::new(),
+        images: Vec::new(),
+        nodes: vec![
+            node("parent", None, vec![1], None, parent_local, parent_local),
+            node(
+                "child",
+                Some(0),
+                Vec::new(),
+                Some(0),
+                child_local,
+                parent_local * child_local,
+            ),
+            node(
+                "unused",
+                None,
+                Vec::new(),
+                Some(0),
+                child_local,
+                child_local,
+            ),
+        ],
+        roots: vec![0],
+    };
+
+    let mut graph = SceneGraph::from_gltf(&scene);
+    let commands = graph.draw_commands();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].node, NodeId(1));
+    assert_relative_eq!(commands[0].world, scene.nodes[1].world, epsilon = 1e-5);
+    assert!(!graph.node(NodeId(2)).visible);
+}
@@ -0,0 +1,335 @@
+use cgmath::*;
+use wgpu_step_by_step::capture;
+use wgpu_step_by_step::depth::DepthTarget;
+use wgpu_step_by_step::geometry::{plane_data, vertex_data, Mesh};
+use wgpu_step_by_step::lighting::shadow::{
+    cascade_splits, cascade_view_projection, frustum_corners, spot_view_projection, NO_SHADOW_LIGHT,
+};
+use wgpu_step_by_step::lighting::{
+    FrameUniform, Light, LightColor, PhongMaterial, PhongPipeline, ShadowMap, ShadowSettings,
+};
+use wgpu_step_by_step::transforms::{create_view, Projection};
+use wgpu_step_by_step::InitWgpu;
+
+// light clip space after the perspective divide
+fn project(matrix: Matrix4<f32>, point: Point3<f32>) -> Point3<f32> {
+    Point3::from_homogeneous(matrix * point.to_homogeneous())
+}
+
+fn inside(ndc: Point3<f32>) -> bool {
+    let eps = 1e-4;
+    ndc.x.abs() <= 1.0 + eps && ndc.y.abs() <= 1.0 + eps && (-eps..=1.0 + eps).contains(&ndc.z)
+}
+
+fn camera() -> (Matrix4<f32>, Projection) {
+    let view = create_view(
+        Point3::new(3.0, 2.0, 5.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Vector3::unit_y(),
+    );
+    (view, Projection::perspective(Deg(60.0), 1.5, 0.1, 100.0))
+}
+
+#[test]
+fn cascade_splits_blend_uniform_and_logarithmic() {
+    let uniform = cascade_splits(1.0, 81.0, 4, 0.0);
+    for (split, expected) in uniform.iter().zip([1.0, 21.0, 41.0, 61.0, 81.0]) {
+        assert_relative_eq!(*split, expected, epsilon = 1e-4);
+    }
+    let logarithmic = cascade_splits(1.0, 81.0, 4, 1.0);
+    for (split, expected) in logarithmic.iter().zip([1.0, 3.0, 9.0, 27.0, 81.0]) {
+        assert_relative_eq!(*split, expected, epsilon = 1e-3);
+    }
+    let practical = cascade_splits(0.1, 50.0, 3, 0.75);
+    assert_eq!(practical.len(), 4);
+    assert!(practical.windows(2).all(|pair| pair[0] < pair[1]));
+
+    // a near plane at zero has no logarithm, but still gives finite, ordered splits
+    for lambda in [0.0, 0.75, 1.0] {
+        let splits = cascade_splits(0.0, 50.0, 4, lambda);
+        assert_eq!(splits[0], 0.0);
+        assert_relative_eq!(splits[4], 50.0, epsilon = 1e-4);
+        assert!(splits.iter().all(|split| split.is_finite()));
+        assert!(splits.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+}
+
+#[test]
+fn frustum_corners_lie_on_the_near_and_far_planes() {
+    let (view, projection) = camera();
+    let corners = frustum_corners(projection.matrix() * view);
+    let eye = Point3::new(3.0, 2.0, 5.0);
+    let forward = (Point3::origin() - eye).normalize();
+    for (i, corner) in corners.iter().enumerate() {
+        let depth = (corner - eye).dot(forward);
+        let expected = if i < 4 { 0.1 } else { 100.0 };
+        assert_relative_eq!(depth, expected, max_relative = 1e-3);
+    }
+}
+
+#[test]
+fn cascades_cover_their_slice_and_the_casters_towards_the_light() {
+    let (view, projection) = camera();
+    let direction = Vector3::new(-0.4, -1.0, -0.6);
+    let splits = cascade_splits(projection.near, 30.0, 4, 0.75);
+    for range in splits.windows(2) {
+        let slice = Projection {
+            near: range[0],
+            far: range[1],
+            ..projection
+        };
+        let corners = frustum_corners(slice.matrix() * view);
+        let matrix = cascade_view_projection(direction, &corners, 20.0, 1024);
+        for corner in corners {
+            assert!(inside(project(matrix, corner)), "{:?}", corner);
+            // a caster up to 20 units towards the light is still in the map
+            let caster = corner - direction.normalize() * 19.9;
+            let ndc = project(matrix, caster);
+            assert!(inside(ndc), "{:?}", ndc);
+        }
+    }
+}
+
+#[test]
+fn cascades_move_in_whole_texels() {
+    let (view, projection) = camera();
+    let direction = Vector3::new(-0.4, -1.0, -0.6);
+    let corners = frustum_corners(projection.matrix() * view);
+    let moved = corners.map(|corner| corner + Vector3::new(0.37, 0.0, -0.21));
+    let a = cascade_view_projection(direction, &corners, 20.0, 512);
+    let b = cascade_view_projection(direction, &moved, 20.0, 512);
+
+    // a fixed point shifts across the map by a whole number of texels
+    let point = Point3::new(1.0, 0.0, 2.0);
+    let shift = (project(b, point) - project(a, point)) * 512.0 / 2.0;
+    assert_relative_eq!(shift.x, shift.x.round(), epsilon = 1e-2);
+    assert_relative_eq!(shift.y, shift.y.round(), epsilon = 1e-2);
+}
+
+#[test]
+fn spot_shadows_cover_the_cone() {
+    let position = Point3::new(1.0, 4.0, 0.0);
+    let direction = Vector3::new(0.0, -1.0, 0.0);
+    let matrix = spot_view_projection(position, direction, Deg(30.0).into(), 10.0);
+
+    let axis = project(matrix, Point3::new(1.0, 0.0, 0.0));
+    assert_relative_eq!(axis.x, 0.0, epsilon = 1e-5);
+    assert_relative_eq!(axis.y, 0.0, epsilon = 1e-5);
+    // the edge of the cone 4 units down
+    let edge = Point3::new(1.0 + 4.0 * Deg(29.9).tan(), 0.0, 0.0);
+    assert!(inside(project(matrix, edge)));
+    let outside = Point3::new(1.0 + 4.0 * Deg(31.0).tan(), 0.0, 0.0);
+    assert!(!inside(project(matrix, outside)));
+    // beyond the range
+    assert!(!inside(project(matrix, Point3::new(1.0, -6.5, 0.0))));
+}
+
+fn init() -> InitWgpu<'static> {
+    pollster::block_on(InitWgpu::init_headless(
+        64,
+        64,
+        wgpu::TextureFormat::Rgba8Unorm,
+    ))
+}
+
+fn sun() -> Light {
+    Light::Directional {
+        direction: Vector3::new(1.0, -1.0, 0.0),
+        color: LightColor::new([1.0, 1.0, 1.0], 0.1),
+    }
+}
+
+#[test]
+fn shadow_maps_need_room_for_the_snapping_margin() {
+    let init = init();
+    for size in [0, 1, 2] {
+        let settings = ShadowSettings {
+            size,
+            ..Default::default()
+        };
+        assert!(ShadowMap::new(&init.device, settings).is_err(), "{}", size);
+    }
+    let too_large = ShadowSettings {
+        size: init.device.limits().max_texture_dimension_2d + 1,
+        ..Default::default()
+    };
+    assert!(ShadowMap::new(&init.device, too_large).is_err());
+    let smallest = ShadowSettings {
+        size: 3,
+        ..Default::default()
+    };
+    assert!(ShadowMap::new(&init.device, smallest).is_ok());
+}
+
+#[test]
+fn update_chooses_layers_by_light_kind() {
+    let init = init();
+    let mut shadow_map = ShadowMap::new(
+        &init.device,
+        ShadowSettings {
+            size: 256,
+            cascades: 3,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let (view, projection) = camera();
+    let lights = [
+        sun(),
+        Light::Point {
+            position: Point3::new(0.0, 2.0, 0.0),
+            color: LightColor::new([1.0; 3], 0.0),
+            attenuation: Default::default(),
+        },
+        Light::Spot {
+            position: Point3::new(0.0, 2.0, 0.0),
+            direction: Vector3::new(0.0, -1.0, 0.0),
+            inner_angle: Deg(20.0).into(),
+            outer_angle: Deg(30.0).into(),
+            color: LightColor::new([1.0; 3], 0.0),
+            attenuation: Default::default(),
+        },
+    ];
+    let expected = [
+        (Some(0), 0, 3),
+        (Some(1), NO_SHADOW_LIGHT, 0),
+        (Some(2), 2, 1),
+    ];
+    for (light, index, layers) in expected {
+        shadow_map.update(&init.queue, &lights, light, view, &projection);
+        assert_eq!(shadow_map.uniform().light, index);
+        assert_eq!(shadow_map.layer_count(), layers);
+    }
+    shadow_map.update(&init.queue, &lights, None, view, &projection);
+    assert_eq!(shadow_map.uniform().light, NO_SHADOW_LIGHT);
+    assert_eq!(shadow_map.uniform().texel_size, 1.0 / 256.0);
+}
+
+// a floor seen from straight above with a small cube floating one unit over its centre,
+// lit from the upper left so the shadow falls one unit to the right
+fn render_floor(init: &InitWgpu, light: Option<usize>, pcf_kernel: u32) -> image::RgbaImage {
+    let (pos, _col, _uv, normal) = vertex_data::cube_data();
+    let to_f32 = |v: &[i8; 3]| [v[0] as f32, v[1] as f32, v[2] as f32];
+    let cube = Mesh {
+        positions: pos.iter().map(to_f32).collect(),
+        normals: Some(normal.iter().map(to_f32).collect()),
+        ..Default::default()
+    };
+    let floor = Mesh {
+        uvs: None,
+        ..plane_data(4.0, 4.0, 1, 1).into()
+    };
+
+    let format = wgpu::TextureFormat::Depth32Float;
+    let depth = DepthTarget::new(&init.device, 64, 64, format);
+    let mut shadow_map = ShadowMap::new(
+        &init.device,
+        ShadowSettings {
+            size: 512,
+            pcf_kernel,
+            distance: 10.0,
+            ..Default::default()
+        },
+    )
+    .unwrap();
+    let shadow_pipeline = shadow_map.create_pipeline(&init.device, &cube.layout(0));
+    let pipeline = PhongPipeline::new(
+        &init.device,
+        init.config.format,
+        Some(format),
+        1,
+        &cube.layout(0),
+        &shadow_map,
+    );
+    let material = PhongMaterial {
+        specular: [0.0; 3],
+        ..Default::default()
+    };
+    let cube_model =
+        Matrix4::from_translation(Vector3::new(0.0, 1.0, 0.0)) * Matrix4::from_scale(0.25);
+    let objects = [
+        (
+            cube.upload(&init.device),
+            pipeline.create_object(&init.device, cube_model, &material),
+        ),
+        (
+            floor.upload(&init.device),
+            pipeline.create_object(&init.device, Matrix4::identity(), &material),
+        ),
+    ];
+
+    let eye = Point3::new(0.0, 5.0, 0.0);
+    let view = create_view(eye, Point3::origin(), -Vector3::unit_z());
+    let projection = Projection::orthographic(4.0, 1.0, 0.1, 10.0);
+    let lights = [sun()];
+    pipeline.set_frame(
+        &init.queue,
+        &FrameUniform::new(projection.matrix() * view, eye, &lights),
+    );
+    shadow_map.update(&init.queue, &lights, light, view, &projection);
+
+    let frame = init.get_current_frame().unwrap();
+    let frame_view = frame
+        .texture()
+        .create_view(&wgpu::TextureViewDescriptor::default());
+    let mut encoder = init
+        .device
+        .create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
+    shadow_map.render(&mut encoder, |render_pass| {
+        for (mesh, object) in &objects {
+            shadow_pipeline.draw(render_pass, &object.bind_group, mesh);
+        }
+    });
+    {
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: None,
+            timestamp_writes: None,
+            occlusion_query_set: None,
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &frame_view,
+                resolve_target: None,
+                ops: wgpu::Operations::default(),
+            })],
+            depth_stencil_attachment: Some(depth.attachment()),
+        });
+        for (mesh, object) in &objects {
+            pipeline.draw(&mut render_pass, object, mesh);
+        }
+    }
+    init.queue.submit(Some(encoder.finish()));
+    capture::read_texture(&init.device, &init.queue, frame.texture()).unwrap()
+}
+
+#[test]
+fn casters_shadow_the_floor() {
+    let init = init();
+    let lit = render_floor(&init, None, 1);
+    let shadowed = render_floor(&init, Some(0), 1);
+    // 16 texels per unit, with x to the right and the shadow centred at x = 1
+    let (shadow, open) = ((48, 32), (16, 32));
+    assert_eq!(
+        lit.get_pixel(shadow.0, shadow.1),
+        lit.get_pixel(open.0, open.1)
+    );
+    assert_eq!(
+        shadowed.get_pixel(open.0, open.1),
+        lit.get_pixel(open.0, open.1)
+    );
+    // only the ambient term is left
+    let dark = shadowed.get_pixel(shadow.0, shadow.1)[0];
+    assert!(dark < lit.get_pixel(shadow.0, shadow.1)[0] / 4, "{}", dark);
+    // the cube top is lit either way
+    assert_eq!(shadowed.get_pixel(32, 32), lit.get_pixel(32, 32));
+
+    // a wider kernel blurs the shadow edge over more pixels
+    let soft = render_floor(&init, Some(0), 7);
+    let partial = |image: &image::RgbaImage| {
+        (40..58)
+            .filter(|&x| {
+                let value = image.get_pixel(x, 32)[0];
+                value > dark + 2 && value + 2 < lit.get_pixel(x, 32)[0]
+            })
+            .count()
+    };
+    assert!(partial(&soft) > partial(&shadowed));
+}
@@ -0,0 +1,214 @@
+use image::{ImageBuffer, Rgb, Rgba};
+use std::path::PathBuf;
+use wgpu_step_by_step::capture;
+use wgpu_step_by_step::texture::mipmap::{self, MipmapGenerator};
+use wgpu_step_by_step::texture::{ColorSpace, Mipmaps, SamplerOptions, Texture, TextureData};
+use wgpu_step_by_step::InitWgpu;
+
+fn temp_dir() -> PathBuf {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("texture");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn gradient() -> image::RgbaImage {
+    ImageBuffer::from_fn(8, 4, |x, y| {
+        Rgba([(x * 32) as u8, (y * 64) as u8, 200, (255 - x * 16) as u8])
+    })
+}
+
+#[test]
+fn png_picks_srgb_or_linear_rgba8() {
+    let path = temp_dir().join("gradient.png");
+    gradient().save(&path).unwrap();
+
+    let srgb = TextureData::open(&path, ColorSpace::Srgb).unwrap();
+    assert_eq!((srgb.width, srgb.height), (8, 4));
+    assert_eq!(srgb.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+    assert_eq!(srgb.bytes, gradient().into_raw());
+    assert_eq!(srgb.bytes_per_pixel(), 4);
+
+    let linear = TextureData::open(&path, ColorSpace::Linear).unwrap();
+    assert_eq!(linear.format, wgpu::TextureFormat::Rgba8Unorm);
+    assert_eq!(linear.bytes, srgb.bytes);
+}
+
+#[test]
+fn jpeg_decodes_to_opaque_rgba8() {
+    let path = temp_dir().join("flat.jpg");
+    let image: image::RgbImage = ImageBuffer::from_pixel(16, 16, Rgb([200, 100, 50]));
+    image.save(&path).unwrap();
+
+    let data = TextureData::open(&path, ColorSpace::Srgb).unwrap();
+    assert_eq!(data.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+    assert_eq!(data.bytes.len(), 16 * 16 * 4);
+    for pixel in data.bytes.chunks_exact(4) {
+        assert!(pixel[0].abs_diff(200) <= 3 && pixel[1].abs_diff(100) <= 3);
+        assert_eq!(pixel[3], 255);
+    }
+}
+
+#[test]
+fn hdr_keeps_values_above_one_as_half_floats() {
+    let path = temp_dir().join("bright.hdr");
+    let pixels = vec![Rgb([4.0f32, 0.5, 0.25]); 4 * 2];
+    let file = std::fs::File::create(&path).unwrap();
+    image::codecs::hdr::HdrEncoder::new(file)
+        .encode(&pixels, 4, 2)
+        .unwrap();
+
+    // float data is linear, whatever the requested color space
+    let data = TextureData::open(&path, ColorSpace::Srgb).unwrap();
+    assert_eq!(data.format, wgpu::TextureFormat::Rgba16Float);
+    assert_eq!(data.bytes_per_pixel(), 8);
+    let halves: &[u16] = bytemuck::cast_slice(&data.bytes);
+    let first: Vec<f32> = halves[..4]
+        .iter()
+        .map(|&h| half::f16::from_bits(h).to_f32())
+        .collect();
+    assert_eq!(first, [4.0, 0.5, 0.25, 1.0]);
+}
+
+#[test]
+fn sixteen_bit_images_stay_wide_only_when_linear() {
+    let image = image::DynamicImage::ImageRgba16(ImageBuffer::from_pixel(
+        2,
+        2,
+        Rgba([65535, 32768, 0, 65535]),
+    ));
+    let linear = TextureData::from_image(&image, ColorSpace::Linear);
+    assert_eq!(linear.format, wgpu::TextureFormat::Rgba16Float);
+    let srgb = TextureData::from_image(&image, ColorSpace::Srgb);
+    assert_eq!(srgb.format, wgpu::TextureFormat::Rgba8UnormSrgb);
+    assert_eq!(&srgb.bytes[..4], &[255, 128, 0, 255]);
+}
+
+#[test]
+fn missing_file_names_the_path() {
+    let err = TextureData::open("no/such/texture.png", ColorSpace::Srgb).unwrap_err();
+    assert!(format!("{:#}", err).contains("no/such/texture.png"));
+}
+
+#[test]
+fn uploaded_pixels_read_back_unchanged() {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        4,
+        4,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let data = TextureData::rgba8(8, 4, gradient().into_raw(), ColorSpace::Linear);
+    let texture = Texture::from_data(
+        &init.device,
+        &init.queue,
+        &data,
+        Some("Gradient"),
+        &SamplerOptions::nearest(wgpu::AddressMode::ClampToEdge),
+        Mipmaps::None,
+    );
+    let layout = Texture::bind_group_layout(&init.device);
+    let _bind_group = texture.bind_group(&init.device, &layout);
+
+    let pixels = capture::read_texture(&init.device, &init.queue, &texture.texture).unwrap();
+    assert_eq!(pixels, gradient());
+}
+
+fn noise(width: u32, height: u32, color_space: ColorSpace) -> TextureData {
+    let mut state = 7u32;
+    let bytes = (0..width * height * 4)
+        .map(|_| {
+            state = state.wrapping_mul(1103515245).wrapping_add(12345);
+            (state >> 16) as u8
+        })
+        .collect();
+    TextureData::rgba8(width, height, bytes, color_space)
+}
+
+#[test]
+fn cpu_mip_chain_halves_down_to_one_texel() {
+    assert_eq!(mipmap::mip_level_count(64, 16), 7);
+    assert_eq!(mipmap::mip_level_count(5, 3), 3);
+    assert_eq!(mipmap::mip_level_size(5, 3, 1), (2, 1));
+
+    let data = TextureData::rgba8(
+        2,
+        2,
+        vec![
+            0, 0, 0, 0, 255, 0, 0, 255, 0, 255, 0, 255, 255, 255, 255, 255,
+        ],
+        ColorSpace::Linear,
+    );
+    let levels = mipmap::generate_mip_levels(&data);
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0], data);
+    assert_eq!(levels[1].bytes, [128, 128, 64, 191]);
+
+    // sRGB colors are averaged as light, so black and white give a light grey
+    let srgb = TextureData::rgba8(
+        2,
+        1,
+        vec![0, 0, 0, 255, 255, 255, 255, 255],
+        ColorSpace::Srgb,
+    );
+    assert_eq!(
+        mipmap::generate_mip_levels(&srgb)[1].bytes,
+        [188, 188, 188, 255]
+    );
+
+    // odd sizes clamp at the edge
+    let sizes: Vec<_> = mipmap::generate_mip_levels(&noise(5, 3, ColorSpace::Linear))
+        .iter()
+        .map(|level| (level.width, level.height, level.bytes.len()))
+        .collect();
+    assert_eq!(sizes, [(5, 3, 60), (2, 1, 8), (1, 1, 4)]);
+}
+
+#[test]
+fn gpu_mip_levels_match_the_cpu_fallback() {
+    let init = pollster::block_on(InitWgpu::init_headless(
+        4,
+        4,
+        wgpu::TextureFormat::Rgba8UnormSrgb,
+    ));
+    let generator = MipmapGenerator::new(&init.device);
+    let sampler = SamplerOptions::default().with_anisotropy(8);
+    for color_space in [ColorSpace::Linear, ColorSpace::Srgb] {
+        let data = noise(20, 12, color_space);
+        let gpu = Texture::from_data(
+            &init.device,
+            &init.queue,
+            &data,
+            None,
+            &sampler,
+            Mipmaps::Gpu(&generator),
+        );
+        let cpu = Texture::from_data(
+            &init.device,
+            &init.queue,
+            &data,
+            None,
+            &sampler,
+            Mipmaps::Cpu,
+        );
+        assert_eq!(gpu.texture.mip_level_count(), 5);
+        assert_eq!(cpu.texture.mip_level_count(), 5);
+
+        for (level, expected) in mipmap::generate_mip_levels(&data).iter().enumerate() {
+            let level = level as u32;
+            let rendered =
+                capture::read_texture_level(&init.device, &init.queue, &gpu.texture, level)
+                    .unwrap();
+            let uploaded =
+                capture::read_texture_level(&init.device, &init.queue, &cpu.texture, level)
+                    .unwrap();
+            assert_eq!(rendered.dimensions(), (expected.width, expected.height));
+            assert_eq!(uploaded.as_raw(), &expected.bytes);
+            assert_eq!(
+                rendered.as_raw(),
+                &expected.bytes,
+                "{:?} level {}",
+                color_space,
+                level
+            );
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use cgmath::*;
+use wgpu_step_by_step::transforms::{self, Transform};
+
+fn euler_matrix(translation: [f32; 3], rotation: [f32; 3], scaling: [f32; 3]) -> Matrix4<f32> {
+    Matrix4::from_translation(translation.into())
+        * Matrix4::from_angle_z(Rad(rotation[2]))
+        * Matrix4::from_angle_y(Rad(rotation[1]))
+        * Matrix4::from_angle_x(Rad(rotation[0]))
+        * Matrix4::from_nonuniform_scale(scaling[0], scaling[1], scaling[2])
+}
+
+#[test]
+fn euler_constructor_matches_matrix_composition() {
+    let (t, r, s) = ([1.0, -2.0, 0.5], [0.3, -1.2, 2.5], [1.0, 2.0, 3.0]);
+    let expected = euler_matrix(t, r, s);
+    assert_relative_eq!(
+        Transform::from_euler(t, r, s).matrix(),
+        expected,
+        epsilon = 1e-5
+    );
+    assert_relative_eq!(
+        transforms::create_transforms(t, r, s),
+        expected,
+        epsilon = 1e-5
+    );
+}
+
+#[test]
+fn matrix_round_trip() {
+    let transform = Transform::from_euler([3.0, 1.0, -4.0], [0.1, 0.7, -2.0], [0.5, 2.0, 1.5]);
+    let back = Transform::from_matrix(transform.matrix());
+    assert_relative_eq!(back.translation, transform.translation, epsilon = 1e-5);
+    assert_relative_eq!(back.scale, transform.scale, epsilon = 1e-5);
+    assert_relative_eq!(back.matrix(), transform.matrix(), epsilon = 1e-5);
+
+    // a mirror survives as a negative scale
+    let mirrored = Matrix4::from_nonuniform_scale(1.0, -1.0, 1.0) * transform.matrix();
+    assert_relative_eq!(Transform::from(mirrored).matrix(), mirrored, epsilon = 1e-5);
+}
+
+#[test]
+fn composition_and_inverse() {
+    let parent = Transform {
+        scale: Vector3::new(2.0, 2.0, 2.0),
+        ..Transform::from_euler([1.0, 2.0, 3.0], [0.5, 0.0, 1.0], [1.0; 3])
+    };
+    let child = Transform::from_euler([0.0, 1.0, 0.0], [0.0, 0.8, 0.0], [1.0, 3.0, 1.0]);
+    assert_relative_eq!(
+        (parent * child).matrix(),
+        parent.matrix() * child.matrix(),
+        epsilon = 1e-5
+    );
+
+    let point = Point3::new(0.3, -0.2, 5.0);
+    assert_relative_eq!(
+        parent.transform_point(point),
+        Point3::from_homogeneous(parent.matrix() * point.to_homogeneous()),
+        epsilon = 1e-5
+    );
+    assert_relative_eq!(
+        parent.inverse().matrix(),
+        parent.matrix().invert().unwrap(),
+        epsilon = 1e-5
+    );
+    assert_relative_eq!(
+        (parent.inverse() * parent).matrix(),
+        Matrix4::identity(),
+        epsilon = 1e-5
+    );
+}
+
+#[test]
+fn interpolation() {
+    let a = Transform::IDENTITY;
+    let b = Transform {
+        translation: Vector3::new(2.0, 0.0, 0.0),
+        rotation: Quaternion::from_angle_y(Deg(90.0)),
+        scale: Vector3::new(3.0, 3.0, 3.0),
+    };
+    let half = a.slerp(&b, 0.5);
+    assert_relative_eq!(half.translation, Vector3::new(1.0, 0.0, 0.0));
+    assert_relative_eq!(half.scale, Vector3::new(2.0, 2.0, 2.0));
+    assert_relative_eq!(
+        half.rotation,
+        Quaternion::from_angle_y(Deg(45.0)),
+        epsilon = 1e-6
+    );
+
+    // both take the short way round even when the quaternions have opposite signs
+    let flipped = Transform {
+        rotation: -b.rotation,
+        ..b
+    };
+    let half = a.slerp(&flipped, 0.5);
+    assert_relative_eq!(half.matrix(), a.slerp(&b, 0.5).matrix(), epsilon = 1e-6);
+    let half = a.lerp(&flipped, 0.5);
+    assert_relative_eq!(half.rotation.magnitude(), 1.0, epsilon = 1e-6);
+    assert_relative_eq!(
+        half.rotation,
+        Quaternion::from_angle_y(Deg(45.0)),
+        epsilon = 1e-6
+    );
+
+    assert_eq!(a.slerp(&b, 0.0), a);
+    assert_relative_eq!(a.slerp(&b, 1.0).matrix(), b.matrix(), epsilon = 1e-6);
+}
+
+#[test]
+fn look_at_inverts_the_view_matrix() {
+    let eye = Point3::new(3.0, 1.5, 3.0);
+    let transform = Transform::look_at(eye, Point3::origin(), Vector3::unit_y());
+    let view = transforms::create_view(eye, Point3::origin(), Vector3::unit_y());
+    assert_relative_eq!(transform.inverse().matrix(), view, epsilon = 1e-5);
+    assert_relative_eq!(
+        transform.transform_vector(-Vector3::unit_z()),
+        (Point3::origin() - eye).normalize(),
+        epsilon = 1e-5
+    );
+}
@@ -0,0 +1,172 @@
+use wgpu_step_by_step::geometry::{validate_mesh, Attribute, Indices, Mesh, MeshIssue};
+
+// a closed tetrahedron with outward-facing, counter-clockwise triangles
+fn tetrahedron(indices: Vec<u16>) -> Mesh {
+    Mesh {
+        positions: vec![
+            [0.0, 0.0, 0.0],
+            [1.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 0.0, 1.0],
+        ],
+        indices: Some(Indices::U16(indices)),
+        ..Default::default()
+    }
+}
+
+const TETRAHEDRON: [u16; 12] = [0, 2, 1, 0, 1, 3, 0, 3, 2, 1, 2, 3];
+
+fn issues(mesh: &Mesh) -> Vec<MeshIssue> {
+    validate_mesh(mesh).issues
+}
+
+#[test]
+fn closed_outward_mesh_is_valid() {
+    let report = validate_mesh(&tetrahedron(TETRAHEDRON.to_vec()));
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!((report.vertex_count, report.triangle_count), (4, 4));
+}
+
+#[test]
+fn reversed_mesh_is_inside_out() {
+    let reversed = TETRAHEDRON.chunks(3).flat_map(|t| [t[0], t[2], t[1]]);
+    let mesh = tetrahedron(reversed.collect());
+    assert_eq!(issues(&mesh), vec![MeshIssue::InsideOut]);
+}
+
+#[test]
+fn flipped_triangle_breaks_edge_winding() {
+    let mut indices = TETRAHEDRON.to_vec();
+    indices.swap(10, 11);
+    let found = issues(&tetrahedron(indices));
+    assert!(found.contains(&MeshIssue::InconsistentWinding {
+        triangle: 3,
+        other: 0
+    }));
+    assert!(!found.contains(&MeshIssue::InsideOut));
+}
+
+#[test]
+fn index_problems_are_reported() {
+    let mut indices = TETRAHEDRON.to_vec();
+    indices.extend([3, 4]);
+    let found = issues(&tetrahedron(indices));
+    assert_eq!(
+        found,
+        vec![
+            MeshIssue::IncompleteTriangle { count: 14 },
+            MeshIssue::IndexOutOfRange {
+                position: 13,
+                index: 4,
+                vertex_count: 4
+            },
+        ]
+    );
+}
+
+#[test]
+fn attribute_counts_must_match_positions() {
+    let mut mesh = tetrahedron(TETRAHEDRON.to_vec());
+    mesh.uvs = Some(vec![[0.0, 0.0]; 4]);
+    mesh.colors = Some(vec![[1.0; 4]; 3]);
+    assert_eq!(
+        issues(&mesh),
+        vec![MeshIssue::AttributeCount {
+            attribute: Attribute::Color,
+            expected: 4,
+            found: 3
+        }]
+    );
+}
+
+#[test]
+fn degenerate_and_duplicate_triangles() {
+    // the duplicate is the first triangle seen from the back
+    let mut mesh = tetrahedron(vec![0, 2, 1, 0, 0, 1, 0, 1, 2, 0, 1, 4]);
+    mesh.positions.push([2.0, 0.0, 0.0]);
+    assert_eq!(
+        issues(&mesh),
+        vec![
+            MeshIssue::DegenerateTriangle { triangle: 1 },
+            MeshIssue::DuplicateTriangle {
+                triangle: 2,
+                first: 0
+            },
+            MeshIssue::DegenerateTriangle { triangle: 3 },
+        ]
+    );
+}
+
+#[test]
+fn duplicate_vertices_compare_every_attribute() {
+    let mut mesh = tetrahedron(TETRAHEDRON.to_vec());
+    mesh.positions.push([0.0, 0.0, -0.0]);
+    assert_eq!(
+        issues(&mesh),
+        vec![MeshIssue::DuplicateVertex {
+            vertex: 4,
+            first: 0
+        }]
+    );
+
+    // the same position with another color is a separate vertex, as on a hard edge
+    mesh.colors = Some(vec![[1.0; 4], [1.0; 4], [1.0; 4], [1.0; 4], [0.0; 4]]);
+    assert!(validate_mesh(&mesh).is_valid());
+}
+
+#[test]
+fn winding_is_checked_against_normals() {
+    let mut mesh = Mesh {
+        positions: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+        normals: Some(vec![[0.0, 0.0, 1.0]; 3]),
+        ..Default::default()
+    };
+    assert!(validate_mesh(&mesh).is_valid());
+
+    mesh.normals = Some(vec![[0.0, 0.0, -1.0]; 3]);
+    assert_eq!(
+        issues(&mesh),
+        vec![MeshIssue::WindingAgainstNormals { triangle: 0 }]
+    );
+}
+
+// the indexed cube as it shipped before it was fixed: vertex g repeated vertex a and
+// the front face had a stray index
+#[test]
+fn broken_cube_is_rejected() {
+    let positions = [
+        [-1, -1, 1],
+        [-1, 1, 1],
+        [1, 1, 1],
+        [-1, 1, -1],
+        [-1, -1, -1],
+        [1, -1, -1],
+        [-1, -1, 1],
+        [1, 1, -1],
+    ];
+    let indices = vec![
+        0, 1, 2, 2, 3, 3, 0, 1, 5, 6, 6, 2, 1, 4, 7, 6, 6, 5, 4, 0, 3, 7, 7, 4, 0, 3, 2, 6, 6, 7,
+        3, 0, 4, 5, 5, 1, 0,
+    ];
+    let mesh = Mesh {
+        positions: positions
+            .iter()
+            .map(|p: &[i8; 3]| p.map(f32::from))
+            .collect(),
+        indices: Some(Indices::U16(indices)),
+        ..Default::default()
+    };
+    let report = validate_mesh(&mesh);
+    assert_eq!(report.triangle_count, 12);
+    assert!(report
+        .issues
+        .contains(&MeshIssue::IncompleteTriangle { count: 37 }));
+    assert!(report.issues.contains(&MeshIssue::DuplicateVertex {
+        vertex: 6,
+        first: 0
+    }));
+    assert!(report
+        .issues
+        .iter()
+        .any(|issue| matches!(issue, MeshIssue::DegenerateTriangle { .. })));
+}
@@ -0,0 +1,168 @@
+use wgpu_step_by_step::geometry::*;
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+// checks shared by every generator: a valid mesh with unit normals and UVs in [0, 1]
+fn check_mesh(name: &str, data: &MeshData) {
+    let report = validate_mesh(&Mesh::from(data.clone()));
+    assert!(report.is_valid(), "{}: {}", name, report);
+    assert!(!data.positions.is_empty(), "{}: no vertices", name);
+    for normal in &data.normals {
+        assert!(
+            (dot(*normal, *normal) - 1.0).abs() < 1e-4,
+            "{}: normal {:?}",
+            name,
+            normal
+        );
+    }
+    for uv in &data.uvs {
+        assert!(
+            uv.iter().all(|c| (-1e-6..=1.0 + 1e-6).contains(c)),
+            "{}: uv {:?}",
+            name,
+            uv
+        );
+    }
+}
+
+fn to_f32<const N: usize>(values: &[[i8; N]]) -> Vec<[f32; N]> {
+    values.iter().map(|v| v.map(f32::from)).collect()
+}
+
+#[test]
+fn cube() {
+    let (positions, colors, uvs, normals) = cube_data();
+    let mesh = Mesh {
+        positions: to_f32(&positions),
+        normals: Some(to_f32(&normals)),
+        uvs: Some(to_f32(&uvs)),
+        colors: Some(
+            to_f32(&colors)
+                .iter()
+                .map(|c| [c[0], c[1], c[2], 1.0])
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    let report = validate_mesh(&mesh);
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.triangle_count, 12);
+    assert!(uvs.iter().flatten().all(|&c| c == 0 || c == 1));
+    // one color per face
+    for face in colors.chunks(6) {
+        assert!(face.iter().all(|c| *c == face[0]));
+    }
+}
+
+#[test]
+fn cube_index() {
+    let (positions, colors, indices) = cube_data_index();
+    let mesh = Mesh {
+        positions: to_f32(&positions),
+        colors: Some(
+            to_f32(&colors)
+                .iter()
+                .map(|c| [c[0], c[1], c[2], 1.0])
+                .collect(),
+        ),
+        indices: Some(Indices::U16(indices)),
+        ..Default::default()
+    };
+    let report = validate_mesh(&mesh);
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!((report.vertex_count, report.triangle_count), (8, 12));
+    // the colors map the corners of the cube onto the corners of the RGB cube
+    for (p, c) in positions.iter().zip(&colors) {
+        assert_eq!(p.map(|x| (x + 1) / 2), *c);
+    }
+}
+
+#[test]
+fn uv_sphere() {
+    let data = uv_sphere_data(2.0, 16, 8);
+    check_mesh("uv_sphere", &data);
+    assert_eq!(data.positions.len(), 17 * 9);
+    // two pole rows contribute one triangle per sector, the others two
+    assert_eq!(data.indices.len() / 3, 16 * (2 * 8 - 2));
+    for p in &data.positions {
+        assert!((dot(*p, *p).sqrt() - 2.0).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn icosphere() {
+    for subdivisions in 0..3 {
+        let data = icosphere_data(1.5, subdivisions);
+        check_mesh("icosphere", &data);
+        assert_eq!(data.indices.len() / 3, 20 * 4usize.pow(subdivisions));
+        // every edge is shared, so V - E + F = 2
+        assert_eq!(data.positions.len(), 10 * 4usize.pow(subdivisions) + 2);
+        for p in &data.positions {
+            assert!((dot(*p, *p).sqrt() - 1.5).abs() < 1e-4);
+        }
+    }
+}
+
+#[test]
+fn cylinder() {
+    let data = cylinder_data(1.0, 2.0, 12, 3);
+    check_mesh("cylinder", &data);
+    assert!(data.positions.iter().all(|p| p[1].abs() <= 1.0 + 1e-6));
+    assert_eq!(data.indices.len() / 3, 12 * 3 * 2 + 2 * 12);
+}
+
+#[test]
+fn cone() {
+    let data = cone_data(1.0, 2.0, 12, 2);
+    check_mesh("cone", &data);
+    assert_eq!(data.indices.len() / 3, 12 * (2 * 2 - 1) + 12);
+    // the side normals lean upwards
+    assert!(data.normals[0][1] > 0.0);
+}
+
+#[test]
+fn torus() {
+    let data = torus_data(2.0, 0.5, 24, 12);
+    check_mesh("torus", &data);
+    assert_eq!(data.indices.len() / 3, 24 * 12 * 2);
+    for (p, n) in data.positions.iter().zip(&data.normals) {
+        // the tube center is major_radius from the axis, in the direction of the vertex
+        let len = (p[0] * p[0] + p[2] * p[2]).sqrt();
+        let center = [2.0 * p[0] / len, 0.0, 2.0 * p[2] / len];
+        let offset = sub(*p, center);
+        assert!((dot(offset, offset).sqrt() - 0.5).abs() < 1e-4);
+        assert!(dot(offset, *n) > 0.0);
+    }
+}
+
+#[test]
+fn plane() {
+    let data = plane_data(4.0, 2.0, 4, 2);
+    check_mesh("plane", &data);
+    assert_eq!(data.positions.len(), 5 * 3);
+    assert_eq!(data.indices.len() / 3, 4 * 2 * 2);
+    assert!(data.positions.iter().all(|p| p[1] == 0.0));
+}
+
+#[test]
+fn capsule() {
+    let data = capsule_data(0.5, 2.0, 16, 4);
+    check_mesh("capsule", &data);
+    let top = data.positions.iter().map(|p| p[1]).fold(f32::MIN, f32::max);
+    let bottom = data.positions.iter().map(|p| p[1]).fold(f32::MAX, f32::min);
+    assert!((top - 1.5).abs() < 1e-5 && (bottom + 1.5).abs() < 1e-5);
+}
+
+#[test]
+fn append_offsets_indices() {
+    let mut data = plane_data(1.0, 1.0, 1, 1);
+    data.append(plane_data(1.0, 1.0, 1, 1));
+    assert_eq!(data.positions.len(), 8);
+    assert_eq!(&data.indices[6..], &[4, 6, 5, 5, 6, 7]);
+}